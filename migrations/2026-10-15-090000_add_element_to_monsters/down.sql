@@ -0,0 +1,2 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE monsters DROP COLUMN element;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE monsters
+ADD COLUMN element varchar NOT NULL DEFAULT 'neutral';
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE battles
+DROP COLUMN winner_remaining_hp,
+DROP COLUMN rounds;
\ No newline at end of file
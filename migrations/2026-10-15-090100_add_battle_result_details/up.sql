@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE battles
+ADD COLUMN winner_remaining_hp integer NOT NULL DEFAULT 0,
+ADD COLUMN rounds integer NOT NULL DEFAULT 0;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE monsters
+ADD COLUMN version integer NOT NULL DEFAULT 1;
\ No newline at end of file
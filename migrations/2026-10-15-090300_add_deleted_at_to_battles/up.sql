@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE battles
+ADD COLUMN deleted_at timestamp;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE monsters
+ADD COLUMN tags jsonb NOT NULL DEFAULT '[]'::jsonb;
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE battles
+ADD COLUMN log jsonb;
\ No newline at end of file
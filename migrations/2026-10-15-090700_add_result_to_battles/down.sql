@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DELETE FROM battles WHERE winner IS NULL;
+ALTER TABLE battles DROP COLUMN result;
+ALTER TABLE battles ALTER COLUMN winner SET NOT NULL;
\ No newline at end of file
@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE battles ALTER COLUMN winner DROP NOT NULL;
+-- every battle stored so far had a winner
+ALTER TABLE battles
+ADD COLUMN result varchar NOT NULL DEFAULT 'win';
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE battles
+ADD COLUMN status varchar NOT NULL DEFAULT 'completed';
\ No newline at end of file
@@ -0,0 +1,3 @@
+-- Your SQL goes here
+ALTER TABLE monsters
+ADD COLUMN created_by varchar;
\ No newline at end of file
@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE monsters
+ADD COLUMN available_from timestamp,
+ADD COLUMN available_to timestamp;
\ No newline at end of file
@@ -0,0 +1,48 @@
+use super::errors::ApiError;
+use crate::config::AppConfig;
+use crate::repository::{admin_repository, database::Database};
+use actix_web::{post, web, HttpRequest, HttpResponse};
+
+#[utoipa::path(
+    responses(
+        (status = 204, description = "Every monster and battle deleted"),
+        (status = 403, description = "ENABLE_ADMIN_RESET is not set", body = String)
+    )
+)]
+#[post("/admin/reset")]
+pub async fn reset_database(
+    req: HttpRequest,
+    db: web::Data<Database>,
+) -> Result<HttpResponse, ApiError> {
+    if !AppConfig::of(&req).admin_reset.enabled() {
+        return Err(ApiError::Forbidden(
+            "Database reset is disabled".to_string(),
+        ));
+    }
+    admin_repository::reset(&db)?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reset_database;
+    use crate::config::AppConfig;
+    use crate::repository::database::Database;
+    use crate::utils::flags::AdminReset;
+    use actix_web::{http::StatusCode, test, web::Data, App};
+
+    #[actix_rt::test]
+    async fn test_should_refuse_a_reset_with_403_error_when_it_is_disabled() {
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .app_data(Data::new(AppConfig {
+                admin_reset: AdminReset::new(false),
+                ..AppConfig::default()
+            }))
+            .service(reset_database);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post().uri("/admin/reset").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -1,236 +1,1899 @@
+use super::errors::ApiError;
+use super::json::Json;
+use super::monster_apis::{
+    check_pagination, insert_link_header, parse_monster_id, with_default_page_size,
+};
+use crate::battle_engine::{
+    self, BattleOptions, Formula, Initiative, KnockoutRule, RoundLog, DEFAULT_MIN_DAMAGE,
+};
+use crate::config::AppConfig;
+use crate::models::battle::{
+    Battle, BattleDetail, BattleFilter, BattleMonsters, BattleSort, ExplainedBattle,
+    LeaderboardEntry,
+};
+use crate::models::monster::{Monster, MonsterId};
+use crate::models::pagination::Pagination;
 use crate::repository::battle_repository;
+use crate::repository::database::{Database, DbResult};
 use crate::repository::monster_repository;
-use crate::{models::battle::Battle, repository::database::Database};
-use actix_web::{delete, get, post, web, HttpResponse};
+use crate::utils::leaderboard_cache::{LeaderboardCache, CACHE_HEADER};
+use crate::utils::metrics::Metrics;
+use actix_web::{delete, get, http::header, post, rt, web, HttpRequest, HttpResponse};
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
+use validator::Validate;
 
-#[derive(Serialize, Deserialize)]
+const MAX_BATCH_BATTLES: usize = 100;
+
+#[derive(Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateBattleRequest {
-    monster_a: Option<String>,
-    monster_b: Option<String>,
+    #[schema(value_type = Option<String>, format = Uuid)]
+    monster_a: Option<MonsterId>,
+    #[schema(value_type = Option<String>, format = Uuid)]
+    monster_b: Option<MonsterId>,
+    #[validate(range(min = 1))]
+    min_damage: Option<i32>,
+    /// Damage formula, `linear` when omitted.
+    formula: Option<Formula>,
+    /// Rule for a round that knocks out both monsters, `first_attacker_wins` when omitted.
+    knockout_rule: Option<KnockoutRule>,
+    /// Who strikes first, `speed` when omitted; `weighted` draws it in proportion to speed.
+    initiative: Option<Initiative>,
 }
 
-#[get("/battles")]
-pub async fn get_battles(db: web::Data<Database>) -> HttpResponse {
-    let battles = battle_repository::get_battles(&db);
-    HttpResponse::Ok().json(battles)
+impl CreateBattleRequest {
+    /// Validates the request and loads both monsters.
+    fn combatants(&self, db: &Database) -> Result<(Monster, Monster), ApiError> {
+        if self.validate().is_err() {
+            return Err(ApiError::BadRequest(
+                "min_damage must be at least 1".to_string(),
+            ));
+        }
+        match (self.monster_a, self.monster_b) {
+            (Some(monster_a), Some(monster_b)) => {
+                let combatants = load_combatants(db, monster_a, monster_b)?;
+                check_availability(&combatants)?;
+                Ok(combatants)
+            }
+            (None, Some(_)) => Err(ApiError::BadRequest("monster_a is required".to_string())),
+            (Some(_), None) => Err(ApiError::BadRequest("monster_b is required".to_string())),
+            (None, None) => Err(ApiError::BadRequest(
+                "monster_a and monster_b are required".to_string(),
+            )),
+        }
+    }
+
+    fn options(&self, config: Option<&AppConfig>) -> BattleOptions {
+        BattleOptions {
+            min_damage: self.min_damage.unwrap_or(DEFAULT_MIN_DAMAGE),
+            formula: self.formula.unwrap_or_default(),
+            knockout_rule: self.knockout_rule.unwrap_or_default(),
+            initiative: self.initiative.unwrap_or_default(),
+            ..BattleOptions::from_config(config)
+        }
+    }
 }
 
-#[post("/battles")]
-pub async fn create_battle(
-    db: web::Data<Database>,
-    mut new_battle: web::Json<Battle>,
-) -> HttpResponse {
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchBattleFailure {
+    pub index: usize,
+    pub monster_a: Option<String>,
+    pub monster_b: Option<String>,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchBattleResponse {
+    pub battles: Vec<Battle>,
+    pub failures: Vec<BatchBattleFailure>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BattleQuery {
+    expand: Option<String>,
+}
+
+pub fn parse_battle_id(id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|_| ApiError::BadRequest("Invalid battle id".to_string()))
+}
+
+/// Validates both ids and loads the two monsters that are about to fight.
+pub(crate) fn resolve_combatants(
+    db: &Database,
+    monster_a: &str,
+    monster_b: &str,
+) -> Result<(Monster, Monster), ApiError> {
     //validate formats
-    if !Uuid::parse_str(&new_battle.monster_a).is_ok() {
-        return HttpResponse::NotFound().json("Monster a not found");
+    let monster_a = MonsterId(parse_monster_id(monster_a)?);
+    let monster_b = MonsterId(parse_monster_id(monster_b)?);
+    load_combatants(db, monster_a, monster_b)
+}
+
+/// Refuses a battle when either monster is outside its availability window right now.
+fn check_availability((monster_a, monster_b): &(Monster, Monster)) -> Result<(), ApiError> {
+    let now = Utc::now().naive_utc();
+    match [monster_a, monster_b]
+        .into_iter()
+        .find(|monster| !monster.is_available_at(now))
+    {
+        Some(monster) => Err(ApiError::Conflict(format!(
+            "{} is outside its availability window",
+            monster.name
+        ))),
+        None => Ok(()),
     }
-    if !Uuid::parse_str(&new_battle.monster_b).is_ok() {
-        return HttpResponse::NotFound().json("Monster b not found");
+}
+
+/// Loads the two monsters that are about to fight.
+pub(crate) fn load_combatants(
+    db: &Database,
+    monster_a: MonsterId,
+    monster_b: MonsterId,
+) -> Result<(Monster, Monster), ApiError> {
+    let monster_a = monster_a.to_string();
+    let monster_b = monster_b.to_string();
+    if monster_a == monster_b {
+        return Err(ApiError::BadRequest(
+            "A monster cannot battle itself.".to_string(),
+        ));
     }
     //validate if exist
-    let monster_a = match monster_repository::get_monster_by_id(&db, &new_battle.monster_a) {
-        Some(m) => m,
-        None => return HttpResponse::NotFound().json("Monster a not found"),
+    let monster_a = monster_repository::get_monster_by_id(db, &monster_a)?
+        .ok_or_else(|| ApiError::NotFound("Monster a not found".to_string()))?;
+    let monster_b = monster_repository::get_monster_by_id(db, &monster_b)?
+        .ok_or_else(|| ApiError::NotFound("Monster b not found".to_string()))?;
+    Ok((monster_a, monster_b))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BattleRangeQuery {
+    /// ISO 8601 timestamp or date, inclusive.
+    from: Option<String>,
+    /// ISO 8601 timestamp or date, inclusive.
+    to: Option<String>,
+    /// `rounds` puts the longest fights first, `margin` the closest wins (lowest remaining
+    /// winner hp) with battles that have no winner last; defaults to `created_at`, newest first.
+    sort_by: Option<String>,
+}
+
+/// Accepts RFC 3339 (converted to UTC), a naive `YYYY-MM-DDTHH:MM:SS[.f]` or a bare date.
+pub fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|timestamp| timestamp.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
+fn parse_bound(name: &str, value: Option<&str>) -> Result<Option<NaiveDateTime>, ApiError> {
+    value
+        .map(|value| {
+            parse_timestamp(value)
+                .ok_or_else(|| ApiError::BadRequest(format!("Invalid {} date", name)))
+        })
+        .transpose()
+}
+
+#[utoipa::path(
+    params(BattleRangeQuery, Pagination),
+    responses(
+        (status = 200, description = "One page of the battles created within the range, with a `Link` header", body = [Battle]),
+        (status = 400, description = "Unparseable or inverted date range, unknown sort_by or invalid pagination", body = String)
+    )
+)]
+#[get("/battles")]
+pub async fn get_battles(
+    req: HttpRequest,
+    db: web::Data<Database>,
+    range: web::Query<BattleRangeQuery>,
+    pagination: web::Query<Pagination>,
+) -> Result<HttpResponse, ApiError> {
+    let pagination = with_default_page_size(&req, pagination.into_inner());
+    check_pagination(&pagination)?;
+    let sort = match range.sort_by.as_deref() {
+        None => BattleSort::default(),
+        Some(sort_by) => BattleSort::parse(sort_by)
+            .ok_or_else(|| ApiError::BadRequest("Invalid sort_by value".to_string()))?,
     };
-    let monster_b = match monster_repository::get_monster_by_id(&db, &new_battle.monster_b) {
-        Some(m) => m,
-        None => return HttpResponse::NotFound().json("Monster b not found"),
+    let filter = BattleFilter {
+        from: parse_bound("from", range.from.as_deref())?,
+        to: parse_bound("to", range.to.as_deref())?,
+        sort,
     };
-    //sets turn order
-    let (mut first_monster, mut second_monster) = if monster_a.speed > monster_b.speed {
-        (monster_a, monster_b)
-    } else if monster_a.speed < monster_b.speed {
-        (monster_b, monster_a)
-    } else {
-        if monster_a.attack > monster_b.attack {
-            (monster_a, monster_b)
-        } else {
-            (monster_b, monster_a)
+    if let (Some(from), Some(to)) = (filter.from, filter.to) {
+        if from > to {
+            return Err(ApiError::BadRequest(
+                "from must not be after to".to_string(),
+            ));
         }
+    }
+    let mut response = HttpResponse::Ok();
+    let total = battle_repository::count_battles(&db, &filter)?;
+    insert_link_header(&req, &pagination, total, &mut response);
+    Ok(response.json(battle_repository::get_battles(&db, &filter, &pagination)?))
+}
+
+/// How many monsters `GET /leaderboard` ranks.
+const LEADERBOARD_SIZE: usize = 10;
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Monsters with the most wins over completed battles; `X-Cache` tells whether the cached ranking was served", body = [LeaderboardEntry])
+    )
+)]
+#[get("/leaderboard")]
+pub async fn get_leaderboard(
+    db: web::Data<Database>,
+    cache: web::Data<LeaderboardCache>,
+) -> Result<HttpResponse, ApiError> {
+    let generation = match cache.lookup() {
+        Ok(entries) => {
+            return Ok(HttpResponse::Ok()
+                .insert_header((CACHE_HEADER, "HIT"))
+                .json(entries))
+        }
+        Err(generation) => generation,
     };
+    let entries = battle_repository::get_leaderboard(&db, LEADERBOARD_SIZE)?;
+    cache.store(generation, entries.clone());
+    Ok(HttpResponse::Ok()
+        .insert_header((CACHE_HEADER, "MISS"))
+        .json(entries))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateBattleQuery {
+    /// Queue the battle and simulate it in the background; poll `GET /battles/{id}`.
+    #[serde(rename = "async")]
+    #[param(rename = "async")]
+    run_async: Option<bool>,
+    /// Add a plain-language `explanation` of the fight; ignored for async battles.
+    explain: Option<bool>,
+}
+
+#[utoipa::path(
+    request_body = CreateBattleRequest,
+    params(CreateBattleQuery),
+    responses(
+        (status = 201, description = "Battle fought and saved, with an `explanation` when asked", body = Battle),
+        (status = 202, description = "Battle queued in `pending` status; it ends `completed` or `failed`", body = Battle),
+        (status = 400, description = "Missing or invalid monster ids", body = String),
+        (status = 404, description = "Monster not found", body = String),
+        (status = 409, description = "A monster is outside its availability window", body = String)
+    )
+)]
+#[post("/battles")]
+pub async fn create_battle(
+    db: web::Data<Database>,
+    metrics: web::Data<Metrics>,
+    config: Option<web::Data<AppConfig>>,
+    leaderboard: Option<web::Data<LeaderboardCache>>,
+    query: web::Query<CreateBattleQuery>,
+    request: Json<CreateBattleRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (monster_a, monster_b) = request.combatants(&db)?;
+    if query.run_async.unwrap_or(false) {
+        let battle =
+            battle_repository::create_battle(&db, Battle::pending(&monster_a, &monster_b))?;
+        spawn_simulation(
+            db,
+            metrics,
+            leaderboard,
+            battle.id.clone(),
+            monster_a,
+            monster_b,
+            request.options(config.as_ref().map(web::Data::get_ref)),
+        );
+        return Ok(HttpResponse::Accepted()
+            .insert_header((header::LOCATION, format!("/api/battles/{}", battle.id)))
+            .json(battle));
+    }
     //battle
-    while first_monster.hp > 0 && second_monster.hp > 0 {
-        //first monster attack
-        let mut damage = match first_monster.attack - second_monster.defense {
-            diff if diff <= 0 => 1,
-            diff => diff,
-        };
-        second_monster.hp = second_monster.hp - damage;
-        if second_monster.hp <= 0 {
-            new_battle.winner = first_monster.id.to_string();
-            break;
+    let outcome = battle_engine::simulate(
+        &monster_a,
+        &monster_b,
+        &request.options(config.as_ref().map(web::Data::get_ref)),
+    );
+    let explanation = query
+        .explain
+        .unwrap_or(false)
+        .then(|| battle_engine::narrate(&monster_a, &monster_b, &outcome));
+    // only the combatants come from the client; id and winner are never trusted
+    let new_battle = Battle::fought(&monster_a, &monster_b, outcome);
+    //save battle
+    let battle = battle_repository::create_battle(&db, new_battle)?;
+    metrics.inc_battles_created();
+    metrics.observe_battle_rounds(battle.rounds as u64);
+    if let Some(leaderboard) = leaderboard {
+        leaderboard.invalidate();
+    }
+    let mut response = HttpResponse::Created();
+    response.insert_header((header::LOCATION, format!("/api/battles/{}", battle.id)));
+    Ok(match explanation {
+        Some(explanation) => response.json(ExplainedBattle {
+            battle,
+            explanation,
+        }),
+        None => response.json(battle),
+    })
+}
+
+/// Simulates a pending battle on the blocking pool so slow formulas never hold a worker.
+fn spawn_simulation(
+    db: web::Data<Database>,
+    metrics: web::Data<Metrics>,
+    leaderboard: Option<web::Data<LeaderboardCache>>,
+    battle_id: String,
+    monster_a: Monster,
+    monster_b: Monster,
+    options: BattleOptions,
+) {
+    rt::spawn(finish_queued_battle(
+        db,
+        metrics,
+        leaderboard,
+        battle_id,
+        move |db, battle_id| {
+            let outcome = battle_engine::simulate(&monster_a, &monster_b, &options);
+            let fought = Battle::fought(&monster_a, &monster_b, outcome);
+            battle_repository::complete_battle(db, battle_id, fought)
+        },
+    ));
+}
+
+/// Runs `complete` on the blocking pool; when it errors or panics the battle is marked
+/// `failed`, so clients polling it always reach an end state.
+async fn finish_queued_battle<F>(
+    db: web::Data<Database>,
+    metrics: web::Data<Metrics>,
+    leaderboard: Option<web::Data<LeaderboardCache>>,
+    battle_id: String,
+    complete: F,
+) where
+    F: FnOnce(&Database, &str) -> DbResult<Battle> + Send + 'static,
+{
+    let completed = {
+        let db = db.clone();
+        let battle_id = battle_id.clone();
+        web::block(move || complete(&db, &battle_id)).await
+    };
+    match completed {
+        Ok(Ok(battle)) => {
+            metrics.inc_battles_created();
+            metrics.observe_battle_rounds(battle.rounds as u64);
+            if let Some(leaderboard) = leaderboard {
+                leaderboard.invalidate();
+            }
+            return;
         }
-        //second monster attack
-        damage = match second_monster.attack - first_monster.defense {
-            diff if diff <= 0 => 1,
-            diff => diff,
-        };
-        first_monster.hp = first_monster.hp - damage;
-        if first_monster.hp <= 0 {
-            new_battle.winner = second_monster.id.to_string();
-            break;
+        Ok(Err(err)) => log::error!("Completing queued battle {} failed: {}", battle_id, err),
+        Err(err) => log::error!("Simulating queued battle {} failed: {}", battle_id, err),
+    }
+    match web::block(move || battle_repository::fail_battle(&db, &battle_id)).await {
+        Ok(Ok(_)) => {}
+        Ok(Err(err)) => log::error!("Marking a queued battle as failed did not work: {}", err),
+        Err(err) => log::error!("Marking a queued battle as failed did not work: {}", err),
+    }
+}
+
+#[utoipa::path(
+    request_body = Vec<CreateBattleRequest>,
+    responses(
+        (status = 201, description = "Battles created and per-entry failures", body = BatchBattleResponse),
+        (status = 400, description = "Batch too large", body = String)
+    )
+)]
+#[post("/battles/batch")]
+pub async fn create_battles(
+    db: web::Data<Database>,
+    metrics: web::Data<Metrics>,
+    config: Option<web::Data<AppConfig>>,
+    leaderboard: Option<web::Data<LeaderboardCache>>,
+    requests: Json<Vec<CreateBattleRequest>>,
+) -> Result<HttpResponse, ApiError> {
+    if requests.len() > MAX_BATCH_BATTLES {
+        return Err(ApiError::BadRequest(format!(
+            "A batch cannot contain more than {} battles",
+            MAX_BATCH_BATTLES
+        )));
+    }
+    let mut new_battles = Vec::new();
+    let mut failures = Vec::new();
+    for (index, request) in requests.into_inner().into_iter().enumerate() {
+        match request.combatants(&db) {
+            Ok((monster_a, monster_b)) => {
+                let outcome = battle_engine::simulate(
+                    &monster_a,
+                    &monster_b,
+                    &request.options(config.as_ref().map(web::Data::get_ref)),
+                );
+                new_battles.push(Battle::fought(&monster_a, &monster_b, outcome));
+            }
+            Err(err) => failures.push(BatchBattleFailure {
+                index,
+                monster_a: request.monster_a.map(|id| id.to_string()),
+                monster_b: request.monster_b.map(|id| id.to_string()),
+                error: err.to_string(),
+            }),
         }
     }
-    //save battle
-    let battle = battle_repository::create_battle(&db, new_battle.into_inner());
-    match battle {
-        Ok(battle) => HttpResponse::Created().json(battle),
-        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    let battles = if new_battles.is_empty() {
+        Vec::new()
+    } else {
+        battle_repository::create_battles(&db, new_battles)?
+    };
+    for battle in &battles {
+        metrics.inc_battles_created();
+        metrics.observe_battle_rounds(battle.rounds as u64);
+    }
+    if let Some(leaderboard) = leaderboard {
+        leaderboard.invalidate();
     }
+    Ok(HttpResponse::Created().json(BatchBattleResponse { battles, failures }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PreviewQuery {
+    monster_a: Option<String>,
+    monster_b: Option<String>,
+    /// Add a plain-language `explanation` of the predicted fight.
+    explain: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BattlePreview {
+    /// `None` when the battle would end in a draw.
+    pub winner: Option<String>,
+    pub rounds: i32,
+    pub winner_remaining_hp: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<String>,
+}
+
+#[utoipa::path(
+    params(PreviewQuery),
+    responses(
+        (status = 200, description = "Predicted outcome, nothing is saved", body = BattlePreview),
+        (status = 400, description = "Missing or invalid monster ids", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[get("/battles/preview")]
+pub async fn preview_battle(
+    db: web::Data<Database>,
+    config: Option<web::Data<AppConfig>>,
+    query: web::Query<PreviewQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (monster_a, monster_b) = match (&query.monster_a, &query.monster_b) {
+        (Some(monster_a), Some(monster_b)) => resolve_combatants(&db, monster_a, monster_b)?,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "monster_a and monster_b are required".to_string(),
+            ))
+        }
+    };
+    let outcome = battle_engine::simulate(
+        &monster_a,
+        &monster_b,
+        &BattleOptions::from_config(config.as_ref().map(web::Data::get_ref)),
+    );
+    let explanation = query
+        .explain
+        .unwrap_or(false)
+        .then(|| battle_engine::narrate(&monster_a, &monster_b, &outcome));
+    Ok(HttpResponse::Ok().json(BattlePreview {
+        explanation,
+        winner: outcome.winner,
+        rounds: outcome.rounds,
+        winner_remaining_hp: outcome.winner_remaining_hp,
+    }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct QuickBattleQuery {
+    monster_a: Option<String>,
+    monster_b: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct QuickBattleResult {
+    /// `None` when the battle would end in a draw.
+    pub winner_id: Option<String>,
+    pub winner_name: Option<String>,
+}
+
+#[utoipa::path(
+    params(QuickBattleQuery),
+    responses(
+        (status = 200, description = "Predicted winner only, nothing is saved", body = QuickBattleResult),
+        (status = 400, description = "Missing or invalid monster ids", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[get("/battles/quick")]
+pub async fn quick_battle(
+    db: web::Data<Database>,
+    config: Option<web::Data<AppConfig>>,
+    query: web::Query<QuickBattleQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let (monster_a, monster_b) = match (&query.monster_a, &query.monster_b) {
+        (Some(monster_a), Some(monster_b)) => resolve_combatants(&db, monster_a, monster_b)?,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "monster_a and monster_b are required".to_string(),
+            ))
+        }
+    };
+    let options = BattleOptions::from_config(config.as_ref().map(web::Data::get_ref));
+    let winner = battle_engine::simulate_result(&monster_a, &monster_b, &options)
+        .winner
+        .and_then(|winner| {
+            [monster_a, monster_b]
+                .into_iter()
+                .find(|monster| monster.id == winner)
+        });
+    Ok(HttpResponse::Ok().json(QuickBattleResult {
+        winner_id: winner.as_ref().map(|winner| winner.id.clone()),
+        winner_name: winner.map(|winner| winner.name),
+    }))
+}
+
+const DEFAULT_ODDS_TRIALS: u32 = 1000;
+const MAX_ODDS_TRIALS: u32 = 10000;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OddsQuery {
+    monster_a: Option<String>,
+    monster_b: Option<String>,
+    /// Simulations to run, 1000 when omitted and at most 10000.
+    trials: Option<u32>,
+    /// Who strikes first, `speed` when omitted.
+    initiative: Option<Initiative>,
+}
+
+/// Share of simulated battles each side won, plus the draws; the three add up to 1.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BattleOdds {
+    pub monster_a: String,
+    pub monster_b: String,
+    pub trials: u32,
+    pub monster_a_win_rate: f64,
+    pub monster_b_win_rate: f64,
+    pub draw_rate: f64,
+}
+
+#[utoipa::path(
+    params(OddsQuery),
+    responses(
+        (status = 200, description = "Win and draw rates over many seeded simulations, nothing is saved", body = BattleOdds),
+        (status = 400, description = "Missing or invalid monster ids, or trials out of range", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[get("/battles/odds")]
+pub async fn battle_odds(
+    db: web::Data<Database>,
+    config: Option<web::Data<AppConfig>>,
+    query: web::Query<OddsQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let trials = query.trials.unwrap_or(DEFAULT_ODDS_TRIALS);
+    if trials == 0 || trials > MAX_ODDS_TRIALS {
+        return Err(ApiError::BadRequest(format!(
+            "trials must be between 1 and {}",
+            MAX_ODDS_TRIALS
+        )));
+    }
+    let (monster_a, monster_b) = match (&query.monster_a, &query.monster_b) {
+        (Some(monster_a), Some(monster_b)) => resolve_combatants(&db, monster_a, monster_b)?,
+        _ => {
+            return Err(ApiError::BadRequest(
+                "monster_a and monster_b are required".to_string(),
+            ))
+        }
+    };
+    let options = BattleOptions {
+        initiative: query.initiative.unwrap_or_default(),
+        ..BattleOptions::from_config(config.as_ref().map(web::Data::get_ref))
+    };
+    // thousands of simulations are too slow for a worker thread
+    let (monster_a, monster_b, tally) = web::block(move || {
+        let tally = battle_engine::odds(&monster_a, &monster_b, &options, trials);
+        (monster_a, monster_b, tally)
+    })
+    .await
+    .map_err(|err| ApiError::InternalServerError(err.to_string()))?;
+    let rate = |count: u32| count as f64 / trials as f64;
+    Ok(HttpResponse::Ok().json(BattleOdds {
+        monster_a: monster_a.id,
+        monster_b: monster_b.id,
+        trials,
+        monster_a_win_rate: rate(tally.monster_a_wins),
+        monster_b_win_rate: rate(tally.monster_b_wins),
+        draw_rate: rate(tally.draws),
+    }))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ReplayCombatant {
+    pub id: String,
+    pub starting_hp: i32,
+}
+
+/// A stored battle laid out for animation: both sides at full health, then every attack.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BattleReplay {
+    pub battle_id: String,
+    pub monster_a: ReplayCombatant,
+    pub monster_b: ReplayCombatant,
+    pub winner: Option<String>,
+    pub rounds: Vec<RoundLog>,
+}
+
+#[utoipa::path(
+    params(("id" = String, Path, description = "Battle id")),
+    responses(
+        (status = 200, description = "Round-by-round log of the battle", body = BattleReplay),
+        (status = 400, description = "Invalid battle id", body = String),
+        (status = 404, description = "Battle not found", body = String),
+        (status = 409, description = "Battle was fought before logs were stored", body = String)
+    )
+)]
+#[get("/battles/{id}/replay")]
+pub async fn replay_battle(
+    db: web::Data<Database>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_battle_id(&id)?;
+    let battle = battle_repository::get_battle_by_id(&db, &id.to_string())?
+        .ok_or_else(|| ApiError::NotFound("Battle not found".to_string()))?;
+    let log = battle
+        .log
+        .ok_or_else(|| ApiError::Conflict("This battle has no replay".to_string()))?;
+    Ok(HttpResponse::Ok().json(BattleReplay {
+        battle_id: battle.id,
+        monster_a: ReplayCombatant {
+            id: battle.monster_a,
+            starting_hp: log.monster_a_hp,
+        },
+        monster_b: ReplayCombatant {
+            id: battle.monster_b,
+            starting_hp: log.monster_b_hp,
+        },
+        winner: battle.winner,
+        rounds: log.rounds,
+    }))
 }
 
+#[utoipa::path(
+    params(("id" = String, Path, description = "Battle id"), BattleQuery),
+    responses(
+        (status = 200, description = "Battle found, with monsters when expanded", body = BattleDetail),
+        (status = 400, description = "Invalid battle id or expand value", body = String),
+        (status = 404, description = "Battle not found", body = String)
+    )
+)]
 #[get("/battles/{id}")]
-pub async fn get_battle_by_id(db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
-    if !Uuid::parse_str(&id).is_ok() {
-        return HttpResponse::NotFound().json("Battle not found");
+pub async fn get_battle_by_id(
+    db: web::Data<Database>,
+    id: web::Path<String>,
+    query: web::Query<BattleQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_battle_id(&id)?;
+    let expand_monsters = match query.expand.as_deref() {
+        None => false,
+        Some("monsters") => true,
+        Some(_) => return Err(ApiError::BadRequest("Invalid expand value".to_string())),
+    };
+    let battle = battle_repository::get_battle_by_id(&db, &id.to_string())?
+        .ok_or_else(|| ApiError::NotFound("Battle not found".to_string()))?;
+    if !expand_monsters {
+        return Ok(HttpResponse::Ok().json(battle));
     }
-    let battle = battle_repository::get_battle_by_id(&db, &id);
-    match battle {
-        Some(battle) => HttpResponse::Ok().json(battle),
-        None => HttpResponse::NotFound().json("Battle not found"),
+    let monster_a = monster_repository::get_monster_by_id(&db, &battle.monster_a)?;
+    let monster_b = monster_repository::get_monster_by_id(&db, &battle.monster_b)?;
+    let winner = [&monster_a, &monster_b]
+        .into_iter()
+        .flatten()
+        .find(|monster| battle.winner.as_ref() == Some(&monster.id))
+        .cloned();
+    Ok(HttpResponse::Ok().json(BattleDetail {
+        battle,
+        monsters: BattleMonsters {
+            monster_a,
+            monster_b,
+            winner,
+        },
+    }))
+}
+
+#[utoipa::path(
+    params(("id" = String, Path, description = "Battle id")),
+    responses(
+        (status = 201, description = "Rematch fought and saved", body = Battle),
+        (status = 400, description = "Invalid battle id", body = String),
+        (status = 404, description = "Battle or monster not found", body = String),
+        (status = 409, description = "A monster is outside its availability window", body = String)
+    )
+)]
+#[post("/battles/{id}/rematch")]
+pub async fn rematch_battle(
+    db: web::Data<Database>,
+    metrics: web::Data<Metrics>,
+    config: Option<web::Data<AppConfig>>,
+    leaderboard: Option<web::Data<LeaderboardCache>>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_battle_id(&id)?;
+    let original = battle_repository::get_battle_by_id(&db, &id.to_string())?
+        .ok_or_else(|| ApiError::NotFound("Battle not found".to_string()))?;
+    //refetch so the rematch uses the current stats
+    let combatants = resolve_combatants(&db, &original.monster_a, &original.monster_b)?;
+    check_availability(&combatants)?;
+    let (monster_a, monster_b) = combatants;
+    let outcome = battle_engine::simulate(
+        &monster_a,
+        &monster_b,
+        &BattleOptions::from_config(config.as_ref().map(web::Data::get_ref)),
+    );
+    let new_battle = Battle::fought(&monster_a, &monster_b, outcome);
+    let battle = battle_repository::create_battle(&db, new_battle)?;
+    metrics.inc_battles_created();
+    metrics.observe_battle_rounds(battle.rounds as u64);
+    if let Some(leaderboard) = leaderboard {
+        leaderboard.invalidate();
     }
+    Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, format!("/api/battles/{}", battle.id)))
+        .json(battle))
 }
 
+#[utoipa::path(
+    params(("id" = String, Path, description = "Battle id")),
+    responses(
+        (status = 204, description = "Battle deleted"),
+        (status = 400, description = "Invalid battle id", body = String),
+        (status = 404, description = "Battle not found", body = String)
+    )
+)]
 #[delete("/battles/{id}")]
-pub async fn delete_battle_by_id(db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
-    if !Uuid::parse_str(&id).is_ok() {
-        return HttpResponse::NotFound().json("Battle not found");
-    }
-    let battle = battle_repository::delete_battle_by_id(&db, &id);
-    match battle {
-        Some(_) => HttpResponse::NoContent().finish(),
-        None => HttpResponse::NotFound().json("Battle not found"),
-    }
+pub async fn delete_battle_by_id(
+    db: web::Data<Database>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_battle_id(&id)?;
+    battle_repository::delete_battle_by_id(&db, &id.to_string())?
+        .ok_or_else(|| ApiError::NotFound("Battle not found".to_string()))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    params(("id" = String, Path, description = "Battle id")),
+    responses(
+        (status = 200, description = "Battle restored", body = Battle),
+        (status = 400, description = "Invalid battle id", body = String),
+        (status = 404, description = "Battle not found", body = String)
+    )
+)]
+#[post("/battles/{id}/restore")]
+pub async fn restore_battle(
+    db: web::Data<Database>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_battle_id(&id)?;
+    let battle = battle_repository::restore(&db, &id.to_string())?
+        .ok_or_else(|| ApiError::NotFound("Battle not found".to_string()))?;
+    Ok(HttpResponse::Ok().json(battle))
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{create_battle, delete_battle_by_id, get_battle_by_id, get_battles};
-    use crate::models::battle::Battle;
-    use crate::repository::database::Database;
+    use super::{
+        battle_odds, create_battle, create_battles, delete_battle_by_id, finish_queued_battle,
+        get_battle_by_id, get_battles, get_leaderboard, parse_timestamp, preview_battle,
+        quick_battle, rematch_battle, replay_battle, restore_battle, BatchBattleResponse,
+        BattleOdds, BattlePreview, BattleReplay, QuickBattleResult,
+    };
+    use crate::models::battle::{
+        Battle, BattleDetail, BattleResult, BattleStatus, ExplainedBattle, LeaderboardEntry,
+    };
+    use crate::models::monster::Monster;
+    use crate::models::pagination::Pagination;
+    use crate::repository::battle_repository;
+    use crate::repository::database::{Database, DbError};
+    use crate::repository::monster_repository;
+    use crate::utils::leaderboard_cache::{LeaderboardCache, CACHE_HEADER};
+    use crate::utils::metrics::Metrics;
+    use crate::utils::monster_generator::random_monster;
     use crate::utils::test_utils::{init_test_battle, init_test_monsters};
     use actix_web::{http, test, web::Data, App};
     use serde_json::{self, json};
     use uuid::Uuid;
 
+    #[actix_rt::test]
+    async fn test_should_replay_a_logged_battle_from_full_health() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let unlogged = init_test_battle(&db).await.remove(0);
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle)
+            .service(replay_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/battles")
+            .set_json(json!({
+                "monster_a": test_monsters[0].id,
+                "monster_b": test_monsters[1].id
+            }))
+            .to_request();
+        let battle: Battle = test::call_and_read_body_json(&app, req).await;
+
+        let req = test::TestRequest::get()
+            .uri(format!("/battles/{}/replay", battle.id).as_str())
+            .to_request();
+        let replay: BattleReplay = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(replay.monster_a.starting_hp, test_monsters[0].hp);
+        assert_eq!(replay.monster_b.starting_hp, test_monsters[1].hp);
+        assert_eq!(replay.winner, battle.winner);
+        assert_eq!(
+            replay.rounds.last().map(|entry| entry.round),
+            Some(battle.rounds)
+        );
+        let finishing_blow = replay.rounds.last().unwrap();
+        assert_eq!(Some(finishing_blow.attacker.clone()), battle.winner);
+        assert!(finishing_blow.defender_hp <= 0);
+
+        let req = test::TestRequest::get()
+            .uri(format!("/battles/{}/replay", unlogged.id).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+    }
+
     #[actix_rt::test]
     async fn test_should_get_all_battles_correctly() {
         let db = Database::new();
         let app = App::new().app_data(Data::new(db)).service(get_battles);
 
-        let mut app = test::init_service(app).await;
+        let app = test::init_service(app).await;
 
         let req = test::TestRequest::get().uri("/battles").to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let resp = test::call_service(&app, req).await;
 
         assert!(resp.status().is_success());
     }
 
+    #[actix_rt::test]
+    async fn test_should_filter_battles_by_creation_date_range() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let battle = battle_repository::create_battles(
+            &db,
+            vec![Battle {
+                id: String::new(),
+                monster_a: test_monsters[0].id.clone(),
+                monster_b: test_monsters[1].id.clone(),
+                winner: Some(test_monsters[0].id.clone()),
+                created_at: None,
+                updated_at: None,
+                winner_remaining_hp: 0,
+                rounds: 0,
+                deleted_at: None,
+                log: None,
+                result: BattleResult::Win,
+                status: BattleStatus::Completed,
+            }],
+        )
+        .unwrap()
+        .remove(0);
+        let created_at = battle.created_at.expect("created_at should be set");
+        let format =
+            |timestamp: chrono::NaiveDateTime| timestamp.format("%Y-%m-%dT%H:%M:%S%.f").to_string();
+        let before = format(created_at - chrono::Duration::seconds(1));
+        let after = format(created_at + chrono::Duration::seconds(1));
+        let app = App::new().app_data(Data::new(db)).service(get_battles);
+        let app = test::init_service(app).await;
+
+        for (query, included) in [
+            (format!("from={}&to={}", before, after), true),
+            (format!("from={}", before), true),
+            (format!("to={}", after), true),
+            (format!("from={}", after), false),
+            (format!("to={}", before), false),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(format!("/battles?{}", query).as_str())
+                .to_request();
+            let battles: Vec<Battle> = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(
+                battles.iter().any(|listed| listed.id == battle.id),
+                included,
+                "{}",
+                query
+            );
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_should_sort_battles_by_rounds_or_margin() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let seeded: Vec<Battle> = battle_repository::create_battles(
+            &db,
+            [
+                (2, 1, BattleResult::Win, BattleStatus::Completed),
+                (9, 30, BattleResult::Win, BattleStatus::Completed),
+                (5, 12, BattleResult::Win, BattleStatus::Completed),
+                (7, 0, BattleResult::Draw, BattleStatus::Completed),
+                (0, 0, BattleResult::Win, BattleStatus::Pending),
+            ]
+            .into_iter()
+            .map(|(rounds, winner_remaining_hp, result, status)| Battle {
+                id: String::new(),
+                monster_a: test_monsters[0].id.clone(),
+                monster_b: test_monsters[1].id.clone(),
+                winner: (result == BattleResult::Win && status == BattleStatus::Completed)
+                    .then(|| test_monsters[0].id.clone()),
+                created_at: None,
+                updated_at: None,
+                winner_remaining_hp,
+                rounds,
+                deleted_at: None,
+                log: None,
+                result,
+                status,
+            })
+            .collect(),
+        )
+        .unwrap();
+        // only a page is returned, so leave out the battles stored before these
+        let from = seeded
+            .iter()
+            .filter_map(|battle| battle.created_at)
+            .min()
+            .expect("created_at should be set")
+            .format("%Y-%m-%dT%H:%M:%S%.f");
+        let seeded: Vec<String> = seeded.into_iter().map(|battle| battle.id).collect();
+        let app = App::new().app_data(Data::new(db)).service(get_battles);
+        let app = test::init_service(app).await;
+
+        // battles without a winner tie on margin, so only their place after the wins is fixed
+        for (sort_by, expected, fixed) in [
+            ("rounds", [1, 3, 2, 0, 4], 5),
+            ("margin", [0, 2, 1, 3, 4], 3),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(&format!(
+                    "/battles?sort_by={}&from={}&per_page=100",
+                    sort_by, from
+                ))
+                .to_request();
+            let battles: Vec<Battle> = test::call_and_read_body_json(&app, req).await;
+            let order: Vec<&String> = battles
+                .iter()
+                .map(|battle| &battle.id)
+                .filter(|id| seeded.contains(id))
+                .collect();
+            let expected: Vec<&String> = expected.iter().map(|index| &seeded[*index]).collect();
+            assert_eq!(order[..fixed], expected[..fixed], "{}", sort_by);
+            let mut unranked = order[fixed..].to_vec();
+            unranked.sort();
+            let mut expected_unranked = expected[fixed..].to_vec();
+            expected_unranked.sort();
+            assert_eq!(unranked, expected_unranked, "{}", sort_by);
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/battles?sort_by=loudest")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_link_the_next_page_of_battles_only_when_there_is_one() {
+        let db = Database::new();
+        init_test_battle(&db).await;
+        init_test_battle(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(get_battles);
+        let app = test::init_service(app).await;
+        let link = |resp: actix_web::dev::ServiceResponse| {
+            resp.headers()
+                .get(http::header::LINK)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+                .expect("Link header should be set")
+        };
+
+        let req = test::TestRequest::get()
+            .uri("/battles?page=1&per_page=1")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert!(link(resp).contains("</battles?page=2&per_page=1>; rel=\"next\""));
+
+        let req = test::TestRequest::get()
+            .uri("/battles?from=2999-01-01&per_page=10")
+            .to_request();
+        let links = link(test::call_service(&app, req).await);
+        assert!(!links.contains("rel=\"next\""));
+        assert!(links.contains("</battles?from=2999-01-01&page=1&per_page=10>; rel=\"last\""));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_battles_with_400_error_if_a_date_is_unparseable() {
+        let db = Database::new();
+        let app = App::new().app_data(Data::new(db)).service(get_battles);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/battles?from=yesterday")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_parse_iso_8601_timestamps_and_dates() {
+        let expected = chrono::NaiveDate::from_ymd_opt(2023, 10, 27)
+            .unwrap()
+            .and_hms_opt(14, 30, 0)
+            .unwrap();
+        assert_eq!(parse_timestamp("2023-10-27T14:30:00"), Some(expected));
+        assert_eq!(parse_timestamp("2023-10-27T14:30:00Z"), Some(expected));
+        assert_eq!(parse_timestamp("2023-10-27T16:30:00+02:00"), Some(expected));
+        assert_eq!(
+            parse_timestamp("2023-10-27"),
+            expected.date().and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(parse_timestamp("27/10/2023"), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_preview_a_battle_without_saving_it() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(preview_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(
+                format!(
+                    "/battles/preview?monster_a={}&monster_b={}",
+                    test_monsters[4].id, test_monsters[1].id
+                )
+                .as_str(),
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let preview: BattlePreview = test::read_body_json(resp).await;
+        assert_eq!(preview.winner.as_ref(), Some(&test_monsters[1].id));
+        assert!(preview.rounds > 0);
+        assert!(preview.winner_remaining_hp > 0);
+        let db = Database::new();
+        let related = battle_repository::get_battles_for_monster(
+            &db,
+            &test_monsters[4].id,
+            &Pagination::default(),
+        )
+        .unwrap();
+        assert!(related.is_empty());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_name_only_the_winner_of_a_quick_battle() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(quick_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(
+                format!(
+                    "/battles/quick?monster_a={}&monster_b={}",
+                    test_monsters[4].id, test_monsters[1].id
+                )
+                .as_str(),
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body.as_object().unwrap().len(), 2);
+        let result: QuickBattleResult = serde_json::from_value(body).unwrap();
+        assert_eq!(result.winner_id.as_ref(), Some(&test_monsters[1].id));
+        assert_eq!(result.winner_name.as_deref(), Some("monster-2"));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_explain_a_previewed_battle_only_when_asked() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(preview_battle);
+        let app = test::init_service(app).await;
+        let uri = format!(
+            "/battles/preview?monster_a={}&monster_b={}",
+            test_monsters[4].id, test_monsters[1].id
+        );
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let preview: BattlePreview = test::call_and_read_body_json(&app, req).await;
+        assert!(preview.explanation.is_none());
+        let req = test::TestRequest::get()
+            .uri(&format!("{uri}&explain=true"))
+            .to_request();
+        let preview: BattlePreview = test::call_and_read_body_json(&app, req).await;
+        let explanation = preview.explanation.expect("explanation should be present");
+        assert!(explanation.contains("monster-5"), "{explanation}");
+        assert!(
+            explanation.contains("monster-2 struck first"),
+            "{explanation}"
+        );
+        assert!(explanation.contains("monster-2 won after"), "{explanation}");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_an_explained_battle_naming_the_winner() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/battles?explain=true")
+            .set_json(json!({
+                "monster_a": test_monsters[0].id.clone(),
+                "monster_b": test_monsters[1].id.clone(),
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let body: ExplainedBattle = test::read_body_json(resp).await;
+        let winner = test_monsters
+            .iter()
+            .find(|monster| body.battle.winner.as_ref() == Some(&monster.id))
+            .expect("battle should have a winner");
+        assert!(
+            body.explanation.contains("monster-1"),
+            "{}",
+            body.explanation
+        );
+        assert!(
+            body.explanation.contains("monster-2"),
+            "{}",
+            body.explanation
+        );
+        assert!(
+            body.explanation
+                .contains(&format!("{} won after", winner.name)),
+            "{}",
+            body.explanation
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_give_identical_monsters_even_odds() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        // same stats as monster-1 under a new id, so every battle is a coin flip
+        let twin = monster_repository::create_monster(
+            &db,
+            Monster {
+                id: Uuid::new_v4().to_string(),
+                name: "monster-1 twin".to_string(),
+                ..test_monsters[0].clone()
+            },
+        )
+        .unwrap();
+        let app = App::new().app_data(Data::new(db)).service(battle_odds);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/battles/odds?monster_a={}&monster_b={}&trials=2000",
+                test_monsters[0].id, twin.id
+            ))
+            .to_request();
+        let odds: BattleOdds = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(odds.trials, 2000);
+        assert!(
+            (0.44..=0.56).contains(&odds.monster_a_win_rate),
+            "{}",
+            odds.monster_a_win_rate
+        );
+        assert!(
+            (odds.monster_a_win_rate + odds.monster_b_win_rate + odds.draw_rate - 1.0).abs() < 1e-9
+        );
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/battles/odds?monster_a={}&monster_b={}&trials=10001",
+                test_monsters[0].id, twin.id
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_preview_a_battle_with_404_error_if_a_monster_does_not_exists() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(preview_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(
+                format!(
+                    "/battles/preview?monster_a={}&monster_b={}",
+                    test_monsters[0].id,
+                    Uuid::new_v4()
+                )
+                .as_str(),
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
     #[actix_rt::test]
     async fn test_should_get_404_error_if_battle_does_not_exists() {
-        let app = App::new().service(delete_battle_by_id);
-        let mut app = test::init_service(app).await;
-        let req = test::TestRequest::get().uri("/battles/999999").to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let db = Database::new();
+        let app = App::new().app_data(Data::new(db)).service(get_battle_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/battles/{}", Uuid::new_v4()).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
     }
 
+    #[actix_rt::test]
+    async fn test_should_get_400_error_if_battle_id_is_malformed() {
+        let db = Database::new();
+        let app = App::new().app_data(Data::new(db)).service(get_battle_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/battles/999999").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
     #[actix_rt::test]
     async fn test_should_get_a_single_battle_correctly() {
-        let mut db = Database::new();
-        let test_battles = init_test_battle(&mut db).await;
+        let db = Database::new();
+        let test_battles = init_test_battle(&db).await;
         let app = App::new().app_data(Data::new(db)).service(get_battle_by_id);
-        let mut app = test::init_service(app).await;
+        let app = test::init_service(app).await;
         let req = test::TestRequest::get()
             .uri(format!("/battles/{}", test_battles[0].id).as_str())
             .to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_round_trip_a_drawn_battle() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let drawn = battle_repository::create_battle(
+            &db,
+            Battle {
+                id: String::new(),
+                monster_a: test_monsters[0].id.clone(),
+                monster_b: test_monsters[1].id.clone(),
+                winner: None,
+                created_at: None,
+                updated_at: None,
+                winner_remaining_hp: 0,
+                rounds: 3,
+                deleted_at: None,
+                log: None,
+                result: BattleResult::Draw,
+                status: BattleStatus::Completed,
+            },
+        )
+        .unwrap();
+        let app = App::new().app_data(Data::new(db)).service(get_battle_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/battles/{}", drawn.id).as_str())
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(body["winner"].is_null());
+        assert_eq!(body["result"], "draw");
+        let battle: Battle = serde_json::from_value(body).unwrap();
+        assert_eq!(battle.winner, None);
+        assert_eq!(battle.result, BattleResult::Draw);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_a_battle_with_expanded_monsters() {
+        let db = Database::new();
+        let test_battles = init_test_battle(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(get_battle_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/battles/{}?expand=monsters", test_battles[0].id).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), http::StatusCode::OK);
+        let detail: BattleDetail = test::read_body_json(resp).await;
+        assert_eq!(detail.battle.id, test_battles[0].id);
+        let monster_a = detail
+            .monsters
+            .monster_a
+            .expect("monster_a should be expanded");
+        let monster_b = detail
+            .monsters
+            .monster_b
+            .expect("monster_b should be expanded");
+        let winner = detail.monsters.winner.expect("winner should be expanded");
+        assert_eq!(monster_a.name, "monster-1");
+        assert_eq!(monster_b.name, "monster-2");
+        assert_eq!(Some(winner.id), test_battles[0].winner.clone());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_a_battle_with_400_error_if_expand_is_unknown() {
+        let db = Database::new();
+        let test_battles = init_test_battle(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(get_battle_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/battles/{}?expand=everything", test_battles[0].id).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
     }
 
     #[actix_rt::test]
     async fn test_should_delete_a_battle_correctly() {
-        let mut db = Database::new();
-        let _test_battles = init_test_battle(&mut db).await;
+        let db = Database::new();
+        let _test_battles = init_test_battle(&db).await;
         let app = App::new()
             .app_data(Data::new(db))
             .service(delete_battle_by_id);
-        let mut app = test::init_service(app).await;
+        let app = test::init_service(app).await;
         let req = test::TestRequest::delete()
             .uri(format!("/battles/{}", _test_battles[0].id).as_str())
             .to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
     }
 
+    #[actix_rt::test]
+    async fn test_should_restore_a_deleted_battle() {
+        let db = Database::new();
+        let test_battles = init_test_battle(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_battles)
+            .service(get_battle_by_id)
+            .service(delete_battle_by_id)
+            .service(restore_battle);
+        let app = test::init_service(app).await;
+        let uri = format!("/battles/{}", test_battles[0].id);
+        let listed = |battles: Vec<Battle>| battles.iter().any(|b| b.id == test_battles[0].id);
+
+        let req = test::TestRequest::delete().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+        let req = test::TestRequest::get().uri("/battles").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(!listed(test::read_body_json(resp).await));
+
+        let req = test::TestRequest::post()
+            .uri(&format!("{}/restore", uri))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let restored: Battle = test::read_body_json(resp).await;
+        assert_eq!(restored.id, test_battles[0].id);
+        assert!(restored.deleted_at.is_none());
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let req = test::TestRequest::get().uri("/battles").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert!(listed(test::read_body_json(resp).await));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_restore_a_battle_with_404_error_if_battle_does_not_exists() {
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .service(restore_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri(&format!("/battles/{}/restore", Uuid::new_v4()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_rematch_as_a_new_battle() {
+        let db = Database::new();
+        let test_battles = init_test_battle(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(rematch_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri(format!("/battles/{}/rematch", test_battles[0].id).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let rematch: Battle = test::read_body_json(resp).await;
+        assert_ne!(rematch.id, test_battles[0].id);
+        assert_eq!(rematch.monster_a, test_battles[0].monster_a);
+        assert_eq!(rematch.monster_b, test_battles[0].monster_b);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_rematch_with_404_error_if_battle_does_not_exists() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(rematch_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri(format!("/battles/{}/rematch", Uuid::new_v4()).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
     #[actix_rt::test]
     async fn test_should_create_a_battle_with_404_error_if_one_parameter_has_a_monster_id_does_not_exists(
     ) {
         let db = Database::new();
-        let app = App::new().app_data(Data::new(db)).service(create_battle);
-        let mut app = test::init_service(app).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
         let req = test::TestRequest::post()
             .uri("/battles")
-            .set_json(&json!({
+            .set_json(json!({
                 "monster_a":Uuid::default().to_string(),
-                "monster_b":Uuid::default().to_string()
+                "monster_b":Uuid::new_v4().to_string()
             }))
             .to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
     }
 
+    #[actix_rt::test]
+    async fn test_should_create_a_battle_with_400_error_if_one_parameter_is_malformed() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/battles")
+            .set_json(json!({
+                "monster_a":"999999",
+                "monster_b":Uuid::default().to_string()
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        let message: String = test::read_body_json(resp).await;
+        assert!(message.starts_with("Invalid value for `monster_a`: Invalid monster id"));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_reject_a_batch_with_a_malformed_monster_id_naming_the_entry() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battles);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/battles/batch")
+            .set_json(json!([
+                { "monster_a": test_monsters[0].id, "monster_b": test_monsters[1].id },
+                { "monster_a": test_monsters[2].id, "monster_b": "not-a-uuid" },
+            ]))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        let message: String = test::read_body_json(resp).await;
+        assert!(message.starts_with("Invalid value for `[1].monster_b`: Invalid monster id"));
+    }
+
     #[actix_rt::test]
     async fn test_should_create_a_battle_with_a_bad_request_response_if_one_parameter_is_null() {
         let db = Database::new();
-        let app = App::new().app_data(Data::new(db)).service(create_battle);
-        let mut app = test::init_service(app).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
         let req = test::TestRequest::post()
             .uri("/battles")
-            .set_json(&json!({
+            .set_json(json!({
                 "monster_a":Uuid::default().to_string(),
             }))
             .to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
     }
 
+    async fn post_battle(body: serde_json::Value) -> (http::StatusCode, serde_json::Value) {
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/battles")
+            .set_json(body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        (resp.status(), test::read_body_json(resp).await)
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_battle_with_400_error_if_monster_a_is_missing() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let (status, body) = post_battle(json!({ "monster_b": test_monsters[1].id })).await;
+        assert_eq!(status, http::StatusCode::BAD_REQUEST);
+        assert_eq!(body, "monster_a is required");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_battle_with_400_error_if_monster_b_is_missing() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let (status, body) = post_battle(json!({ "monster_a": test_monsters[0].id })).await;
+        assert_eq!(status, http::StatusCode::BAD_REQUEST);
+        assert_eq!(body, "monster_b is required");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_battle_when_both_monsters_are_present() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let (status, body) = post_battle(json!({
+            "monster_a": test_monsters[0].id,
+            "monster_b": test_monsters[1].id,
+        }))
+        .await;
+        assert_eq!(status, http::StatusCode::CREATED);
+        assert_eq!(body["monster_a"], test_monsters[0].id);
+        assert_eq!(body["monster_b"], test_monsters[1].id);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_battle_with_400_error_if_a_monster_battles_itself() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/battles")
+            .set_json(json!({
+                "monster_a": test_monsters[0].id.clone(),
+                "monster_b": test_monsters[0].id.clone(),
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_battles_in_batch_and_report_failures() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battles);
+        let app = test::init_service(app).await;
+        let missing_id = Uuid::new_v4().to_string();
+        let req = test::TestRequest::post()
+            .uri("/battles/batch")
+            .set_json(json!([
+                { "monster_a": test_monsters[0].id, "monster_b": test_monsters[1].id },
+                { "monster_a": test_monsters[2].id, "monster_b": missing_id },
+                { "monster_a": test_monsters[3].id, "monster_b": test_monsters[4].id },
+                { "monster_a": test_monsters[5].id },
+                { "monster_a": test_monsters[5].id, "monster_b": test_monsters[6].id, "min_damage": 0 },
+            ]))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let body: BatchBattleResponse = test::read_body_json(resp).await;
+        assert_eq!(body.battles.len(), 2);
+        assert_eq!(body.battles[0].monster_a, test_monsters[0].id);
+        assert_eq!(body.battles[1].monster_a, test_monsters[3].id);
+        assert_eq!(body.failures.len(), 3);
+        assert_eq!(body.failures[0].index, 1);
+        assert_eq!(body.failures[0].monster_b, Some(missing_id));
+        assert_eq!(body.failures[1].index, 3);
+        assert_eq!(body.failures[2].index, 4);
+        assert_eq!(body.failures[2].error, "min_damage must be at least 1");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_battle_with_a_location_header() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/battles")
+            .set_json(json!({
+                "monster_a": test_monsters[0].id.clone(),
+                "monster_b": test_monsters[1].id.clone(),
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let location = resp
+            .headers()
+            .get(http::header::LOCATION)
+            .expect("response should carry a Location header")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let battle: Battle = test::read_body_json(resp).await;
+        assert_eq!(location, format!("/api/battles/{}", battle.id));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_serve_the_leaderboard_from_cache_until_a_battle_is_created() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(LeaderboardCache::default()))
+            .service(get_leaderboard)
+            .service(create_battle);
+        let app = test::init_service(app).await;
+        let leaderboard_request = || test::TestRequest::get().uri("/leaderboard").to_request();
+
+        let first = test::call_service(&app, leaderboard_request()).await;
+        assert_eq!(first.status(), http::StatusCode::OK);
+        assert_eq!(first.headers().get(CACHE_HEADER).unwrap(), "MISS");
+        let first: Vec<LeaderboardEntry> = test::read_body_json(first).await;
+
+        let second = test::call_service(&app, leaderboard_request()).await;
+        assert_eq!(second.status(), http::StatusCode::OK);
+        assert_eq!(second.headers().get(CACHE_HEADER).unwrap(), "HIT");
+        let second: Vec<LeaderboardEntry> = test::read_body_json(second).await;
+        assert_eq!(first, second);
+
+        let req = test::TestRequest::post()
+            .uri("/battles")
+            .set_json(json!({
+                "monster_a": test_monsters[0].id.clone(),
+                "monster_b": test_monsters[1].id.clone(),
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+
+        let after_battle = test::call_service(&app, leaderboard_request()).await;
+        assert_eq!(after_battle.headers().get(CACHE_HEADER).unwrap(), "MISS");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_complete_an_async_battle_in_the_background() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle)
+            .service(get_battle_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/battles?async=true")
+            .set_json(json!({
+                "monster_a": test_monsters[0].id.clone(),
+                "monster_b": test_monsters[1].id.clone(),
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::ACCEPTED);
+        let pending: Battle = test::read_body_json(resp).await;
+        assert_eq!(pending.status, BattleStatus::Pending);
+
+        let mut battle = pending;
+        for _ in 0..50 {
+            let req = test::TestRequest::get()
+                .uri(format!("/battles/{}", battle.id).as_str())
+                .to_request();
+            battle = test::call_and_read_body_json(&app, req).await;
+            if battle.status == BattleStatus::Completed {
+                break;
+            }
+            actix_rt::time::sleep(std::time::Duration::from_millis(100)).await;
+        }
+        assert_eq!(battle.status, BattleStatus::Completed);
+        assert_eq!(battle.winner.as_ref(), Some(&test_monsters[1].id));
+        assert!(battle.rounds > 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_mark_a_queued_battle_failed_when_it_cannot_complete() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let db = Data::new(db);
+        let metrics = Data::new(Metrics::default());
+        let queue = || {
+            battle_repository::create_battle(
+                &db,
+                Battle::pending(&test_monsters[0], &test_monsters[1]),
+            )
+            .unwrap()
+        };
+
+        let erroring = queue();
+        finish_queued_battle(
+            db.clone(),
+            metrics.clone(),
+            None,
+            erroring.id.clone(),
+            |_, _| Err(DbError::Query(diesel::result::Error::NotFound)),
+        )
+        .await;
+        let panicking = queue();
+        finish_queued_battle(
+            db.clone(),
+            metrics.clone(),
+            None,
+            panicking.id.clone(),
+            |_, _| panic!("simulation blew up"),
+        )
+        .await;
+
+        for queued in [erroring, panicking] {
+            let battle = battle_repository::get_battle_by_id(&db, &queued.id)
+                .unwrap()
+                .unwrap();
+            assert_eq!(battle.status, BattleStatus::Failed);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_battle_ignoring_a_client_supplied_winner_and_id() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
+        let tampered_id = Uuid::new_v4().to_string();
+        let req = test::TestRequest::post()
+            .uri("/battles")
+            .set_json(json!({
+                "id": tampered_id,
+                "monster_a": test_monsters[4].id.clone(),
+                "monster_b": test_monsters[1].id.clone(),
+                "winner": test_monsters[4].id.clone(),
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let battle: Battle = test::read_body_json(resp).await;
+        assert_ne!(battle.id, tampered_id);
+        assert_eq!(battle.winner.as_ref(), Some(&test_monsters[1].id));
+        let db = Database::new();
+        let persisted = battle_repository::get_battle_by_id(&db, &battle.id)
+            .unwrap()
+            .expect("battle should be persisted");
+        assert_eq!(persisted.winner.as_ref(), Some(&test_monsters[1].id));
+        assert!(battle_repository::get_battle_by_id(&db, &tampered_id)
+            .unwrap()
+            .is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_battle_with_409_error_if_a_monster_is_out_of_its_window() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let now = chrono::Utc::now().naive_utc();
+        let seasonal = monster_repository::create_monster(
+            &db,
+            Monster {
+                available_from: Some(now - chrono::Duration::days(30)),
+                available_to: Some(now - chrono::Duration::days(1)),
+                ..random_monster(&mut rand::thread_rng())
+            },
+        )
+        .unwrap();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/battles")
+            .set_json(json!({
+                "monster_a": test_monsters[0].id,
+                "monster_b": seasonal.id,
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+        let message: String = test::read_body_json(resp).await;
+        assert!(message.contains("availability window"));
+    }
+
     #[actix_rt::test]
     async fn test_should_create_battle_correctly_with_monster_a_winning() {
-        let mut db = Database::new();
-        let test_battles = init_test_battle(&mut db).await;
-        let app = App::new().app_data(Data::new(db)).service(create_battle);
-        let mut app = test::init_service(app).await;
+        let db = Database::new();
+        let test_battles = init_test_battle(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
         let req = test::TestRequest::post()
             .uri("/battles")
-            .set_json(&json!({
+            .set_json(json!({
                 "monster_a": test_battles[0].monster_a.clone(),
                 "monster_b": test_battles[0].monster_b.clone(),
             }))
             .to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let resp = test::call_service(&app, req).await;
         let battle_response: Battle = serde_json::from_slice(&test::read_body(resp).await)
             .expect("Failed to deserialize JSON");
-        assert_eq!(battle_response.winner, test_battles[0].monster_b);
+        assert_eq!(
+            battle_response.winner.as_ref(),
+            Some(&test_battles[0].monster_b)
+        );
     }
 
     #[actix_rt::test]
     async fn test_should_create_battle_correctly_with_monster_b_winning_if_theirs_speeds_same_and_monster_b_has_higher_attack(
     ) {
-        let mut db = Database::new();
-        let test_monsters = init_test_monsters(&mut db).await;
-        let app = App::new().app_data(Data::new(db)).service(create_battle);
-        let mut app = test::init_service(app).await;
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_battle);
+        let app = test::init_service(app).await;
         let req = test::TestRequest::post()
             .uri("/battles")
-            .set_json(&json!({
+            .set_json(json!({
                 "monster_a": test_monsters[4].id.clone(),
                 "monster_b": test_monsters[1].id.clone(),
             }))
             .to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let resp = test::call_service(&app, req).await;
         let battle_response: Battle = serde_json::from_slice(&test::read_body(resp).await)
             .expect("Failed to deserialize JSON");
         debug_assert!(
@@ -240,6 +1903,6 @@ mod tests {
             test_monsters[4],
             test_monsters[1]
         );
-        assert_eq!(battle_response.winner, test_monsters[1].id);
+        assert_eq!(battle_response.winner.as_ref(), Some(&test_monsters[1].id));
     }
 }
@@ -0,0 +1,137 @@
+use super::battle_apis::resolve_combatants;
+use crate::battle_engine::{self, BattleOptions};
+use crate::config::AppConfig;
+use crate::repository::database::Database;
+use actix_web::{get, rt, web, Error, HttpRequest, HttpResponse};
+use actix_ws::{Message, Session};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+
+/// Pause between streamed attacks so clients can animate them.
+const ROUND_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Deserialize)]
+struct BattleWsRequest {
+    monster_a: String,
+    monster_b: String,
+}
+
+/// Waits for `{monster_a, monster_b}`, then streams every attack and a final `{winner}`.
+/// Nothing is persisted; this is a replay feed for animations.
+#[get("/ws/battle")]
+pub async fn battle_ws(
+    req: HttpRequest,
+    body: web::Payload,
+    db: web::Data<Database>,
+    config: Option<web::Data<AppConfig>>,
+) -> Result<HttpResponse, Error> {
+    let options = BattleOptions::from_config(config.as_ref().map(web::Data::get_ref));
+    let (response, session, mut messages) = actix_ws::handle(&req, body)?;
+    rt::spawn(async move {
+        while let Some(Ok(message)) = messages.recv().await {
+            match message {
+                Message::Text(text) => {
+                    stream_battle(&db, session, &text, &options).await;
+                    return;
+                }
+                Message::Close(_) => return,
+                _ => {}
+            }
+        }
+    });
+    Ok(response)
+}
+
+async fn stream_battle(db: &Database, mut session: Session, text: &str, options: &BattleOptions) {
+    let result = serde_json::from_str::<BattleWsRequest>(text)
+        .map_err(|_| "Expected monster_a and monster_b".to_string())
+        .and_then(|request| {
+            resolve_combatants(db, &request.monster_a, &request.monster_b)
+                .map_err(|err| err.to_string())
+        });
+    let (monster_a, monster_b) = match result {
+        Ok(combatants) => combatants,
+        Err(message) => {
+            let _ = session.text(json!({ "error": message }).to_string()).await;
+            let _ = session.close(None).await;
+            return;
+        }
+    };
+    let outcome = battle_engine::simulate(&monster_a, &monster_b, options);
+    for entry in &outcome.log {
+        let message = serde_json::to_string(entry).expect("Error serializing round");
+        if session.text(message).await.is_err() {
+            return;
+        }
+        rt::time::sleep(ROUND_DELAY).await;
+    }
+    let _ = session
+        .text(json!({ "winner": outcome.winner }).to_string())
+        .await;
+    let _ = session.close(None).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::battle_ws;
+    use crate::repository::database::Database;
+    use crate::utils::test_utils::init_test_monsters;
+    use actix_web::{web::Data, App};
+    use awc::ws::{Frame, Message};
+    use futures::{SinkExt, StreamExt};
+    use serde_json::{json, Value};
+
+    async fn collect_messages(request: Value) -> Vec<Value> {
+        let mut server = actix_test::start(|| {
+            App::new()
+                .app_data(Data::new(Database::new()))
+                .service(battle_ws)
+        });
+        let mut framed = server.ws_at("/ws/battle").await.unwrap();
+        framed
+            .send(Message::Text(request.to_string().into()))
+            .await
+            .unwrap();
+        let mut messages = Vec::new();
+        while let Some(Ok(frame)) = framed.next().await {
+            match frame {
+                Frame::Text(text) => messages.push(serde_json::from_slice(&text).unwrap()),
+                Frame::Close(_) => break,
+                _ => {}
+            }
+        }
+        messages
+    }
+
+    #[actix_rt::test]
+    async fn test_should_stream_every_round_and_the_winner() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let messages = collect_messages(json!({
+            "monster_a": test_monsters[0].id,
+            "monster_b": test_monsters[1].id,
+        }))
+        .await;
+        // monster-1 strikes first for 20, monster-2 answers for 50 and wins
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0]["round"], 1);
+        assert_eq!(messages[0]["attacker"], test_monsters[0].id);
+        assert_eq!(messages[0]["damage"], 20);
+        assert_eq!(messages[0]["defender_hp"], 20);
+        assert_eq!(messages[1]["attacker"], test_monsters[1].id);
+        assert_eq!(messages[2]["winner"], test_monsters[1].id);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_stream_an_error_if_a_monster_does_not_exists() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let messages = collect_messages(json!({
+            "monster_a": test_monsters[0].id,
+            "monster_b": uuid::Uuid::new_v4().to_string(),
+        }))
+        .await;
+        assert_eq!(messages, vec![json!({ "error": "Monster b not found" })]);
+    }
+}
@@ -1,39 +1,172 @@
-use super::battle_apis::{create_battle, delete_battle_by_id, get_battles};
+use super::admin_apis::reset_database;
+use super::battle_apis::{
+    battle_odds, create_battle, create_battles, delete_battle_by_id, get_battle_by_id, get_battles,
+    get_leaderboard, preview_battle, quick_battle, rematch_battle, replay_battle, restore_battle,
+};
 use super::monster_apis::{
-    create_monster, delete_monster_by_id, get_monster_by_id, get_monsters, import_csv,
-    update_monster_by_id,
+    add_monster_tags, batch_get_monsters, bulk_delete_monsters, bulk_update_monsters,
+    clone_monster, compare_monsters, count_monsters, create_monster, delete_monster_by_id,
+    export_csv, export_ndjson, generate_monsters, get_monster_advantage, get_monster_battles,
+    get_monster_by_id, get_monster_histogram, get_monster_names, get_monster_presets,
+    get_monster_schema, get_monsters, import_csv, level_up_monster, patch_monster_by_id,
+    remove_monster_tag, search_monsters, spar_monsters, update_monster_by_id, upload_monster_image,
+    validate_rows,
 };
+use super::tournament_apis::create_tournament;
+use actix_web::dev::ResourceDef;
+use actix_web::http::Method;
 use actix_web::web;
 
+/// Every path served under `/api` with the methods it accepts, used to answer
+/// 405 instead of 404 when a known path is hit with the wrong method.
+const ROUTES: [(&str, &[Method]); 39] = [
+    ("/api/monsters", &[Method::GET, Method::POST]),
+    ("/api/monsters/count", &[Method::GET]),
+    ("/api/monsters/search", &[Method::GET]),
+    ("/api/monsters/names", &[Method::GET]),
+    ("/api/monsters/compare", &[Method::GET]),
+    ("/api/monsters/presets", &[Method::GET]),
+    ("/api/monsters/schema", &[Method::GET]),
+    ("/api/monsters/histogram", &[Method::GET]),
+    ("/api/monsters/export_csv", &[Method::GET]),
+    ("/api/monsters/export_ndjson", &[Method::GET]),
+    ("/api/monsters/generate", &[Method::POST]),
+    ("/api/monsters/spar", &[Method::POST]),
+    ("/api/monsters/batch_get", &[Method::POST]),
+    ("/api/monsters/bulk_delete", &[Method::POST]),
+    ("/api/monsters/bulk_update", &[Method::POST]),
+    ("/api/monsters/import_csv", &[Method::POST]),
+    ("/api/monsters/validate_rows", &[Method::POST]),
+    (
+        "/api/monsters/{id}",
+        &[Method::GET, Method::PUT, Method::PATCH, Method::DELETE],
+    ),
+    ("/api/monsters/{id}/battles", &[Method::GET]),
+    ("/api/monsters/{id}/advantage", &[Method::GET]),
+    ("/api/monsters/{id}/image", &[Method::POST]),
+    ("/api/monsters/{id}/clone", &[Method::POST]),
+    ("/api/monsters/{id}/level_up", &[Method::POST]),
+    ("/api/monsters/{id}/tags", &[Method::POST]),
+    ("/api/monsters/{id}/tags/{tag}", &[Method::DELETE]),
+    ("/api/battles", &[Method::GET, Method::POST]),
+    ("/api/battles/batch", &[Method::POST]),
+    ("/api/battles/preview", &[Method::GET]),
+    ("/api/battles/odds", &[Method::GET]),
+    ("/api/battles/quick", &[Method::GET]),
+    ("/api/battles/{id}", &[Method::GET, Method::DELETE]),
+    ("/api/battles/{id}/rematch", &[Method::POST]),
+    ("/api/battles/{id}/replay", &[Method::GET]),
+    ("/api/battles/{id}/restore", &[Method::POST]),
+    ("/api/leaderboard", &[Method::GET]),
+    ("/api/tournaments", &[Method::POST]),
+    ("/api/admin/reset", &[Method::POST]),
+    ("/health", &[Method::GET]),
+    ("/ready", &[Method::GET]),
+];
+
+/// Methods accepted on `path` across every route it matches; empty for unknown paths.
+pub fn allowed_methods(path: &str) -> Vec<Method> {
+    let mut methods: Vec<Method> = Vec::new();
+    for (pattern, route_methods) in ROUTES.iter() {
+        if !ResourceDef::new(*pattern).is_match(path) {
+            continue;
+        }
+        for method in route_methods.iter() {
+            if !methods.contains(method) {
+                methods.push(method.clone());
+            }
+        }
+    }
+    methods
+}
+
 pub fn config(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/api")
             .service(get_monsters)
             .service(create_monster)
+            .service(count_monsters)
+            .service(search_monsters)
+            .service(get_monster_names)
+            .service(compare_monsters)
+            .service(get_monster_presets)
+            .service(get_monster_schema)
+            .service(get_monster_histogram)
+            .service(export_csv)
+            .service(export_ndjson)
+            .service(generate_monsters)
+            .service(spar_monsters)
+            .service(batch_get_monsters)
+            .service(bulk_delete_monsters)
+            .service(bulk_update_monsters)
             .service(get_monster_by_id)
+            .service(get_monster_battles)
+            .service(get_monster_advantage)
+            .service(clone_monster)
+            .service(level_up_monster)
+            .service(add_monster_tags)
+            .service(remove_monster_tag)
             .service(delete_monster_by_id)
             .service(update_monster_by_id)
+            .service(patch_monster_by_id)
+            .service(upload_monster_image)
             .service(import_csv)
+            .service(validate_rows)
             .service(get_battles)
             .service(create_battle)
-            .service(delete_battle_by_id),
+            .service(create_battles)
+            .service(preview_battle)
+            .service(battle_odds)
+            .service(quick_battle)
+            .service(get_battle_by_id)
+            .service(rematch_battle)
+            .service(replay_battle)
+            .service(restore_battle)
+            .service(delete_battle_by_id)
+            .service(get_leaderboard)
+            .service(create_tournament)
+            .service(reset_database),
     );
 }
 
 #[cfg(test)]
 mod tests {
-    use super::config;
+    use super::{allowed_methods, config};
     use crate::repository::database::Database;
+    use actix_web::http::Method;
     use actix_web::web::Data;
     use actix_web::{test, App};
 
     #[actix_rt::test]
     async fn test_should_get_all_battles_correctly() {
         let db = Database::new();
-        let mut app =
-            test::init_service(App::new().app_data(Data::new(db)).configure(config)).await;
+        let app = test::init_service(App::new().app_data(Data::new(db)).configure(config)).await;
         let request = test::TestRequest::get().uri("/api/battles").to_request();
-        let response = test::call_service(&mut app, request).await;
+        let response = test::call_service(&app, request).await;
         assert!(response.status().is_success());
     }
+
+    #[actix_rt::test]
+    async fn test_should_route_monster_count_before_monster_id() {
+        let db = Database::new();
+        let app = test::init_service(App::new().app_data(Data::new(db)).configure(config)).await;
+        let request = test::TestRequest::get()
+            .uri("/api/monsters/count")
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert!(response.status().is_success());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_list_the_methods_allowed_on_a_path() {
+        assert_eq!(
+            allowed_methods("/api/monsters"),
+            vec![Method::GET, Method::POST]
+        );
+        assert_eq!(
+            allowed_methods("/api/battles/abc/rematch"),
+            vec![Method::POST]
+        );
+        assert!(allowed_methods("/api/lorem").is_empty());
+    }
 }
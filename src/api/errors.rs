@@ -0,0 +1,97 @@
+use crate::repository::database::DbError;
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+use std::fmt;
+use validator::ValidationErrors;
+
+#[derive(Debug)]
+pub enum ApiError {
+    BadRequest(String),
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict(String),
+    PayloadTooLarge(String),
+    /// Well-formed body that breaks a validation rule.
+    UnprocessableEntity(String),
+    PreconditionRequired(String),
+    InternalServerError(String),
+    /// The database could not hand out a connection in time; the client may retry.
+    ServiceUnavailable(String),
+    /// Not enough disk left to take the upload.
+    InsufficientStorage(String),
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::BadRequest(message)
+            | ApiError::Unauthorized(message)
+            | ApiError::Forbidden(message)
+            | ApiError::NotFound(message)
+            | ApiError::Conflict(message)
+            | ApiError::PayloadTooLarge(message)
+            | ApiError::UnprocessableEntity(message)
+            | ApiError::PreconditionRequired(message)
+            | ApiError::InternalServerError(message)
+            | ApiError::ServiceUnavailable(message)
+            | ApiError::InsufficientStorage(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden(_) => StatusCode::FORBIDDEN,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Conflict(_) => StatusCode::CONFLICT,
+            ApiError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ApiError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::PreconditionRequired(_) => StatusCode::PRECONDITION_REQUIRED,
+            ApiError::InternalServerError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::InsufficientStorage(_) => StatusCode::INSUFFICIENT_STORAGE,
+        }
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).json(self.to_string())
+    }
+}
+
+/// Lists each failing field with the rule it broke, e.g. `attack (range), element (element)`;
+/// rules spanning several fields are listed by name alone.
+pub fn validation_details(errors: &ValidationErrors) -> String {
+    let mut fields: Vec<String> = errors
+        .field_errors()
+        .into_iter()
+        .map(|(field, errors)| {
+            let rules: Vec<&str> = errors.iter().map(|error| error.code.as_ref()).collect();
+            match field {
+                "__all__" => rules.join(", "),
+                field => format!("{} ({})", field, rules.join(", ")),
+            }
+        })
+        .collect();
+    fields.sort();
+    fields.join(", ")
+}
+
+impl From<DbError> for ApiError {
+    fn from(err: DbError) -> Self {
+        match err {
+            DbError::Unavailable(_) => {
+                ApiError::ServiceUnavailable("Database is busy, try again later".to_string())
+            }
+            DbError::Query(err) => ApiError::InternalServerError(err.to_string()),
+        }
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        ApiError::UnprocessableEntity(format!("Invalid data: {}", validation_details(&errors)))
+    }
+}
@@ -0,0 +1,139 @@
+use super::errors::ApiError;
+use actix_web::error::PayloadError;
+use actix_web::{dev::Payload, web, Error, FromRequest, HttpRequest};
+use futures::future::LocalBoxFuture;
+use serde::de::DeserializeOwned;
+use std::ops::{Deref, DerefMut};
+
+/// JSON body extractor whose errors name the offending field, e.g. `attack`.
+///
+/// `web::Json` only reports serde's message, which says what type was expected but not where.
+pub struct Json<T>(pub T);
+
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> Deref for Json<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+pub const DEFAULT_MAX_JSON_BODY_BYTES: usize = 256 * 1024;
+
+/// Caps the bodies `Json` reads at `limit` bytes, from `MAX_JSON_BODY_BYTES`.
+pub fn payload_config(limit: usize) -> web::PayloadConfig {
+    web::PayloadConfig::new(limit)
+}
+
+fn field_error(err: serde_path_to_error::Error<serde_json::Error>) -> ApiError {
+    let path = err.path().to_string();
+    if path == "." {
+        ApiError::BadRequest(format!("Invalid JSON body: {}", err.inner()))
+    } else {
+        ApiError::BadRequest(format!("Invalid value for `{}`: {}", path, err.inner()))
+    }
+}
+
+pub fn parse_json<T: DeserializeOwned>(body: &[u8]) -> Result<T, ApiError> {
+    let deserializer = &mut serde_json::Deserializer::from_slice(body);
+    serde_path_to_error::deserialize(deserializer).map_err(field_error)
+}
+
+/// Like `parse_json`, for a body that was already read as a `Value` and adjusted.
+pub fn from_value<T: DeserializeOwned>(body: serde_json::Value) -> Result<T, ApiError> {
+    serde_path_to_error::deserialize(body).map_err(field_error)
+}
+
+impl<T: DeserializeOwned + 'static> FromRequest for Json<T> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let body = web::Bytes::from_request(req, payload);
+        Box::pin(async move {
+            let body = body
+                .await
+                .map_err(|err| match err.as_error::<PayloadError>() {
+                    Some(PayloadError::Overflow) => {
+                        ApiError::PayloadTooLarge("Request body is too large".to_string()).into()
+                    }
+                    _ => err,
+                })?;
+            Ok(Json(parse_json(&body)?))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_json;
+    use crate::api::monster_apis::create_monster;
+    use crate::models::monster::PartialMonster;
+    use crate::repository::database::Database;
+    use crate::utils::metrics::Metrics;
+    use actix_web::{
+        http::StatusCode,
+        test,
+        web::{Data, PayloadConfig},
+        App,
+    };
+    use serde_json::json;
+
+    #[actix_rt::test]
+    async fn test_should_name_the_field_with_the_wrong_type() {
+        let err = parse_json::<Vec<PartialMonster>>(br#"[{"name": "ok"}, {"attack": "lots"}]"#)
+            .expect_err("body should be rejected");
+        assert!(err
+            .to_string()
+            .starts_with("Invalid value for `[1].attack`"));
+        assert!(err.to_string().contains("expected i32"));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_monster_with_400_error_naming_the_invalid_field() {
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({ "attack": "lots" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let message: String = test::read_body_json(resp).await;
+        assert!(message.contains("attack"), "{}", message);
+        assert!(message.contains("expected i32"), "{}", message);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_monster_with_413_error_if_the_body_is_too_large() {
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(PayloadConfig::new(1024))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({ "name": "x".repeat(2048) }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let message: String = test::read_body_json(resp).await;
+        assert_eq!(message, "Request body is too large");
+    }
+}
@@ -1,3 +1,9 @@
+pub mod admin_apis;
+pub mod battle_apis;
+pub mod battle_ws;
 pub mod config;
+pub mod errors;
+pub mod json;
 pub mod monster_apis;
-pub mod battle_apis;
\ No newline at end of file
+pub mod openapi;
+pub mod tournament_apis;
@@ -1,390 +1,4534 @@
-use crate::repository::monster_repository;
-use crate::{models::monster::Monster, repository::database::Database};
+use super::errors::{validation_details, ApiError};
+use super::json::{self, Json};
+use crate::battle_engine;
+use crate::config::AppConfig;
+use crate::models::battle::{BattleRecord, MonsterBattle};
+use crate::models::monster::{
+    check_stat_spread, default_element, normalize_tag, validate_element, Monster, MonsterFilter,
+    MonsterSort, MonsterView, PartialMonster, Stat, StringList, MONSTER_FIELDS,
+};
+use crate::models::pagination::{Pagination, MAX_PER_PAGE};
+use crate::repository::database::{Database, DbError};
+use crate::repository::monster_repository::VersionedUpdate;
+use crate::repository::{battle_repository, monster_repository};
+use crate::utils::auth::identity;
+use crate::utils::disk_space::{available_bytes, DiskSpace};
+use crate::utils::image_hosts::ImageHosts;
+use crate::utils::import_limit::ImportLimit;
+use crate::utils::metrics::Metrics;
+use crate::utils::monster_generator;
+use crate::utils::monster_quota::MonsterQuota;
+use crate::utils::normalize::{normalize, normalize_name};
+use crate::utils::presets::{find_preset, MonsterPreset, PRESETS};
+use crate::utils::request_id::request_id;
+use crate::utils::uploads::{image_extension, MAX_IMAGE_BYTES, UPLOADS_PATH};
 use actix_multipart::Multipart;
-use actix_web::{delete, get, post, put, web, Error, HttpResponse};
-use futures::TryStreamExt;
+use actix_web::http::header::{self, ETag, EntityTag, Header, IfNoneMatch};
+use actix_web::{
+    delete, get, mime, patch, post, put, web, Error, HttpMessage, HttpRequest, HttpResponse,
+    HttpResponseBuilder,
+};
+use futures::{stream, TryStreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::{Hash, Hasher};
 use std::io::Write;
+use std::path::Path;
 use tempfile::NamedTempFile;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
-use validator::Validate;
+use validator::{Validate, ValidationErrors};
 
+pub fn parse_monster_id(id: &str) -> Result<Uuid, ApiError> {
+    Uuid::parse_str(id).map_err(|_| ApiError::BadRequest("Invalid monster id".to_string()))
+}
+
+pub(crate) fn check_pagination(pagination: &Pagination) -> Result<(), ApiError> {
+    if pagination.is_valid() {
+        return Ok(());
+    }
+    Err(ApiError::BadRequest(format!(
+        "page must be at least 1 and small enough to offset into the list, and per_page between 1 and {}",
+        MAX_PER_PAGE
+    )))
+}
+
+/// Runs the field rules of `Monster`, then the `max_spread` check when they pass.
+fn validate_monster(monster: &Monster, max_spread: Option<i32>) -> Result<(), ValidationErrors> {
+    monster.validate()?;
+    check_stat_spread(monster, max_spread).map_err(|err| {
+        let mut errors = ValidationErrors::new();
+        errors.add("__all__", err);
+        errors
+    })
+}
+
+/// Rejects inserting `adding` monsters when `MAX_MONSTERS` would be exceeded.
+fn check_quota(db: &Database, quota: &MonsterQuota, adding: usize) -> Result<(), ApiError> {
+    if !quota.is_limited() {
+        return Ok(());
+    }
+    let stored = monster_repository::count(db, &MonsterFilter::default())?;
+    match quota.exceeded(stored, adding as i64) {
+        Some(remaining) => Err(ApiError::Conflict(format!(
+            "Monster limit reached, {} slots remain",
+            remaining
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Rejects an `image_url` outside `ALLOWED_IMAGE_HOSTS`.
+fn check_image_host(image_hosts: &ImageHosts, image_url: &str) -> Result<(), ApiError> {
+    image_hosts.check(image_url).map_err(ApiError::BadRequest)
+}
+
+/// Applies the configured `DEFAULT_PAGE_SIZE` when the client didn't pick a `per_page`, so
+/// every list request is paged.
+pub(crate) fn with_default_page_size(req: &HttpRequest, pagination: Pagination) -> Pagination {
+    pagination.or_per_page(AppConfig::of(req).default_page_size.per_page())
+}
+
+/// Sets the `Link` header for a page out of `total` items.
+pub(crate) fn insert_link_header(
+    req: &HttpRequest,
+    pagination: &Pagination,
+    total: i64,
+    response: &mut HttpResponseBuilder,
+) {
+    let links = pagination.link_header(req.path(), req.query_string(), total);
+    response.insert_header((header::LINK, links));
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MonsterListQuery {
+    /// Comma separated fields to return, e.g. `id,name`.
+    fields: Option<String>,
+    /// `power` sorts the strongest monsters first.
+    sort_by: Option<String>,
+}
+
+fn parse_fields(fields: &str) -> Result<Vec<&str>, ApiError> {
+    let fields: Vec<&str> = fields.split(',').map(str::trim).collect();
+    match fields
+        .iter()
+        .find(|field| !MONSTER_FIELDS.contains(field) && **field != "power")
+    {
+        Some(unknown) => Err(ApiError::BadRequest(format!("Unknown field: {}", unknown))),
+        None => Ok(fields),
+    }
+}
+
+/// True when the `Accept` header ranks `text/csv` above JSON; JSON stays the default.
+fn accepts_csv(req: &HttpRequest) -> bool {
+    let accept = match header::Accept::parse(req) {
+        Ok(accept) => accept,
+        Err(_) => return false,
+    };
+    accept
+        .ranked()
+        .into_iter()
+        .find(|mime| {
+            *mime == mime::TEXT_CSV || *mime == mime::APPLICATION_JSON || *mime == mime::STAR_STAR
+        })
+        .is_some_and(|mime| mime == mime::TEXT_CSV)
+}
+
+fn csv_cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(value)) => value.clone(),
+        Some(Value::Array(values)) => values
+            .iter()
+            .map(|value| csv_cell(Some(value)))
+            .collect::<Vec<_>>()
+            .join(";"),
+        Some(value) => value.to_string(),
+    }
+}
+
+/// Writes one CSV row per monster with `columns` as the header; list columns are joined with `;`.
+fn render_csv(monsters: &[MonsterView], columns: &[&str]) -> Result<Vec<u8>, ApiError> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let to_error = |err: csv::Error| ApiError::InternalServerError(err.to_string());
+    writer.write_record(columns).map_err(to_error)?;
+    for monster in monsters {
+        let row = project(monster, columns);
+        writer
+            .write_record(columns.iter().map(|column| csv_cell(row.get(*column))))
+            .map_err(to_error)?;
+    }
+    writer
+        .into_inner()
+        .map_err(|err| ApiError::InternalServerError(err.to_string()))
+}
+
+fn project(monster: &MonsterView, fields: &[&str]) -> Map<String, Value> {
+    let mut monster = match serde_json::to_value(monster) {
+        Ok(Value::Object(monster)) => monster,
+        _ => Map::new(),
+    };
+    monster.retain(|key, _| fields.contains(&key.as_str()));
+    monster
+}
+
+#[utoipa::path(
+    params(MonsterFilter, MonsterListQuery, Pagination),
+    responses(
+        (status = 200, description = "One page of monsters, optionally projected to `fields`, with a `Link` header", content(
+            (Vec<MonsterView> = "application/json"),
+            (String = "text/csv")
+        )),
+        (status = 400, description = "Invalid element, unknown field or invalid pagination", body = String)
+    )
+)]
 #[get("/monsters")]
-pub async fn get_monsters(db: web::Data<Database>) -> HttpResponse {
-    let monsters = monster_repository::get_monsters(&db);
-    HttpResponse::Ok().json(monsters)
+pub async fn get_monsters(
+    req: HttpRequest,
+    db: web::Data<Database>,
+    filter: web::Query<MonsterFilter>,
+    query: web::Query<MonsterListQuery>,
+    pagination: web::Query<Pagination>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(element) = &filter.element {
+        validate_element(element)
+            .map_err(|_| ApiError::BadRequest("Invalid element".to_string()))?;
+    }
+    let pagination = with_default_page_size(&req, pagination.into_inner());
+    check_pagination(&pagination)?;
+    let fields = query.fields.as_deref().map(parse_fields).transpose()?;
+    let sort = match query.sort_by.as_deref() {
+        None => MonsterSort::default(),
+        Some(sort_by) => MonsterSort::parse(sort_by)
+            .ok_or_else(|| ApiError::BadRequest("Invalid sort_by value".to_string()))?,
+    };
+    let mut response = HttpResponse::Ok();
+    let total = monster_repository::count(&db, &filter)?;
+    insert_link_header(&req, &pagination, total, &mut response);
+    let monsters: Vec<MonsterView> =
+        monster_repository::get_monster_page(&db, &filter, sort, &pagination)?
+            .into_iter()
+            .map(MonsterView::from)
+            .collect();
+    if accepts_csv(&req) {
+        let columns = fields.unwrap_or_else(|| MONSTER_FIELDS.to_vec());
+        return Ok(response
+            .content_type("text/csv")
+            .body(render_csv(&monsters, &columns)?));
+    }
+    match fields {
+        Some(fields) => {
+            let monsters: Vec<Map<String, Value>> = monsters
+                .iter()
+                .map(|monster| project(monster, &fields))
+                .collect();
+            Ok(response.json(monsters))
+        }
+        None => Ok(response.json(monsters)),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportCsvQuery {
+    /// Comma separated columns in output order, e.g. `name,attack`; every field when omitted.
+    columns: Option<String>,
+}
+
+#[utoipa::path(
+    params(MonsterFilter, ExportCsvQuery),
+    responses(
+        (status = 200, description = "Every monster as a CSV download with the requested columns", content_type = "text/csv", body = String),
+        (status = 400, description = "Invalid element or unknown column", body = String)
+    )
+)]
+#[get("/monsters/export_csv")]
+pub async fn export_csv(
+    db: web::Data<Database>,
+    filter: web::Query<MonsterFilter>,
+    query: web::Query<ExportCsvQuery>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(element) = &filter.element {
+        validate_element(element)
+            .map_err(|_| ApiError::BadRequest("Invalid element".to_string()))?;
+    }
+    let columns = match query.columns.as_deref() {
+        Some(columns) => parse_fields(columns)?,
+        None => MONSTER_FIELDS.to_vec(),
+    };
+    let monsters: Vec<MonsterView> = monster_repository::get_monsters(&db, &filter)?
+        .into_iter()
+        .map(MonsterView::from)
+        .collect();
+    Ok(HttpResponse::Ok()
+        .content_type("text/csv")
+        .insert_header(header::ContentDisposition::attachment("monsters.csv"))
+        .body(render_csv(&monsters, &columns)?))
+}
+
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Every monster as newline-delimited JSON", content_type = "application/x-ndjson", body = Monster)
+    )
+)]
+#[get("/monsters/export_ndjson")]
+pub async fn export_ndjson(db: web::Data<Database>) -> HttpResponse {
+    // reads one batch per chunk so neither side holds the whole table
+    let batches = stream::unfold(Some(None::<String>), move |cursor| {
+        let db = db.clone();
+        async move {
+            let after_id = cursor?;
+            let batch = match monster_repository::get_monsters_after(
+                &db,
+                after_id.as_deref(),
+                EXPORT_BATCH_SIZE,
+            ) {
+                Ok(batch) => batch,
+                Err(err) => return Some((Err(ApiError::from(err).into()), None)),
+            };
+            let last = batch.last()?.id.clone();
+            let mut lines = Vec::new();
+            for monster in &batch {
+                serde_json::to_writer(&mut lines, monster).expect("Error serializing monster");
+                lines.push(b'\n');
+            }
+            let next = (batch.len() as i64 == EXPORT_BATCH_SIZE).then_some(Some(last));
+            Some((Ok::<_, Error>(web::Bytes::from(lines)), next))
+        }
+    });
+    HttpResponse::Ok()
+        .content_type("application/x-ndjson")
+        .streaming(batches)
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CountResponse {
+    pub count: i64,
+}
+
+#[utoipa::path(
+    params(MonsterFilter),
+    responses(
+        (status = 200, description = "Number of monsters", body = CountResponse),
+        (status = 400, description = "Invalid element", body = String)
+    )
+)]
+#[get("/monsters/count")]
+pub async fn count_monsters(
+    db: web::Data<Database>,
+    filter: web::Query<MonsterFilter>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(element) = &filter.element {
+        validate_element(element)
+            .map_err(|_| ApiError::BadRequest("Invalid element".to_string()))?;
+    }
+    let count = monster_repository::count(&db, &filter)?;
+    Ok(HttpResponse::Ok().json(CountResponse { count }))
+}
+
+const DEFAULT_HISTOGRAM_BUCKETS: i32 = 10;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HistogramQuery {
+    /// `attack`, `defense`, `hp` or `speed`.
+    stat: Option<String>,
+    /// Number of equal-width ranges, 2 to 50; defaults to 10.
+    buckets: Option<i32>,
+}
+
+/// Inclusive range of stat values and how many monsters fall in it.
+#[derive(Serialize, Deserialize, Debug, PartialEq, ToSchema)]
+pub struct HistogramBucket {
+    pub min: i64,
+    pub max: i64,
+    pub count: usize,
+}
+
+/// Splits the span from the lowest to the highest value into `buckets` equal-width ranges.
+fn histogram(values: &[i32], buckets: i32) -> Vec<HistogramBucket> {
+    let (low, high) = match (values.iter().min(), values.iter().max()) {
+        (Some(low), Some(high)) => (*low as i64, *high as i64),
+        _ => return Vec::new(),
+    };
+    // i64 because hp, defense and speed span the whole i32 range
+    let buckets = buckets as i64;
+    let width = (high - low + buckets) / buckets;
+    let mut histogram: Vec<HistogramBucket> = (0..buckets)
+        .map(|bucket| HistogramBucket {
+            min: low + bucket * width,
+            max: low + (bucket + 1) * width - 1,
+            count: 0,
+        })
+        .collect();
+    for value in values {
+        let bucket = ((*value as i64 - low) / width).min(buckets - 1);
+        histogram[bucket as usize].count += 1;
+    }
+    histogram
+}
+
+#[utoipa::path(
+    params(HistogramQuery, MonsterFilter),
+    responses(
+        (status = 200, description = "Monster counts per range of the stat, lowest first; empty without monsters", body = [HistogramBucket]),
+        (status = 400, description = "Unknown stat, bucket count outside 2 to 50 or invalid element", body = String)
+    )
+)]
+#[get("/monsters/histogram")]
+pub async fn get_monster_histogram(
+    db: web::Data<Database>,
+    query: web::Query<HistogramQuery>,
+    filter: web::Query<MonsterFilter>,
+) -> Result<HttpResponse, ApiError> {
+    let stat = query.stat.as_deref().and_then(Stat::parse).ok_or_else(|| {
+        ApiError::BadRequest("stat must be one of attack, defense, hp or speed".to_string())
+    })?;
+    let buckets = query.buckets.unwrap_or(DEFAULT_HISTOGRAM_BUCKETS);
+    if !(2..=50).contains(&buckets) {
+        return Err(ApiError::BadRequest(
+            "buckets must be between 2 and 50".to_string(),
+        ));
+    }
+    if let Some(element) = &filter.element {
+        validate_element(element)
+            .map_err(|_| ApiError::BadRequest("Invalid element".to_string()))?;
+    }
+    let values = monster_repository::stat_values(&db, &filter, stat)?;
+    Ok(HttpResponse::Ok().json(histogram(&values, buckets)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Matched case-insensitively against names and aliases.
+    q: Option<String>,
+}
+
+#[utoipa::path(
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Monsters whose name or an alias contains `q`", body = [MonsterView]),
+        (status = 400, description = "Missing search term", body = String)
+    )
+)]
+#[get("/monsters/search")]
+pub async fn search_monsters(
+    db: web::Data<Database>,
+    query: web::Query<SearchQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let term = match query.q.as_deref().map(str::trim) {
+        Some(term) if !term.is_empty() => term,
+        _ => return Err(ApiError::BadRequest("q is required".to_string())),
+    };
+    let monsters: Vec<MonsterView> = monster_repository::search(&db, term)?
+        .into_iter()
+        .map(MonsterView::from)
+        .collect();
+    Ok(HttpResponse::Ok().json(monsters))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct NamesQuery {
+    /// Only names starting with this, ignoring case.
+    prefix: Option<String>,
+}
+
+#[utoipa::path(
+    params(NamesQuery),
+    responses(
+        (status = 200, description = "Distinct monster names in alphabetical order", body = [String])
+    )
+)]
+#[get("/monsters/names")]
+pub async fn get_monster_names(
+    db: web::Data<Database>,
+    query: web::Query<NamesQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let names = monster_repository::names(&db, query.prefix.as_deref())?;
+    Ok(HttpResponse::Ok().json(names))
+}
+
+const MAX_COMPARED_MONSTERS: usize = 5;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CompareQuery {
+    /// Comma separated ids of 2 to 5 monsters.
+    ids: Option<String>,
+}
+
+/// Ids of the monsters holding the highest value of each stat; ties list every holder.
+#[derive(Serialize, Deserialize, Debug, Default, ToSchema)]
+pub struct StatLeaders {
+    pub attack: Vec<String>,
+    pub defense: Vec<String>,
+    pub hp: Vec<String>,
+    pub speed: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct MonsterComparison {
+    pub monsters: Vec<MonsterView>,
+    pub leaders: StatLeaders,
+}
+
+fn stat_leaders(monsters: &[Monster], stat: fn(&Monster) -> i32) -> Vec<String> {
+    let best = monsters.iter().map(stat).max();
+    monsters
+        .iter()
+        .filter(|monster| Some(stat(monster)) == best)
+        .map(|monster| monster.id.clone())
+        .collect()
+}
+
+#[utoipa::path(
+    params(CompareQuery),
+    responses(
+        (status = 200, description = "The monsters side by side with the leader of each stat", body = MonsterComparison),
+        (status = 400, description = "Missing, malformed or too many ids", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[get("/monsters/compare")]
+pub async fn compare_monsters(
+    db: web::Data<Database>,
+    query: web::Query<CompareQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let ids: Vec<&str> = query
+        .ids
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .collect();
+    if !(2..=MAX_COMPARED_MONSTERS).contains(&ids.len()) {
+        return Err(ApiError::BadRequest(format!(
+            "ids must list between 2 and {} monsters",
+            MAX_COMPARED_MONSTERS
+        )));
+    }
+    let ids = ids
+        .into_iter()
+        .map(|id| parse_monster_id(id).map(|id| id.to_string()))
+        .collect::<Result<Vec<String>, ApiError>>()?;
+    let monsters = ids
+        .iter()
+        .map(|id| {
+            monster_repository::get_monster_by_id(&db, id)?
+                .ok_or_else(|| ApiError::NotFound(format!("Monster {} not found", id)))
+        })
+        .collect::<Result<Vec<Monster>, ApiError>>()?;
+    let leaders = StatLeaders {
+        attack: stat_leaders(&monsters, |monster| monster.attack),
+        defense: stat_leaders(&monsters, |monster| monster.defense),
+        hp: stat_leaders(&monsters, |monster| monster.hp),
+        speed: stat_leaders(&monsters, |monster| monster.speed),
+    };
+    Ok(HttpResponse::Ok().json(MonsterComparison {
+        monsters: monsters.into_iter().map(MonsterView::from).collect(),
+        leaders,
+    }))
+}
+
+/// Shape and constraints of one `Monster` field, for building forms.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct FieldSchema {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub field_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Whether a create body has to send the field.
+    pub required: bool,
+    pub nullable: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub maximum: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Reads the fields of `Monster` off its OpenAPI schema, so the `#[schema]` and serde
+/// attributes on the model stay the only place the constraints are written.
+fn monster_field_schemas() -> Vec<FieldSchema> {
+    let mut referenced = Vec::new();
+    <Monster as ToSchema>::schemas(&mut referenced);
+    let resolve = |property: Value| match property.get("$ref").and_then(Value::as_str) {
+        Some(reference) => referenced
+            .iter()
+            .find(|(name, _)| reference.rsplit('/').next() == Some(name.as_str()))
+            .and_then(|(_, schema)| serde_json::to_value(schema).ok())
+            .unwrap_or(property),
+        None => property,
+    };
+    let schema =
+        serde_json::to_value(<Monster as utoipa::PartialSchema>::schema()).unwrap_or_default();
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let mut fields: Vec<FieldSchema> = schema["properties"]
+        .as_object()
+        .map(|properties| {
+            properties
+                .iter()
+                .map(|(name, property)| {
+                    let property = resolve(property.clone());
+                    // nullable fields carry their type as `["integer", "null"]`
+                    let types: Vec<&str> = match &property["type"] {
+                        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                        other => other.as_str().into_iter().collect(),
+                    };
+                    FieldSchema {
+                        name: name.clone(),
+                        field_type: types
+                            .iter()
+                            .find(|field_type| **field_type != "null")
+                            .unwrap_or(&"object")
+                            .to_string(),
+                        format: property["format"].as_str().map(str::to_string),
+                        required: required.contains(&name.as_str()),
+                        nullable: types.contains(&"null"),
+                        minimum: property["minimum"].as_f64(),
+                        maximum: property["maximum"].as_f64(),
+                        description: property["description"].as_str().map(str::to_string),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    // same order as the serialized monster, anything not selectable last
+    fields.sort_by_key(|field| {
+        MONSTER_FIELDS
+            .iter()
+            .position(|name| *name == field.name)
+            .unwrap_or(MONSTER_FIELDS.len())
+    });
+    fields
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Every monster field with its type and validation constraints", body = [FieldSchema])
+    )
+)]
+#[get("/monsters/schema")]
+pub async fn get_monster_schema() -> HttpResponse {
+    HttpResponse::Ok().json(monster_field_schemas())
+}
+
+#[utoipa::path(
+    responses(
+        (status = 200, description = "Stat templates `POST /monsters?preset=` can start from", body = [MonsterPreset])
+    )
+)]
+#[get("/monsters/presets")]
+pub async fn get_monster_presets() -> HttpResponse {
+    HttpResponse::Ok().json(&PRESETS)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateMonsterQuery {
+    /// Name of a `GET /monsters/presets` template whose stats fill in what the body leaves out.
+    preset: Option<String>,
 }
 
+#[utoipa::path(
+    request_body = Monster,
+    params(CreateMonsterQuery),
+    responses(
+        (status = 201, description = "Monster created", body = Monster),
+        (status = 400, description = "Malformed body, unknown preset or image host not allowed", body = String),
+        (status = 409, description = "Name already taken while UNIQUE_MONSTER_NAMES is on, or MAX_MONSTERS reached", body = String),
+        (status = 422, description = "Stats or element break a validation rule", body = String)
+    )
+)]
 #[post("/monsters")]
 pub async fn create_monster(
+    req: HttpRequest,
     db: web::Data<Database>,
-    new_monster: web::Json<Monster>,
-) -> HttpResponse {
-    if new_monster.validate().is_err() {
-        return HttpResponse::NotFound().json("Invalid data");
+    metrics: web::Data<Metrics>,
+    query: web::Query<CreateMonsterQuery>,
+    body: Json<Value>,
+) -> Result<HttpResponse, ApiError> {
+    let config = AppConfig::of(&req);
+    let mut body = body.into_inner();
+    if let Some(name) = &query.preset {
+        let preset = find_preset(name).ok_or_else(|| {
+            let names: Vec<&str> = PRESETS.iter().map(|preset| preset.name).collect();
+            ApiError::BadRequest(format!(
+                "Unknown preset {}, expected one of {}",
+                name,
+                names.join(", ")
+            ))
+        })?;
+        if let Some(fields) = body.as_object_mut() {
+            preset.fill(fields);
+        }
     }
-    let monster = monster_repository::create_monster(&db, new_monster.into_inner());
-    match monster {
-        Ok(monster) => HttpResponse::Created().json(monster),
-        Err(err) => HttpResponse::InternalServerError().json(err.to_string()),
+    let mut new_monster: Monster = json::from_value(body)?;
+    normalize(&mut new_monster);
+    validate_monster(&new_monster, config.max_stat_spread)?;
+    check_image_host(&config.image_hosts, &new_monster.image_url)?;
+    new_monster.created_by = identity(&req);
+    if config.unique_names.enabled() && monster_repository::name_taken(&db, &new_monster.name)? {
+        return Err(ApiError::Conflict(format!(
+            "A monster named {} already exists",
+            new_monster.name
+        )));
     }
+    check_quota(&db, &config.monster_quota, 1)?;
+    let monster = monster_repository::create_monster(&db, new_monster)?;
+    metrics.inc_monsters_created(1);
+    Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, format!("/api/monsters/{}", monster.id)))
+        .json(monster))
 }
 
-#[get("/monsters/{id}")]
-pub async fn get_monster_by_id(db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
-    if !Uuid::parse_str(&id).is_ok() {
-        return HttpResponse::NotFound().json("Monster not found");
+#[utoipa::path(
+    params(("id" = String, Path, description = "Id of the monster to copy")),
+    responses(
+        (status = 201, description = "Copy created with the same stats", body = Monster),
+        (status = 400, description = "Invalid monster id", body = String),
+        (status = 404, description = "Monster not found", body = String),
+        (status = 409, description = "Name already taken while UNIQUE_MONSTER_NAMES is on, or MAX_MONSTERS reached", body = String)
+    )
+)]
+#[post("/monsters/{id}/clone")]
+pub async fn clone_monster(
+    req: HttpRequest,
+    db: web::Data<Database>,
+    metrics: web::Data<Metrics>,
+    id: web::Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    let config = AppConfig::of(&req);
+    let id = parse_monster_id(&id)?;
+    let source = monster_repository::get_monster_by_id(&db, &id.to_string())?
+        .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    let copy = Monster {
+        name: format!("{} (copy)", source.name),
+        created_by: identity(&req),
+        ..source
+    };
+    if config.unique_names.enabled() && monster_repository::name_taken(&db, &copy.name)? {
+        return Err(ApiError::Conflict(format!(
+            "A monster named {} already exists",
+            copy.name
+        )));
+    }
+    check_quota(&db, &config.monster_quota, 1)?;
+    let monster = monster_repository::create_monster(&db, copy)?;
+    metrics.inc_monsters_created(1);
+    Ok(HttpResponse::Created()
+        .insert_header((header::LOCATION, format!("/api/monsters/{}", monster.id)))
+        .json(monster))
+}
+
+const DEFAULT_LEVEL_UP_PERCENT: i32 = 10;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LevelUpQuery {
+    /// How much every stat grows, 1 to 100 percent; defaults to 10.
+    percent: Option<i32>,
+}
+
+#[utoipa::path(
+    params(("id" = String, Path, description = "Monster id"), LevelUpQuery),
+    responses(
+        (status = 200, description = "Monster one level up with stats grown, attack capped at 100", body = Monster),
+        (status = 400, description = "Invalid monster id or percent", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[post("/monsters/{id}/level_up")]
+pub async fn level_up_monster(
+    db: web::Data<Database>,
+    id: web::Path<String>,
+    query: web::Query<LevelUpQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_monster_id(&id)?;
+    let percent = query.percent.unwrap_or(DEFAULT_LEVEL_UP_PERCENT);
+    if !(1..=100).contains(&percent) {
+        return Err(ApiError::BadRequest(
+            "percent must be between 1 and 100".to_string(),
+        ));
+    }
+    let monster = monster_repository::level_up(&db, &id.to_string(), percent)?
+        .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    Ok(HttpResponse::Ok().json(monster))
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TagsRequest {
+    pub tags: Vec<String>,
+}
+
+#[utoipa::path(
+    params(("id" = String, Path, description = "Monster id")),
+    request_body = TagsRequest,
+    responses(
+        (status = 200, description = "Tags added, stored lowercase without repeats", body = Monster),
+        (status = 400, description = "Invalid monster id or no usable tag", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[post("/monsters/{id}/tags")]
+pub async fn add_monster_tags(
+    db: web::Data<Database>,
+    id: web::Path<String>,
+    request: Json<TagsRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_monster_id(&id)?;
+    let new_tags: Vec<String> = request
+        .tags
+        .iter()
+        .filter_map(|tag| normalize_tag(tag))
+        .collect();
+    if new_tags.is_empty() {
+        return Err(ApiError::BadRequest(
+            "tags must contain at least one non-empty tag".to_string(),
+        ));
+    }
+    let monster =
+        monster_repository::update_tags(&db, &id.to_string(), |tags| tags.extend(new_tags))?
+            .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    Ok(HttpResponse::Ok().json(monster))
+}
+
+#[utoipa::path(
+    params(
+        ("id" = String, Path, description = "Monster id"),
+        ("tag" = String, Path, description = "Tag to remove, ignoring case")
+    ),
+    responses(
+        (status = 200, description = "Tag removed, or it was never there", body = Monster),
+        (status = 400, description = "Invalid monster id", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[delete("/monsters/{id}/tags/{tag}")]
+pub async fn remove_monster_tag(
+    db: web::Data<Database>,
+    path: web::Path<(String, String)>,
+) -> Result<HttpResponse, ApiError> {
+    let (id, tag) = path.into_inner();
+    let id = parse_monster_id(&id)?;
+    let tag = normalize_tag(&tag).unwrap_or_default();
+    let monster = monster_repository::update_tags(&db, &id.to_string(), |tags| {
+        tags.retain(|existing| *existing != tag)
+    })?
+    .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    Ok(HttpResponse::Ok().json(monster))
+}
+
+/// Entity tag derived from the monster id and its last update time.
+pub fn monster_etag(monster: &Monster, record: Option<&BattleRecord>) -> EntityTag {
+    let mut hasher = DefaultHasher::new();
+    monster.id.hash(&mut hasher);
+    monster.updated_at.hash(&mut hasher);
+    record.hash(&mut hasher);
+    EntityTag::new_strong(format!("{:016x}", hasher.finish()))
+}
+
+const MAX_GENERATED_MONSTERS: usize = 100;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GenerateQuery {
+    count: Option<usize>,
+}
+
+#[utoipa::path(
+    params(GenerateQuery),
+    responses(
+        (status = 201, description = "Random monsters created", body = [Monster]),
+        (status = 400, description = "count out of range", body = String),
+        (status = 409, description = "MAX_MONSTERS would be exceeded", body = String)
+    )
+)]
+#[post("/monsters/generate")]
+pub async fn generate_monsters(
+    req: HttpRequest,
+    db: web::Data<Database>,
+    metrics: web::Data<Metrics>,
+    query: web::Query<GenerateQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let count = query.count.unwrap_or(10);
+    if count == 0 || count > MAX_GENERATED_MONSTERS {
+        return Err(ApiError::BadRequest(format!(
+            "count must be between 1 and {}",
+            MAX_GENERATED_MONSTERS
+        )));
     }
-    let monster = monster_repository::get_monster_by_id(&db, &id);
-    match monster {
-        Some(monster) => HttpResponse::Ok().json(monster),
-        None => HttpResponse::NotFound().json("Monster not found"),
+    check_quota(&db, &AppConfig::of(&req).monster_quota, count)?;
+    let new_monsters = monster_generator::random_monsters(&mut rand::thread_rng(), count);
+    let monsters = monster_repository::create_monsters(&db, new_monsters)?;
+    metrics.inc_monsters_created(monsters.len() as u64);
+    Ok(HttpResponse::Created().json(monsters))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SparQuery {
+    /// Picks both monsters and breaks ties; a random one is used and echoed back when omitted.
+    seed: Option<u64>,
+}
+
+/// Two throwaway monsters and how their battle went; nothing is saved.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SparResult {
+    pub seed: u64,
+    pub monster_a: Monster,
+    pub monster_b: Monster,
+    /// `None` when the battle ended in a draw.
+    pub winner: Option<String>,
+    pub rounds: i32,
+    pub winner_remaining_hp: i32,
+}
+
+#[utoipa::path(
+    params(SparQuery),
+    responses(
+        (status = 200, description = "Two random monsters from the seed and their battle", body = SparResult)
+    )
+)]
+#[post("/monsters/spar")]
+pub async fn spar_monsters(query: web::Query<SparQuery>) -> HttpResponse {
+    let seed = query.seed.unwrap_or_else(rand::random);
+    let (monster_a, monster_b) = monster_generator::seeded_pair(seed);
+    let options = battle_engine::BattleOptions {
+        seed: Some(seed),
+        ..battle_engine::BattleOptions::default()
+    };
+    let outcome = battle_engine::simulate(&monster_a, &monster_b, &options);
+    HttpResponse::Ok().json(SparResult {
+        seed,
+        monster_a,
+        monster_b,
+        winner: outcome.winner,
+        rounds: outcome.rounds,
+        winner_remaining_hp: outcome.winner_remaining_hp,
+    })
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MonsterQuery {
+    include_record: Option<bool>,
+}
+
+#[utoipa::path(
+    params(("id" = String, Path, description = "Monster id"), MonsterQuery),
+    responses(
+        (status = 200, description = "Monster found", body = MonsterView),
+        (status = 304, description = "Monster unchanged since the given ETag"),
+        (status = 400, description = "Invalid monster id", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[get("/monsters/{id}")]
+pub async fn get_monster_by_id(
+    req: HttpRequest,
+    db: web::Data<Database>,
+    id: web::Path<String>,
+    query: web::Query<MonsterQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_monster_id(&id)?;
+    let monster = monster_repository::get_monster_by_id(&db, &id.to_string())?
+        .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    let record = if query.include_record.unwrap_or(false) {
+        Some(battle_repository::get_record_for_monster(&db, &monster.id)?)
+    } else {
+        None
+    };
+    let etag = monster_etag(&monster, record.as_ref());
+    let not_modified = match req.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+    if not_modified {
+        return Ok(HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .finish());
     }
+    let view = MonsterView {
+        record,
+        ..MonsterView::from(monster)
+    };
+    Ok(HttpResponse::Ok().insert_header(ETag(etag)).json(view))
+}
+
+#[utoipa::path(
+    params(("id" = String, Path, description = "Monster id"), Pagination),
+    responses(
+        (status = 200, description = "Battles the monster took part in, newest first", body = [MonsterBattle]),
+        (status = 400, description = "Invalid monster id or pagination", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[get("/monsters/{id}/battles")]
+pub async fn get_monster_battles(
+    db: web::Data<Database>,
+    id: web::Path<String>,
+    pagination: web::Query<Pagination>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_monster_id(&id)?.to_string();
+    check_pagination(&pagination)?;
+    monster_repository::get_monster_by_id(&db, &id)?
+        .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    let history: Vec<MonsterBattle> =
+        battle_repository::get_battles_for_monster(&db, &id, &pagination)?
+            .into_iter()
+            .map(|battle| MonsterBattle {
+                won: battle.winner.as_deref() == Some(id.as_str()),
+                battle,
+            })
+            .collect();
+    Ok(HttpResponse::Ok().json(history))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdvantageQuery {
+    /// Id of the opposing monster.
+    against: Option<String>,
+}
+
+/// Element damage multipliers between two monsters, in both directions.
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
+pub struct ElementAdvantage {
+    pub monster: String,
+    pub against: String,
+    /// Multiplier on the damage `monster` deals to `against`.
+    pub multiplier: f64,
+    /// Multiplier on the damage `against` deals back.
+    pub reverse_multiplier: f64,
+}
+
+#[utoipa::path(
+    params(("id" = String, Path, description = "Monster id"), AdvantageQuery),
+    responses(
+        (status = 200, description = "Element multipliers both ways", body = ElementAdvantage),
+        (status = 400, description = "Missing or invalid monster ids", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[get("/monsters/{id}/advantage")]
+pub async fn get_monster_advantage(
+    db: web::Data<Database>,
+    id: web::Path<String>,
+    query: web::Query<AdvantageQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_monster_id(&id)?.to_string();
+    let against = query
+        .against
+        .as_deref()
+        .ok_or_else(|| ApiError::BadRequest("against is required".to_string()))?;
+    let against = parse_monster_id(against)?.to_string();
+    let monster = monster_repository::get_monster_by_id(&db, &id)?
+        .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    let opponent = monster_repository::get_monster_by_id(&db, &against)?
+        .ok_or_else(|| ApiError::NotFound("Opposing monster not found".to_string()))?;
+    Ok(HttpResponse::Ok().json(ElementAdvantage {
+        multiplier: battle_engine::element_multiplier(&monster.element, &opponent.element),
+        reverse_multiplier: battle_engine::element_multiplier(&opponent.element, &monster.element),
+        monster: monster.id,
+        against: opponent.id,
+    }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteMonsterQuery {
+    /// Also delete every battle the monster fought, in the same transaction.
+    cascade: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CascadeDeleteResponse {
+    pub battles_deleted: usize,
 }
 
+#[utoipa::path(
+    params(("id" = String, Path, description = "Monster id"), DeleteMonsterQuery),
+    responses(
+        (status = 200, description = "Monster deleted with `cascade=true`, with the battles removed", body = CascadeDeleteResponse),
+        (status = 204, description = "Monster deleted"),
+        (status = 400, description = "Invalid monster id", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
 #[delete("/monsters/{id}")]
-pub async fn delete_monster_by_id(db: web::Data<Database>, id: web::Path<String>) -> HttpResponse {
-    if !Uuid::parse_str(&id).is_ok() {
-        return HttpResponse::NotFound().json("Monster not found");
+pub async fn delete_monster_by_id(
+    db: web::Data<Database>,
+    id: web::Path<String>,
+    query: web::Query<DeleteMonsterQuery>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_monster_id(&id)?;
+    if query.cascade.unwrap_or(false) {
+        let battles_deleted = monster_repository::delete_with_battles(&db, &id.to_string())?
+            .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+        return Ok(HttpResponse::Ok().json(CascadeDeleteResponse { battles_deleted }));
     }
-    let monster = monster_repository::delete_monster_by_id(&db, &id);
-    match monster {
-        Some(_) => HttpResponse::NoContent().finish(),
-        None => HttpResponse::NotFound().json("Monster not found"),
+    monster_repository::delete_monster_by_id(&db, &id.to_string())?
+        .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// Reads the expected version from `If-Match` (quotes optional), falling back to the body.
+fn expected_version(req: &HttpRequest, body_version: i32) -> Result<i32, ApiError> {
+    match req.headers().get(header::IF_MATCH) {
+        Some(value) => value
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().trim_matches('"').parse().ok())
+            .ok_or_else(|| ApiError::BadRequest("Invalid If-Match version".to_string())),
+        None if body_version > 0 => Ok(body_version),
+        None => Err(ApiError::PreconditionRequired(
+            "Send the expected version in If-Match or the body".to_string(),
+        )),
     }
 }
 
+#[utoipa::path(
+    request_body = Monster,
+    params(
+        ("id" = String, Path, description = "Monster id"),
+        ("If-Match" = Option<String>, Header, description = "Expected monster version")
+    ),
+    responses(
+        (status = 200, description = "Monster updated", body = Monster),
+        (status = 400, description = "Invalid monster id, malformed body or image host not allowed", body = String),
+        (status = 404, description = "Monster not found", body = String),
+        (status = 409, description = "Monster version is stale", body = String),
+        (status = 422, description = "Stats or element break a validation rule", body = String),
+        (status = 428, description = "No expected version sent", body = String)
+    )
+)]
 #[put("/monsters/{id}")]
 pub async fn update_monster_by_id(
+    req: HttpRequest,
     db: web::Data<Database>,
     id: web::Path<String>,
-    updated_monster: web::Json<Monster>,
-) -> HttpResponse {
-    if !Uuid::parse_str(&id).is_ok() {
-        return HttpResponse::NotFound().json("Monster not found");
+    updated_monster: Json<Monster>,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_monster_id(&id)?;
+    let config = AppConfig::of(&req);
+    let mut updated_monster = updated_monster.into_inner();
+    normalize(&mut updated_monster);
+    validate_monster(&updated_monster, config.max_stat_spread)?;
+    check_image_host(&config.image_hosts, &updated_monster.image_url)?;
+    let expected_version = expected_version(&req, updated_monster.version)?;
+    versioned_response(monster_repository::update_monster_by_id(
+        &db,
+        &id.to_string(),
+        updated_monster,
+        expected_version,
+    )?)
+}
+
+fn versioned_response(update: VersionedUpdate) -> Result<HttpResponse, ApiError> {
+    match update {
+        VersionedUpdate::Updated(monster) => Ok(HttpResponse::Ok().json(monster)),
+        VersionedUpdate::Stale => Err(ApiError::Conflict(
+            "Monster was modified by someone else, reload it and try again".to_string(),
+        )),
+        VersionedUpdate::NotFound => Err(ApiError::NotFound("Monster not found".to_string())),
+    }
+}
+
+const JSON_PATCH_CONTENT_TYPE: &str = "application/json-patch+json";
+
+/// Applies RFC 6902 operations to the stored monster, then validates and saves the result
+/// like `PUT` would.
+fn json_patch_monster(
+    req: &HttpRequest,
+    db: &Database,
+    id: &str,
+    body: &[u8],
+) -> Result<HttpResponse, ApiError> {
+    let operations: json_patch::Patch = json::parse_json(body)?;
+    let current = monster_repository::get_monster_by_id(db, id)?
+        .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    let mut document = serde_json::to_value(&current).expect("Error serializing monster");
+    json_patch::patch(&mut document, &operations).map_err(|err| match err.kind {
+        json_patch::PatchErrorKind::TestFailed => {
+            ApiError::Conflict(format!("Patch test failed: {}", err))
+        }
+        _ => ApiError::UnprocessableEntity(format!("Patch cannot be applied: {}", err)),
+    })?;
+    let mut patched: Monster = serde_json::from_value(document).map_err(|err| {
+        ApiError::UnprocessableEntity(format!("Invalid patched monster: {}", err))
+    })?;
+    patched.id = id.to_string();
+    normalize(&mut patched);
+    let config = AppConfig::of(req);
+    validate_monster(&patched, config.max_stat_spread)?;
+    check_image_host(&config.image_hosts, &patched.image_url)?;
+    let expected_version = expected_version(req, current.version)?;
+    versioned_response(monster_repository::update_monster_by_id(
+        db,
+        id,
+        patched,
+        expected_version,
+    )?)
+}
+
+#[utoipa::path(
+    request_body(content(
+        (PartialMonster = "application/json"),
+        (json_patch::Patch = "application/json-patch+json")
+    )),
+    params(
+        ("id" = String, Path, description = "Monster id"),
+        ("If-Match" = Option<String>, Header, description = "Expected monster version, JSON Patch only")
+    ),
+    responses(
+        (status = 200, description = "Monster updated", body = Monster),
+        (status = 400, description = "Invalid monster id, malformed body or image host not allowed", body = String),
+        (status = 404, description = "Monster not found", body = String),
+        (status = 409, description = "A JSON Patch `test` failed or the monster version is stale", body = String),
+        (status = 422, description = "Stats or element break a validation rule, or the patch cannot be applied", body = String)
+    )
+)]
+#[patch("/monsters/{id}")]
+pub async fn patch_monster_by_id(
+    req: HttpRequest,
+    db: web::Data<Database>,
+    id: web::Path<String>,
+    body: web::Bytes,
+) -> Result<HttpResponse, ApiError> {
+    let id = parse_monster_id(&id)?;
+    let is_json_patch = req
+        .mime_type()
+        .ok()
+        .flatten()
+        .is_some_and(|mime| mime.essence_str() == JSON_PATCH_CONTENT_TYPE);
+    if is_json_patch {
+        return json_patch_monster(&req, &db, &id.to_string(), &body);
+    }
+    let mut changes: PartialMonster = json::parse_json(&body)?;
+    changes.name = changes.name.as_deref().map(normalize_name);
+    changes.image_url = changes
+        .image_url
+        .as_deref()
+        .map(|image_url| image_url.trim().to_string());
+    changes.validate()?;
+    let config = AppConfig::of(&req);
+    if let Some(image_url) = &changes.image_url {
+        check_image_host(&config.image_hosts, image_url)?;
     }
-    let monster = monster_repository::update_monster_by_id(&db, &id, updated_monster.into_inner());
-    match monster {
-        Some(monster) => HttpResponse::Ok().json(monster),
-        None => HttpResponse::NotFound().json("Monster not found"),
+    if config.max_stat_spread.is_some() && (changes.attack.is_some() || changes.defense.is_some()) {
+        // the spread is checked on the stats the monster ends up with
+        let current = monster_repository::get_monster_by_id(&db, &id.to_string())?
+            .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+        let patched = Monster {
+            attack: changes.attack.unwrap_or(current.attack),
+            defense: changes.defense.unwrap_or(current.defense),
+            ..current
+        };
+        validate_monster(&patched, config.max_stat_spread)?;
     }
+    let monster = monster_repository::patch_monster_by_id(&db, &id.to_string(), changes)?
+        .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    Ok(HttpResponse::Ok().json(monster))
 }
 
-#[post("/monsters/import_csv")]
-pub async fn import_csv(
+#[utoipa::path(
+    request_body(content_type = "multipart/form-data", description = "PNG or JPEG image file"),
+    params(("id" = String, Path, description = "Monster id")),
+    responses(
+        (status = 200, description = "Image stored and `image_url` updated", body = Monster),
+        (status = 400, description = "Missing, oversized or non PNG/JPEG image", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[post("/monsters/{id}/image")]
+pub async fn upload_monster_image(
+    req: HttpRequest,
     db: web::Data<Database>,
+    id: web::Path<String>,
     mut payload: Multipart,
 ) -> Result<HttpResponse, Error> {
-    let mut file_name: Option<String> = None;
-    let mut temp_file: Option<NamedTempFile> = None;
-    let mut new_monsters: Vec<Monster> = Vec::new();
+    let id = parse_monster_id(&id)?.to_string();
+    if monster_repository::get_monster_by_id(&db, &id)
+        .map_err(ApiError::from)?
+        .is_none()
+    {
+        return Err(ApiError::NotFound("Monster not found".to_string()).into());
+    }
+    let mut image: Option<(&'static str, Vec<u8>)> = None;
 
     while let Some(mut field) = payload.try_next().await? {
-        let content_disposition = field.content_disposition();
-
-        if let Some(name) = content_disposition.get_filename() {
-            file_name = Some(name.to_string());
-            temp_file = Some(NamedTempFile::new().unwrap());
-
-            while let Some(chunk) = field.try_next().await? {
-                temp_file.as_mut().unwrap().write_all(&chunk).unwrap();
+        if field.content_disposition().get_filename().is_none() {
+            continue;
+        }
+        let extension = match field
+            .content_type()
+            .and_then(|mime| image_extension(mime.essence_str()))
+        {
+            Some(extension) => extension,
+            None => {
+                return Err(ApiError::BadRequest("Image must be a PNG or JPEG".to_string()).into())
+            }
+        };
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.try_next().await? {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() > MAX_IMAGE_BYTES {
+                return Err(ApiError::BadRequest(format!(
+                    "Image must be at most {} bytes",
+                    MAX_IMAGE_BYTES
+                ))
+                .into());
             }
-        } else {
-            return Ok(HttpResponse::BadRequest().json("No file name provided"));
         }
+        image = Some((extension, bytes));
     }
 
-    if let Some(_file_name) = file_name {
-        if let Some(temp_file) = temp_file {
-            let mut reader = csv::ReaderBuilder::new()
-                .has_headers(true)
-                .from_path(temp_file.path())
-                .unwrap();
-
-            for result in reader.deserialize::<Monster>() {
-                match result {
-                    Ok(monster) => {
-                        new_monsters.push(monster);
-                    }
-                    Err(_) => {
-                        return Ok(
-                            HttpResponse::BadRequest().json("Incomplete data, check your file.")
-                        );
-                    }
-                }
-            }
-
-            if new_monsters.is_empty() {
-                return Ok(
-                    HttpResponse::BadRequest().json("No valid monsters found in the CSV file")
-                );
-            }
+    let (extension, bytes) =
+        image.ok_or_else(|| ApiError::BadRequest("No image uploaded".to_string()))?;
+    let file_name = format!("{}.{}", id, extension);
+    let upload_dir = &AppConfig::of(&req).upload_dir;
+    std::fs::create_dir_all(upload_dir.path())
+        .and_then(|_| std::fs::write(upload_dir.path().join(&file_name), bytes))
+        .map_err(|err| ApiError::InternalServerError(err.to_string()))?;
+    let changes = PartialMonster {
+        image_url: Some(format!("{}/{}", UPLOADS_PATH, file_name)),
+        ..PartialMonster::default()
+    };
+    let monster = monster_repository::patch_monster_by_id(&db, &id, changes)
+        .map_err(ApiError::from)?
+        .ok_or_else(|| ApiError::NotFound("Monster not found".to_string()))?;
+    Ok(HttpResponse::Ok().json(monster))
+}
 
-            let results: Vec<Result<Monster, String>> = new_monsters
-                .iter()
-                .map(|new_monster| {
-                    match monster_repository::create_monster(&db, new_monster.clone()) {
-                        Ok(monster) => Ok(monster),
-                        Err(err) => Err(err.to_string()),
-                    }
-                })
-                .collect();
+const MAX_BATCH_GET_IDS: usize = 100;
 
-            let (successes, _errors): (Vec<_>, Vec<_>) =
-                results.into_iter().partition(Result::is_ok);
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchGetRequest {
+    pub ids: Vec<String>,
+}
 
-            let successful_monsters: Vec<Monster> =
-                successes.into_iter().map(Result::unwrap).collect();
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BatchGetResponse {
+    /// Found monsters, in the order their ids were sent.
+    pub monsters: Vec<Monster>,
+    pub not_found: Vec<String>,
+}
 
-            if successful_monsters.is_empty() {
-                return Ok(HttpResponse::InternalServerError().json("Failed to create monsters"));
-            } else {
-                return Ok(HttpResponse::Ok().json(successful_monsters));
-            }
-        }
+#[utoipa::path(
+    request_body = BatchGetRequest,
+    responses(
+        (status = 200, description = "The monsters found and the ids that did not exist", body = BatchGetResponse),
+        (status = 400, description = "A monster id is malformed or more than 100 ids were sent", body = String)
+    )
+)]
+#[post("/monsters/batch_get")]
+pub async fn batch_get_monsters(
+    db: web::Data<Database>,
+    request: Json<BatchGetRequest>,
+) -> Result<HttpResponse, ApiError> {
+    if request.ids.len() > MAX_BATCH_GET_IDS {
+        return Err(ApiError::BadRequest(format!(
+            "Cannot fetch more than {} monsters at once",
+            MAX_BATCH_GET_IDS
+        )));
     }
+    let ids = request
+        .ids
+        .iter()
+        .map(|id| parse_monster_id(id).map(|id| id.to_string()))
+        .collect::<Result<Vec<String>, ApiError>>()?;
+    let mut monsters = monster_repository::get_monsters_by_ids(&db, &ids)?;
+    monsters.sort_by_key(|monster| ids.iter().position(|id| *id == monster.id));
+    let not_found = ids
+        .into_iter()
+        .filter(|id| !monsters.iter().any(|monster| monster.id == *id))
+        .collect();
+    Ok(HttpResponse::Ok().json(BatchGetResponse {
+        monsters,
+        not_found,
+    }))
+}
 
-    Ok(HttpResponse::BadRequest().json("No file uploaded"))
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BulkDeleteRequest {
+    pub ids: Vec<String>,
 }
 
-#[cfg(test)]
-mod tests {
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BulkDeleteResponse {
+    pub deleted: usize,
+    pub not_found: Vec<String>,
+}
+
+#[utoipa::path(
+    request_body = BulkDeleteRequest,
+    responses(
+        (status = 200, description = "Deleted count and the ids that did not exist", body = BulkDeleteResponse),
+        (status = 400, description = "A monster id is malformed", body = String)
+    )
+)]
+#[post("/monsters/bulk_delete")]
+pub async fn bulk_delete_monsters(
+    db: web::Data<Database>,
+    request: Json<BulkDeleteRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let ids = request
+        .ids
+        .iter()
+        .map(|id| parse_monster_id(id).map(|id| id.to_string()))
+        .collect::<Result<Vec<String>, ApiError>>()?;
+    let deleted_ids = monster_repository::delete_many(&db, &ids)?;
+    let not_found = ids
+        .into_iter()
+        .filter(|id| !deleted_ids.contains(id))
+        .collect();
+    Ok(HttpResponse::Ok().json(BulkDeleteResponse {
+        deleted: deleted_ids.len(),
+        not_found,
+    }))
+}
+
+/// Amounts added to each stat; omitted stats are left alone.
+///
+/// Only `attack` has a range on `Monster` (0 to 100); `defense`, `hp` and `speed` are
+/// unbounded there too, so for them the only check is that the sum fits in an `i32`.
+#[derive(Serialize, Deserialize, Debug, Default, ToSchema)]
+pub struct StatChanges {
+    pub attack: Option<i32>,
+    pub defense: Option<i32>,
+    pub hp: Option<i32>,
+    pub speed: Option<i32>,
+}
+
+impl StatChanges {
+    fn is_empty(&self) -> bool {
+        self.attack.is_none() && self.defense.is_none() && self.hp.is_none() && self.speed.is_none()
+    }
+
+    /// Adds the changes to `monster`; `Err` names the first stat that would overflow.
+    fn apply(&self, monster: &mut Monster) -> Result<(), ApiError> {
+        for (name, stat, change) in [
+            ("attack", &mut monster.attack, self.attack),
+            ("defense", &mut monster.defense, self.defense),
+            ("hp", &mut monster.hp, self.hp),
+            ("speed", &mut monster.speed, self.speed),
+        ] {
+            *stat = stat.checked_add(change.unwrap_or(0)).ok_or_else(|| {
+                ApiError::UnprocessableEntity(format!(
+                    "Invalid data for monster {}: {} is out of range",
+                    monster.id, name
+                ))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BulkUpdateRequest {
+    pub ids: Vec<String>,
+    pub changes: StatChanges,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct BulkUpdateResponse {
+    pub updated: Vec<Monster>,
+    pub not_found: Vec<String>,
+}
+
+#[utoipa::path(
+    request_body = BulkUpdateRequest,
+    responses(
+        (status = 200, description = "Updated monsters and the ids that did not exist", body = BulkUpdateResponse),
+        (status = 400, description = "A monster id is malformed or no change was given", body = String),
+        (status = 422, description = "A monster would break a validation rule; nothing is saved", body = String)
+    )
+)]
+#[post("/monsters/bulk_update")]
+pub async fn bulk_update_monsters(
+    req: HttpRequest,
+    db: web::Data<Database>,
+    request: Json<BulkUpdateRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let max_spread = AppConfig::of(&req).max_stat_spread;
+    let ids = request
+        .ids
+        .iter()
+        .map(|id| parse_monster_id(id).map(|id| id.to_string()))
+        .collect::<Result<Vec<String>, ApiError>>()?;
+    if request.changes.is_empty() {
+        return Err(ApiError::BadRequest(
+            "changes must set at least one stat".to_string(),
+        ));
+    }
+    let updated = monster_repository::update_many(&db, &ids, |monster| {
+        request.changes.apply(monster)?;
+        validate_monster(monster, max_spread).map_err(|errors| {
+            ApiError::UnprocessableEntity(format!(
+                "Invalid data for monster {}: {}",
+                monster.id,
+                validation_details(&errors)
+            ))
+        })
+    })??;
+    let not_found = ids
+        .into_iter()
+        .filter(|id| !updated.iter().any(|monster| monster.id == *id))
+        .collect();
+    Ok(HttpResponse::Ok().json(BulkUpdateResponse { updated, not_found }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    dry_run: Option<bool>,
+    /// Only `name` is supported: rows whose name already exists are skipped.
+    dedupe_by: Option<String>,
+    /// Insert every row in one transaction: all of them are saved or none is.
+    atomic: Option<bool>,
+    /// Fill missing stat columns with `default_stat` instead of rejecting the row.
+    defaults: Option<bool>,
+    /// Stat used by `defaults=true`, 50 when omitted.
+    default_stat: Option<i32>,
+}
+
+const DEFAULT_IMPORT_STAT: i32 = 50;
+
+/// Permissive CSV row for `defaults=true`: only `name` is required.
+#[derive(Deserialize)]
+struct CsvMonster {
+    name: String,
+    image_url: Option<String>,
+    attack: Option<i32>,
+    defense: Option<i32>,
+    hp: Option<i32>,
+    speed: Option<i32>,
+    element: Option<String>,
+}
+
+impl CsvMonster {
+    fn into_monster(self, default_stat: i32) -> Monster {
+        Monster {
+            id: String::new(),
+            image_url: self
+                .image_url
+                .unwrap_or(monster_generator::PLACEHOLDER_IMAGE_URL.to_string()),
+            name: self.name,
+            attack: self.attack.unwrap_or(default_stat),
+            defense: self.defense.unwrap_or(default_stat),
+            hp: self.hp.unwrap_or(default_stat),
+            speed: self.speed.unwrap_or(default_stat),
+            created_at: None,
+            updated_at: None,
+            element: self.element.unwrap_or_else(default_element),
+            version: 0,
+            aliases: StringList::default(),
+            tags: StringList::default(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
+        }
+    }
+}
+
+/// Returned instead of the plain monster list when `dedupe_by` is set.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportResult {
+    pub imported: Vec<Monster>,
+    pub skipped: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportRowError {
+    pub line: u64,
+    pub error: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ImportReport {
+    pub valid: usize,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// Source column name -> `Monster` field name, sent as the optional `mapping` multipart field.
+pub type ColumnMapping = HashMap<String, String>;
+
+fn parse_column_mapping(mapping: &[u8]) -> Result<ColumnMapping, String> {
+    let mapping: ColumnMapping =
+        serde_json::from_slice(mapping).map_err(|_| "Invalid column mapping".to_string())?;
+    match mapping
+        .values()
+        .find(|target| !MONSTER_FIELDS.contains(&target.as_str()))
+    {
+        Some(target) => Err(format!("Unknown mapping target: {}", target)),
+        None => Ok(mapping),
+    }
+}
+
+/// Opens a CSV file with its header row renamed through `mapping`.
+pub fn open_csv(path: &Path, mapping: &ColumnMapping) -> csv::Result<csv::Reader<File>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(path)?;
+    let headers: csv::StringRecord = reader
+        .headers()?
+        .iter()
+        .map(|header| mapping.get(header).map_or(header, String::as_str))
+        .collect();
+    reader.set_headers(headers);
+    Ok(reader)
+}
+
+/// Parses and validates every row of a CSV file without touching the database.
+pub fn validate_csv_file(
+    path: &Path,
+    mapping: &ColumnMapping,
+    max_spread: Option<i32>,
+) -> csv::Result<ImportReport> {
+    let mut reader = open_csv(path, mapping)?;
+    let headers = reader.headers()?.clone();
+    let mut report = ImportReport {
+        valid: 0,
+        errors: Vec::new(),
+    };
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |position| position.line());
+        match validate_row(&headers, &record, max_spread) {
+            Ok(()) => report.valid += 1,
+            Err(error) => report.errors.push(ImportRowError { line, error }),
+        }
+    }
+    Ok(report)
+}
+
+fn describe_row_error(headers: &csv::StringRecord, err: &csv::Error) -> String {
+    if let csv::ErrorKind::Deserialize { err, .. } = err.kind() {
+        if let Some(column) = err.field().and_then(|field| headers.get(field as usize)) {
+            return format!("{}: {}", column, err.kind());
+        }
+    }
+    err.to_string()
+}
+
+/// Parses and validates one row the way an import would; parse errors name the column.
+fn validate_row(
+    headers: &csv::StringRecord,
+    record: &csv::StringRecord,
+    max_spread: Option<i32>,
+) -> Result<(), String> {
+    record
+        .deserialize::<Monster>(Some(headers))
+        .map_err(|err| describe_row_error(headers, &err))
+        .and_then(|mut monster| {
+            normalize(&mut monster);
+            validate_monster(&monster, max_spread).map_err(|err| err.to_string())
+        })
+}
+
+#[utoipa::path(
+    request_body = Vec<HashMap<String, String>>,
+    responses(
+        (status = 200, description = "How many rows would import; `line` is the 1-based position of a failing row", body = ImportReport),
+        (status = 400, description = "Body is not an array of objects with string values", body = String)
+    )
+)]
+#[post("/monsters/validate_rows")]
+pub async fn validate_rows(
+    req: HttpRequest,
+    rows: Json<Vec<HashMap<String, String>>>,
+) -> HttpResponse {
+    let max_spread = AppConfig::of(&req).max_stat_spread;
+    let mut report = ImportReport {
+        valid: 0,
+        errors: Vec::new(),
+    };
+    for (index, row) in rows.iter().enumerate() {
+        let headers: csv::StringRecord = row.keys().collect();
+        let record: csv::StringRecord = row.values().collect();
+        match validate_row(&headers, &record, max_spread) {
+            Ok(()) => report.valid += 1,
+            Err(error) => report.errors.push(ImportRowError {
+                line: index as u64 + 1,
+                error,
+            }),
+        }
+    }
+    HttpResponse::Ok().json(report)
+}
+
+/// Refuses an import up front when the temp dir could not hold the upload, instead of
+/// failing halfway through writing it.
+fn check_disk_space(req: &HttpRequest, disk_space: &DiskSpace) -> Result<(), ApiError> {
+    let temp_dir = std::env::temp_dir();
+    let available = match available_bytes(&temp_dir) {
+        Ok(available) => available,
+        Err(err) => {
+            log::warn!(
+                "Could not read free space of {}: {}",
+                temp_dir.display(),
+                err
+            );
+            return Ok(());
+        }
+    };
+    let declared = req
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    disk_space
+        .check(available, declared)
+        .map_err(ApiError::InsufficientStorage)
+}
+
+#[utoipa::path(
+    request_body(content_type = "multipart/form-data", description = "CSV file with one monster per row"),
+    params(ImportQuery),
+    responses(
+        (status = 200, description = "Monsters imported, the dedupe result, or the dry-run report", body = ImportReport),
+        (status = 400, description = "Missing or invalid CSV file", body = String),
+        (status = 409, description = "The rows would exceed MAX_MONSTERS", body = String),
+        (status = 422, description = "A row breaks a validation rule", body = String),
+        (status = 429, description = "Too many imports in progress", body = String),
+        (status = 507, description = "Too little disk space left for the upload", body = String)
+    )
+)]
+#[post("/monsters/import_csv")]
+pub async fn import_csv(
+    req: HttpRequest,
+    db: web::Data<Database>,
+    metrics: web::Data<Metrics>,
+    import_limit: web::Data<ImportLimit>,
+    query: web::Query<ImportQuery>,
+    mut payload: Multipart,
+) -> Result<HttpResponse, Error> {
+    let config = AppConfig::of(&req);
+    check_disk_space(&req, &config.disk_space)?;
+    let _permit = match import_limit.try_acquire() {
+        Some(permit) => permit,
+        None => {
+            return Ok(HttpResponse::TooManyRequests()
+                .json("Too many imports in progress, try again later"))
+        }
+    };
+    let dedupe = match query.dedupe_by.as_deref() {
+        None => false,
+        Some("name") => true,
+        Some(_) => return Ok(HttpResponse::BadRequest().json("Invalid dedupe_by value")),
+    };
+    let mut file_name: Option<String> = None;
+    let mut temp_file: Option<NamedTempFile> = None;
+    let mut mapping = ColumnMapping::new();
+    let mut new_monsters: Vec<Monster> = Vec::new();
+
+    while let Some(mut field) = payload.try_next().await? {
+        let content_disposition = field.content_disposition();
+
+        if let Some(name) = content_disposition.get_filename() {
+            file_name = Some(name.to_string());
+            temp_file = Some(NamedTempFile::new().unwrap());
+
+            while let Some(chunk) = field.try_next().await? {
+                temp_file.as_mut().unwrap().write_all(&chunk).unwrap();
+            }
+        } else if content_disposition.get_name() == Some("mapping") {
+            let mut raw_mapping = Vec::new();
+            while let Some(chunk) = field.try_next().await? {
+                raw_mapping.extend_from_slice(&chunk);
+            }
+            mapping = match parse_column_mapping(&raw_mapping) {
+                Ok(mapping) => mapping,
+                Err(message) => return Ok(HttpResponse::BadRequest().json(message)),
+            };
+        } else {
+            return Ok(HttpResponse::BadRequest().json("No file name provided"));
+        }
+    }
+
+    if let Some(_file_name) = file_name {
+        if let Some(temp_file) = temp_file {
+            if query.dry_run.unwrap_or(false) {
+                return match validate_csv_file(temp_file.path(), &mapping, config.max_stat_spread) {
+                    Ok(report) => Ok(HttpResponse::Ok().json(report)),
+                    Err(_) => Ok(HttpResponse::BadRequest().json("Unreadable CSV file")),
+                };
+            }
+            let mut reader = match open_csv(temp_file.path(), &mapping) {
+                Ok(reader) => reader,
+                Err(_) => return Ok(HttpResponse::BadRequest().json("Unreadable CSV file")),
+            };
+            let rows: Box<dyn Iterator<Item = csv::Result<Monster>>> =
+                if query.defaults.unwrap_or(false) {
+                    let default_stat = query.default_stat.unwrap_or(DEFAULT_IMPORT_STAT);
+                    Box::new(
+                        reader
+                            .deserialize::<CsvMonster>()
+                            .map(move |row| row.map(|row| row.into_monster(default_stat))),
+                    )
+                } else {
+                    Box::new(reader.deserialize::<Monster>())
+                };
+
+            for (row, result) in rows.enumerate() {
+                match result {
+                    Ok(mut monster) => {
+                        normalize(&mut monster);
+                        if let Err(errors) = validate_monster(&monster, config.max_stat_spread) {
+                            return Ok(HttpResponse::UnprocessableEntity().json(format!(
+                                "Invalid data in row {}: {}",
+                                row + 1,
+                                validation_details(&errors)
+                            )));
+                        }
+                        new_monsters.push(monster);
+                    }
+                    Err(_) => {
+                        return Ok(
+                            HttpResponse::BadRequest().json("Incomplete data, check your file.")
+                        );
+                    }
+                }
+            }
+
+            if new_monsters.is_empty() {
+                return Ok(
+                    HttpResponse::BadRequest().json("No valid monsters found in the CSV file")
+                );
+            }
+
+            let mut skipped = Vec::new();
+            if dedupe {
+                let names: Vec<String> = new_monsters
+                    .iter()
+                    .map(|monster| monster.name.clone())
+                    .collect();
+                let mut taken: HashSet<String> = monster_repository::existing_names(&db, &names)
+                    .map_err(ApiError::from)?
+                    .into_iter()
+                    .collect();
+                // also skips repeats within the same file
+                new_monsters.retain(|monster| {
+                    let fresh = taken.insert(monster.name.clone());
+                    if !fresh {
+                        skipped.push(monster.name.clone());
+                    }
+                    fresh
+                });
+                if new_monsters.is_empty() {
+                    return Ok(HttpResponse::Ok().json(ImportResult {
+                        imported: Vec::new(),
+                        skipped,
+                    }));
+                }
+            }
+
+            check_quota(&db, &config.monster_quota, new_monsters.len())?;
+            let successful_monsters: Vec<Monster> = if query.atomic.unwrap_or(false) {
+                match monster_repository::create_monsters(&db, new_monsters) {
+                    Ok(monsters) => monsters,
+                    Err(err @ DbError::Unavailable(_)) => return Err(ApiError::from(err).into()),
+                    Err(err) => {
+                        log::warn!(
+                            "[{}] atomic import rolled back: {}",
+                            request_id(&req).unwrap_or_default(),
+                            err
+                        );
+                        return Ok(HttpResponse::InternalServerError()
+                            .json("Import rolled back, no monsters were saved"));
+                    }
+                }
+            } else {
+                let results: Vec<Result<Monster, String>> = new_monsters
+                    .iter()
+                    .map(|new_monster| {
+                        match monster_repository::create_monster(&db, new_monster.clone()) {
+                            Ok(monster) => Ok(monster),
+                            Err(err) => Err(err.to_string()),
+                        }
+                    })
+                    .collect();
+
+                let (successes, errors): (Vec<_>, Vec<_>) =
+                    results.into_iter().partition(Result::is_ok);
+                if !errors.is_empty() {
+                    log::warn!(
+                        "[{}] {} of {} imported monsters could not be saved",
+                        request_id(&req).unwrap_or_default(),
+                        errors.len(),
+                        new_monsters.len()
+                    );
+                }
+
+                successes.into_iter().map(Result::unwrap).collect()
+            };
+
+            if successful_monsters.is_empty() {
+                return Ok(HttpResponse::InternalServerError().json("Failed to create monsters"));
+            } else {
+                metrics.inc_monsters_created(successful_monsters.len() as u64);
+                if dedupe {
+                    return Ok(HttpResponse::Ok().json(ImportResult {
+                        imported: successful_monsters,
+                        skipped,
+                    }));
+                }
+                return Ok(HttpResponse::Ok().json(successful_monsters));
+            }
+        }
+    }
+
+    Ok(HttpResponse::BadRequest().json("No file uploaded"))
+}
+
+#[cfg(test)]
+mod tests {
     use super::{
-        create_monster, delete_monster_by_id, get_monster_by_id, get_monsters, import_csv,
-        update_monster_by_id,
+        add_monster_tags, batch_get_monsters, bulk_delete_monsters, bulk_update_monsters,
+        clone_monster, compare_monsters, count_monsters, create_monster, delete_monster_by_id,
+        export_csv, export_ndjson, generate_monsters, get_monster_advantage, get_monster_battles,
+        get_monster_by_id, get_monster_histogram, get_monster_names, get_monster_presets,
+        get_monster_schema, get_monsters, histogram, import_csv, level_up_monster,
+        patch_monster_by_id, remove_monster_tag, search_monsters, spar_monsters,
+        update_monster_by_id, upload_monster_image, validate_rows, BatchGetResponse,
+        BulkDeleteResponse, BulkUpdateResponse, CascadeDeleteResponse, CountResponse,
+        ElementAdvantage, FieldSchema, HistogramBucket, ImportReport, ImportResult,
+        MonsterComparison, SparResult,
+    };
+    use crate::config::AppConfig;
+    use crate::models::battle::{Battle, BattleResult, BattleStatus, MonsterBattle};
+    use crate::models::monster::{Monster, MonsterView, StringList};
+    use crate::models::pagination::DefaultPageSize;
+    use crate::repository::{battle_repository, database::Database, monster_repository};
+    use crate::utils::auth::{require_api_key, ApiKey, API_KEY_HEADER};
+    use crate::utils::disk_space::DiskSpace;
+    use crate::utils::flags::UniqueNames;
+    use crate::utils::image_hosts::ImageHosts;
+    use crate::utils::import_limit::ImportLimit;
+    use crate::utils::metrics::Metrics;
+    use crate::utils::monster_generator::random_monster;
+    use crate::utils::monster_quota::MonsterQuota;
+    use crate::utils::test_utils::{
+        build_multipart_file_payload, build_multipart_payload_and_header,
+        build_multipart_payload_with_fields, init_test_battle, init_test_monsters,
     };
-    use crate::models::monster::Monster;
-    use crate::repository::database::Database;
-    use crate::utils::test_utils::{build_multipart_payload_and_header, init_test_monsters};
-    use actix_web::{http, http::StatusCode, test, web::Data, App};
+    use crate::utils::uploads::UploadDir;
+    use actix_web::{http, http::StatusCode, middleware::from_fn, test, web::Data, App};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::time::Duration;
+    use uuid::Uuid;
 
     #[actix_rt::test]
     async fn test_should_get_all_monsters_correctly() {
         let db = Database::new();
         let app = App::new().app_data(Data::new(db)).service(get_monsters);
 
-        let mut app = test::init_service(app).await;
+        let app = test::init_service(app).await;
+
+        let req = test::TestRequest::get().uri("/monsters").to_request();
+        let resp = test::call_service(&app, req).await;
+
+        assert!(resp.status().is_success());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_list_monsters_as_json_when_json_is_accepted() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters")
+            .insert_header((http::header::ACCEPT, "application/json"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(
+            resp.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let monsters: Vec<Monster> = test::read_body_json(resp).await;
+        assert!(monsters
+            .iter()
+            .any(|monster| monster.id == test_monsters[0].id));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_list_monsters_as_csv_when_csv_is_accepted() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters?fields=id,name,attack")
+            .insert_header((http::header::ACCEPT, "text/csv, application/json;q=0.5"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "text/csv"
+        );
+        let body = test::read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        let mut lines = body.lines();
+        assert_eq!(lines.next(), Some("id,name,attack"));
+        let expected = format!("{},monster-1,40", test_monsters[0].id);
+        assert!(lines.any(|line| line == expected));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_list_the_newest_monsters_first_by_default() {
+        let db = Database::new();
+        let tag = format!("batch-{}", Uuid::new_v4());
+        let mut created = Vec::new();
+        for _ in 0..3 {
+            let monster = monster_repository::create_monster(
+                &db,
+                Monster {
+                    tags: StringList(vec![tag.clone()]),
+                    ..random_monster(&mut rand::thread_rng())
+                },
+            )
+            .unwrap();
+            created.push(monster.id);
+        }
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters?tag={}", tag).as_str())
+            .to_request();
+        let monsters: Vec<Monster> = test::call_and_read_body_json(&app, req).await;
+        let listed: Vec<String> = monsters.into_iter().map(|monster| monster.id).collect();
+        created.reverse();
+        assert_eq!(listed, created);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_answer_503_when_no_database_connection_is_free() {
+        let db = Data::new(Database::with_pool_size(
+            &AppConfig::from_env().unwrap().database_url,
+            1,
+            Duration::from_millis(100),
+        ));
+        let _held = db.get_connection().unwrap();
+        let app = App::new().app_data(db.clone()).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/monsters").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let message: String = test::read_body_json(resp).await;
+        assert_eq!(message, "Database is busy, try again later");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_page_monsters_and_link_the_next_page() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters?per_page=2")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let link = resp
+            .headers()
+            .get(http::header::LINK)
+            .and_then(|value| value.to_str().ok())
+            .expect("Link header should be set")
+            .to_string();
+        assert!(link.contains("</monsters?page=2&per_page=2>; rel=\"next\""));
+        assert!(!link.contains("rel=\"prev\""));
+        let monsters: Vec<MonsterView> = test::read_body_json(resp).await;
+        assert_eq!(monsters.len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_monsters_with_400_error_if_the_page_is_too_large() {
+        let db = Database::new();
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters?page=9223372036854775807")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_page_unparameterized_lists_by_the_default_page_size() {
+        let config = AppConfig {
+            default_page_size: DefaultPageSize::new(5),
+            ..AppConfig::default()
+        };
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(config))
+            .service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/monsters").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert!(resp.headers().contains_key(http::header::LINK));
+        let monsters: Vec<MonsterView> = test::read_body_json(resp).await;
+        assert!(!monsters.is_empty());
+        assert!(monsters.len() <= 5);
+    }
 
+    #[actix_rt::test]
+    async fn test_should_page_by_the_built_in_default_without_a_registered_config() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
         let req = test::TestRequest::get().uri("/monsters").to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let link = resp
+            .headers()
+            .get(http::header::LINK)
+            .and_then(|value| value.to_str().ok())
+            .expect("Link header should be set")
+            .to_string();
+        assert!(link.contains("per_page=20"), "{}", link);
+        let monsters: Vec<MonsterView> = test::read_body_json(resp).await;
+        assert!(monsters.len() <= 20);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_monsters_with_only_the_requested_fields() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters?fields=id,name")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let monsters: Vec<serde_json::Map<String, serde_json::Value>> =
+            test::read_body_json(resp).await;
+        assert!(!monsters.is_empty());
+        for monster in monsters {
+            let mut keys: Vec<&String> = monster.keys().collect();
+            keys.sort();
+            assert_eq!(keys, ["id", "name"]);
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_monsters_with_400_error_if_a_field_is_unknown() {
+        let db = Database::new();
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters?fields=id,password")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_export_monsters_as_ndjson() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(export_ndjson);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters/export_ndjson")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        let body = test::read_body(resp).await;
+        let exported: Vec<Monster> = std::str::from_utf8(&body)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line should be a monster"))
+            .collect();
+        for monster in &test_monsters {
+            assert!(exported.iter().any(|exported| exported.id == monster.id));
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_should_count_monsters() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(count_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/monsters/count").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: CountResponse = test::read_body_json(resp).await;
+        assert!(body.count >= 7);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_400_error_if_monster_id_is_malformed() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_by_id);
+
+        let app = test::init_service(app).await;
+
+        let req = test::TestRequest::get()
+            .uri("/monsters/999999")
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_404_error_if_monster_does_not_exists() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_by_id);
+
+        let app = test::init_service(app).await;
+
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters/{}", Uuid::new_v4()).as_str())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_a_single_monster_correctly() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_by_id);
+
+        let app = test::init_service(app).await;
+
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_304_if_the_monster_etag_matches() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_by_id);
+        let app = test::init_service(app).await;
+        let uri = format!("/monsters/{}", test_monsters[0].id);
+
+        let req = test::TestRequest::get().uri(&uri).to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let etag = resp
+            .headers()
+            .get(http::header::ETAG)
+            .expect("response should carry an ETag")
+            .clone();
+
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header((http::header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_MODIFIED);
+        assert_eq!(resp.headers().get(http::header::ETAG), Some(&etag));
+
+        let req = test::TestRequest::get()
+            .uri(&uri)
+            .insert_header((http::header::IF_NONE_MATCH, "\"stale\""))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_new_monster() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_monster);
+
+        let app = test::init_service(app).await;
+
+        let new_monster_data = Monster {
+            id: _test_monsters[0].id.clone(),
+            name: _test_monsters[0].name.clone(),
+            image_url: _test_monsters[0].image_url.clone(),
+            attack: _test_monsters[0].attack,
+            defense: _test_monsters[0].defense,
+            speed: _test_monsters[0].speed,
+            hp: _test_monsters[0].hp,
+            created_at: _test_monsters[0].created_at,
+            updated_at: _test_monsters[0].updated_at,
+            element: _test_monsters[0].element.clone(),
+            version: _test_monsters[0].version,
+            aliases: _test_monsters[0].aliases.clone(),
+            tags: _test_monsters[0].tags.clone(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
+        };
+
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(&new_monster_data)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_update_a_monster_correctly() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(update_monster_by_id);
+
+        let app = test::init_service(app).await;
+
+        let update_monster_data = Monster {
+            id: _test_monsters[0].id.clone(),
+            name: "Update name of monster".to_string(),
+            image_url: _test_monsters[0].image_url.clone(),
+            attack: _test_monsters[0].attack,
+            defense: _test_monsters[0].defense,
+            speed: _test_monsters[0].speed,
+            hp: _test_monsters[0].hp,
+            created_at: _test_monsters[0].created_at,
+            updated_at: _test_monsters[0].updated_at,
+            element: _test_monsters[0].element.clone(),
+            version: _test_monsters[0].version,
+            aliases: _test_monsters[0].aliases.clone(),
+            tags: _test_monsters[0].tags.clone(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
+        };
+        let req = test::TestRequest::put()
+            .uri(format!("/monsters/{}", _test_monsters[0].id).as_str())
+            .set_json(&update_monster_data)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::OK);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_update_a_monster_with_the_if_match_version() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(update_monster_by_id);
+        let app = test::init_service(app).await;
+        let update_monster_data = Monster {
+            name: "Versioned monster".to_string(),
+            version: 0,
+            ..test_monsters[0].clone()
+        };
+        let req = test::TestRequest::put()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .insert_header((http::header::IF_MATCH, "\"1\""))
+            .set_json(&update_monster_data)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let monster: Monster = test::read_body_json(resp).await;
+        assert_eq!(monster.name, "Versioned monster");
+        assert_eq!(monster.version, 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_update_with_409_error_if_the_version_is_stale() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(update_monster_by_id);
+        let app = test::init_service(app).await;
+        let first_edit = Monster {
+            name: "First edit".to_string(),
+            ..test_monsters[0].clone()
+        };
+        let req = test::TestRequest::put()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .set_json(&first_edit)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let second_edit = Monster {
+            name: "Second edit".to_string(),
+            ..test_monsters[0].clone()
+        };
+        let req = test::TestRequest::put()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .set_json(&second_edit)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_update_with_428_error_if_no_version_is_sent() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(update_monster_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::put()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .set_json(json!({
+                "name": "Unversioned",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::PRECONDITION_REQUIRED);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_update_with_404_error_if_monster_does_not_exists() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(update_monster_by_id);
+
+        let app = test::init_service(app).await;
+
+        let update_monster_data = Monster {
+            id: _test_monsters[0].id.clone(),
+            name: "Update name of monster".to_string(),
+            image_url: _test_monsters[0].image_url.clone(),
+            attack: _test_monsters[0].attack,
+            defense: _test_monsters[0].defense,
+            speed: _test_monsters[0].speed,
+            hp: _test_monsters[0].hp,
+            created_at: _test_monsters[0].created_at,
+            updated_at: _test_monsters[0].updated_at,
+            element: _test_monsters[0].element.clone(),
+            version: _test_monsters[0].version,
+            aliases: _test_monsters[0].aliases.clone(),
+            tags: _test_monsters[0].tags.clone(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
+        };
+        let req = test::TestRequest::put()
+            .uri(format!("/monsters/{}", Uuid::new_v4()).as_str())
+            .set_json(&update_monster_data)
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_delete_a_monster_correctly() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(delete_monster_by_id);
+
+        let app = test::init_service(app).await;
+
+        let req = test::TestRequest::delete()
+            .uri(format!("/monsters/{}", _test_monsters[0].id).as_str())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_delete_a_monster_with_its_battles_when_cascading() {
+        let db = Database::new();
+        let test_battles = init_test_battle(&db).await;
+        let loser = test_battles[0].monster_b.clone();
+        let mut deleted = test_battles[0].clone();
+        deleted.id = Uuid::new_v4().to_string();
+        deleted.winner = None;
+        deleted.result = BattleResult::Draw;
+        let deleted = battle_repository::create_battle(&db, deleted).unwrap();
+        battle_repository::delete_battle_by_id(&db, &deleted.id).unwrap();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(delete_monster_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::delete()
+            .uri(format!("/monsters/{}?cascade=true", loser).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: CascadeDeleteResponse = test::read_body_json(resp).await;
+        assert_eq!(body.battles_deleted, 2);
+        let db = Database::new();
+        assert!(
+            battle_repository::get_battle_by_id(&db, &test_battles[0].id)
+                .unwrap()
+                .is_none()
+        );
+        assert!(battle_repository::restore(&db, &deleted.id)
+            .unwrap()
+            .is_none());
+        assert!(monster_repository::get_monster_by_id(&db, &loser)
+            .unwrap()
+            .is_none());
+        let req = test::TestRequest::delete()
+            .uri(format!("/monsters/{}?cascade=true", loser).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_delete_with_404_error_if_monster_does_not_exists() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(delete_monster_by_id);
+
+        let app = test::init_service(app).await;
+
+        let req = test::TestRequest::delete()
+            .uri(format!("/monsters/{}", Uuid::new_v4()).as_str())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_delete_with_400_error_if_monster_id_is_malformed() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(delete_monster_by_id);
+
+        let app = test::init_service(app).await;
+
+        let req = test::TestRequest::delete()
+            .uri(format!("/monsters/{}", 99999).as_str())
+            .to_request();
+
+        let resp = test::call_service(&app, req).await;
+
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_monster_only_with_an_allowed_image_host() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(AppConfig {
+                image_hosts: ImageHosts::parse(Some("loremflickr.com, cdn.example.com")),
+                ..AppConfig::default()
+            }))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let monster = |image_url: &str| {
+            json!({
+                "name": "hosted monster",
+                "image_url": image_url,
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            })
+        };
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(monster("https://CDN.example.com/goblin.png"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(monster("https://evil.example.org/goblin.png"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        let message: String = test::read_body_json(resp).await;
+        assert_eq!(message, "image_url host evil.example.org is not allowed");
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(monster("not a url"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_patch_a_monster_with_400_error_if_the_image_host_is_not_allowed() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(AppConfig {
+                image_hosts: ImageHosts::parse(Some("loremflickr.com")),
+                ..AppConfig::default()
+            }))
+            .service(patch_monster_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::patch()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .set_json(json!({ "image_url": "https://evil.example.org/goblin.png" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_allow_any_image_host_when_no_hosts_are_configured() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(AppConfig {
+                image_hosts: ImageHosts::parse(Some(" , ")),
+                ..AppConfig::default()
+            }))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "anywhere monster",
+                "image_url": "https://anywhere.example.net/goblin.png",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_patch_only_the_name_of_a_monster() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(patch_monster_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::patch()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .set_json(json!({ "name": "Patched name" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let monster: Monster = test::read_body_json(resp).await;
+        assert_eq!(monster.name, "Patched name");
+        assert_eq!(monster.attack, test_monsters[0].attack);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_patch_only_the_attack_of_a_monster() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(patch_monster_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::patch()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .set_json(json!({ "attack": 99 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let monster: Monster = test::read_body_json(resp).await;
+        assert_eq!(monster.attack, 99);
+        assert_eq!(monster.name, test_monsters[0].name);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_preserve_untouched_fields_when_patching_a_monster() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(patch_monster_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::patch()
+            .uri(format!("/monsters/{}", test_monsters[1].id).as_str())
+            .set_json(json!({ "hp": 1 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let monster: Monster = test::read_body_json(resp).await;
+        assert_eq!(monster.hp, 1);
+        assert_eq!(monster.name, test_monsters[1].name);
+        assert_eq!(monster.image_url, test_monsters[1].image_url);
+        assert_eq!(monster.attack, test_monsters[1].attack);
+        assert_eq!(monster.defense, test_monsters[1].defense);
+        assert_eq!(monster.speed, test_monsters[1].speed);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_patch_with_422_error_if_attack_is_out_of_range() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(patch_monster_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::patch()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .set_json(json!({ "attack": 101 }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_apply_a_json_patch_and_reject_invalid_stats() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(patch_monster_by_id);
+        let app = test::init_service(app).await;
+        let json_patch = |operations: Value| {
+            test::TestRequest::patch()
+                .uri(format!("/monsters/{}", test_monsters[2].id).as_str())
+                .insert_header((http::header::CONTENT_TYPE, "application/json-patch+json"))
+                .set_payload(operations.to_string())
+                .to_request()
+        };
+        let req = json_patch(json!([{ "op": "replace", "path": "/attack", "value": 75 }]));
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let monster: Monster = test::read_body_json(resp).await;
+        assert_eq!(monster.attack, 75);
+        assert_eq!(monster.defense, test_monsters[2].defense);
+        assert_eq!(monster.version, test_monsters[2].version + 1);
+
+        let req = json_patch(json!([{ "op": "replace", "path": "/attack", "value": 150 }]));
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = json_patch(json!([{ "op": "test", "path": "/attack", "value": 40 }]));
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CONFLICT);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_filter_monsters_by_element() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let fire_monster = monster_repository::create_monster(
+            &db,
+            Monster {
+                element: "fire".to_string(),
+                ..test_monsters[0].clone()
+            },
+        )
+        .unwrap();
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters?element=fire")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let monsters: Vec<Monster> = test::read_body_json(resp).await;
+        assert!(monsters.iter().all(|monster| monster.element == "fire"));
+        assert!(monsters.iter().any(|monster| monster.id == fire_monster.id));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_400_error_if_element_filter_is_unknown() {
+        let db = Database::new();
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters?element=plasma")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_add_tags_lowercase_and_without_repeats() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(add_monster_tags);
+        let app = test::init_service(app).await;
+        let uri = format!("/monsters/{}/tags", test_monsters[0].id);
+        let req = test::TestRequest::post()
+            .uri(&uri)
+            .set_json(json!({ "tags": ["Boss", " favorite ", "boss"] }))
+            .to_request();
+        let monster: Monster = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            monster.tags,
+            StringList(vec!["boss".to_string(), "favorite".to_string()])
+        );
+        assert_eq!(monster.version, test_monsters[0].version + 1);
+
+        let req = test::TestRequest::post()
+            .uri(&uri)
+            .set_json(json!({ "tags": [" "] }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_remove_a_tag_ignoring_case() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let id = test_monsters[0].id.clone();
+        monster_repository::update_tags(&db, &id, |tags| {
+            tags.extend(["boss".to_string(), "starter".to_string()])
+        })
+        .unwrap();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(remove_monster_tag);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::delete()
+            .uri(format!("/monsters/{}/tags/BOSS", id).as_str())
+            .to_request();
+        let monster: Monster = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(monster.tags, StringList(vec!["starter".to_string()]));
+
+        let req = test::TestRequest::delete()
+            .uri(format!("/monsters/{}/tags/boss", Uuid::new_v4()).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_filter_monsters_by_tag() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let tag = format!("boss-{}", Uuid::new_v4());
+        monster_repository::update_tags(&db, &test_monsters[1].id, |tags| tags.push(tag.clone()))
+            .unwrap();
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters?tag={}", tag.to_uppercase()).as_str())
+            .to_request();
+        let monsters: Vec<MonsterView> = test::call_and_read_body_json(&app, req).await;
+        let ids: Vec<&str> = monsters
+            .iter()
+            .map(|view| view.monster.id.as_str())
+            .collect();
+        assert_eq!(ids, vec![test_monsters[1].id.as_str()]);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_list_each_monster_name_once() {
+        let db = Database::new();
+        let duplicated_name = format!("twin-{}", Uuid::new_v4());
+        for _ in 0..2 {
+            monster_repository::create_monster(
+                &db,
+                Monster {
+                    name: duplicated_name.clone(),
+                    ..random_monster(&mut rand::thread_rng())
+                },
+            )
+            .unwrap();
+        }
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_names);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(
+                format!(
+                    "/monsters/names?prefix={}",
+                    &duplicated_name[..12].to_uppercase()
+                )
+                .as_str(),
+            )
+            .to_request();
+        let names: Vec<String> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(names, vec![duplicated_name]);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_compare_monsters_and_mark_the_leader_of_each_stat() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(compare_monsters);
+        let app = test::init_service(app).await;
+        // monster-1 is 40/20/50/80, monster-2 is 70/20/40/40
+        let (first, second) = (&test_monsters[0].id, &test_monsters[1].id);
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters/compare?ids={},{}", first, second).as_str())
+            .to_request();
+        let comparison: MonsterComparison = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(comparison.monsters.len(), 2);
+        assert_eq!(comparison.leaders.attack, vec![second.clone()]);
+        assert_eq!(
+            comparison.leaders.defense,
+            vec![first.clone(), second.clone()]
+        );
+        assert_eq!(comparison.leaders.hp, vec![first.clone()]);
+        assert_eq!(comparison.leaders.speed, vec![first.clone()]);
+
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters/compare?ids={},{}", first, Uuid::new_v4()).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_report_the_element_advantage_both_ways() {
+        let db = Database::new();
+        let mut ids = HashMap::new();
+        for element in ["fire", "air", "neutral"] {
+            let monster = monster_repository::create_monster(
+                &db,
+                Monster {
+                    element: element.to_string(),
+                    ..random_monster(&mut rand::thread_rng())
+                },
+            )
+            .unwrap();
+            ids.insert(element, monster.id);
+        }
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_advantage);
+        let app = test::init_service(app).await;
+
+        for (monster, against, multiplier, reverse_multiplier) in [
+            ("fire", "air", 1.5, 0.5),
+            ("air", "fire", 0.5, 1.5),
+            ("fire", "neutral", 1.0, 1.0),
+        ] {
+            let req = test::TestRequest::get()
+                .uri(
+                    format!(
+                        "/monsters/{}/advantage?against={}",
+                        ids[monster], ids[against]
+                    )
+                    .as_str(),
+                )
+                .to_request();
+            let advantage: ElementAdvantage = test::call_and_read_body_json(&app, req).await;
+            assert_eq!(advantage.monster, ids[monster]);
+            assert_eq!(advantage.against, ids[against]);
+            assert_eq!(
+                advantage.multiplier, multiplier,
+                "{} vs {}",
+                monster, against
+            );
+            assert_eq!(advantage.reverse_multiplier, reverse_multiplier);
+        }
+
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters/{}/advantage", ids["fire"]).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let req = test::TestRequest::get()
+            .uri(
+                format!(
+                    "/monsters/{}/advantage?against={}",
+                    ids["fire"],
+                    Uuid::new_v4()
+                )
+                .as_str(),
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_reject_a_monster_past_the_quota() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(AppConfig {
+                monster_quota: MonsterQuota::new(Some(1)),
+                ..AppConfig::default()
+            }))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "one too many",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 40,
+                "defense": 20,
+                "hp": 50,
+                "speed": 30
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CONFLICT);
+        let message: String = test::read_body_json(resp).await;
+        assert_eq!(message, "Monster limit reached, 0 slots remain");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_find_a_monster_by_part_of_an_alias() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_monster)
+            .service(search_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "aliased monster",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 40,
+                "defense": 20,
+                "hp": 50,
+                "speed": 30,
+                "aliases": ["Sparky"]
+            }))
+            .to_request();
+        let created: Monster = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(created.aliases, StringList(vec!["Sparky".to_string()]));
+
+        let req = test::TestRequest::get()
+            .uri("/monsters/search?q=Spark")
+            .to_request();
+        let found: Vec<MonsterView> = test::call_and_read_body_json(&app, req).await;
+        assert!(found.iter().any(|view| view.monster.id == created.id));
+
+        let req = test::TestRequest::get()
+            .uri("/monsters/search?q=")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_monster_with_a_location_header() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "located monster",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let location = resp
+            .headers()
+            .get(http::header::LOCATION)
+            .expect("response should carry a Location header")
+            .to_str()
+            .unwrap()
+            .to_string();
+        let monster: Monster = test::read_body_json(resp).await;
+        assert_eq!(location, format!("/api/monsters/{}", monster.id));
+    }
+
+    async fn create_named_monster(unique_names: bool, existing: &str, requested: &str) -> u16 {
+        let db = Database::new();
+        let mut monster = init_test_monsters(&db).await.remove(0);
+        monster.name = existing.to_string();
+        monster_repository::create_monster(&db, monster).unwrap();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(AppConfig {
+                unique_names: UniqueNames::new(unique_names),
+                ..AppConfig::default()
+            }))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": requested,
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            }))
+            .to_request();
+        test::call_service(&app, req).await.status().as_u16()
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_monster_with_409_error_if_the_name_is_taken_ignoring_case() {
+        let name = format!("Dragon {}", Uuid::new_v4());
+        let status = create_named_monster(true, &name, &name.to_lowercase()).await;
+        assert_eq!(status, StatusCode::CONFLICT.as_u16());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_monster_with_a_duplicate_name_when_uniqueness_is_off() {
+        let name = format!("Dragon {}", Uuid::new_v4());
+        let status = create_named_monster(false, &name, &name.to_lowercase()).await;
+        assert_eq!(status, StatusCode::CREATED.as_u16());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_clone_a_monster_with_the_same_stats() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(clone_monster);
+        let app = test::init_service(app).await;
+        let source = &test_monsters[1];
+        let req = test::TestRequest::post()
+            .uri(format!("/monsters/{}/clone", source.id).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let clone: Monster = test::read_body_json(resp).await;
+        assert_ne!(clone.id, source.id);
+        assert_eq!(clone.name, format!("{} (copy)", source.name));
+        assert_eq!(
+            (clone.attack, clone.defense, clone.hp, clone.speed),
+            (source.attack, source.defense, source.hp, source.speed)
+        );
+        assert_eq!(clone.element, source.element);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_clone_a_monster_with_404_error_if_it_does_not_exists() {
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .app_data(Data::new(Metrics::default()))
+            .service(clone_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri(format!("/monsters/{}/clone", Uuid::new_v4()).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_level_up_a_monster_within_the_stat_cap() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(level_up_monster);
+        let app = test::init_service(app).await;
+        let source = &test_monsters[0];
+        let req = test::TestRequest::post()
+            .uri(&format!("/monsters/{}/level_up?percent=30", source.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let leveled: Value = test::read_body_json(resp).await;
+        assert_eq!(leveled["level"], 2);
+        for (stat, before) in [
+            ("attack", source.attack),
+            ("defense", source.defense),
+            ("hp", source.hp),
+            ("speed", source.speed),
+        ] {
+            let after = leveled[stat].as_i64().unwrap() as i32;
+            assert!(after > before, "{stat} should grow from {before}");
+        }
+        assert_eq!(leveled["attack"], 52);
+        assert_eq!(leveled["speed"], 104);
+
+        let req = test::TestRequest::post()
+            .uri(&format!("/monsters/{}/level_up?percent=0", source.id))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_spar_the_same_monsters_with_the_same_seed() {
+        let app = test::init_service(App::new().service(spar_monsters)).await;
+        let spar = |seed: &str| {
+            test::TestRequest::post()
+                .uri(&format!("/monsters/spar{seed}"))
+                .to_request()
+        };
+        let first: SparResult = test::call_and_read_body_json(&app, spar("?seed=7")).await;
+        let second: SparResult = test::call_and_read_body_json(&app, spar("?seed=7")).await;
+        assert_eq!(first.seed, 7);
+        assert_eq!(
+            serde_json::to_value(&first.monster_a).unwrap(),
+            serde_json::to_value(&second.monster_a).unwrap()
+        );
+        assert_eq!(first.monster_b.id, second.monster_b.id);
+        assert_eq!((first.winner, first.rounds), (second.winner, second.rounds));
+        let unseeded: SparResult = test::call_and_read_body_json(&app, spar("")).await;
+        let replayed: SparResult =
+            test::call_and_read_body_json(&app, spar(&format!("?seed={}", unseeded.seed))).await;
+        assert_eq!(unseeded.monster_a.id, replayed.monster_a.id);
+        assert_eq!(unseeded.winner, replayed.winner);
+        let db = Database::new();
+        assert!(
+            monster_repository::get_monster_by_id(&db, &first.monster_a.id)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_export_only_the_requested_csv_columns_in_order() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let tag = format!("csv-{}", Uuid::new_v4());
+        monster_repository::update_tags(&db, &test_monsters[0].id, |tags| tags.push(tag.clone()))
+            .unwrap();
+        let app = App::new().app_data(Data::new(db)).service(export_csv);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/monsters/export_csv?columns=name,attack&tag={tag}"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        assert_eq!(
+            resp.headers().get(http::header::CONTENT_TYPE).unwrap(),
+            "text/csv"
+        );
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        let mut lines = body.lines();
+        assert_eq!(lines.next(), Some("name,attack"));
+        assert_eq!(lines.next(), Some("monster-1,40"));
+        assert_eq!(lines.next(), None);
+        let req = test::TestRequest::get()
+            .uri("/monsters/export_csv?columns=name,secret")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        let message: String = test::read_body_json(resp).await;
+        assert_eq!(message, "Unknown field: secret");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_describe_monster_fields_with_their_constraints() {
+        let app = test::init_service(App::new().service(get_monster_schema)).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters/schema")
+            .to_request();
+        let fields: Vec<FieldSchema> = test::call_and_read_body_json(&app, req).await;
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|field| field.name == name)
+                .unwrap_or_else(|| panic!("{name} should be listed"))
+        };
+        let attack = field("attack");
+        assert_eq!(attack.field_type, "integer");
+        assert!(attack.required);
+        assert_eq!((attack.minimum, attack.maximum), (Some(0.0), Some(100.0)));
+        assert_eq!(field("name").field_type, "string");
+        assert!(!field("id").required);
+        assert!(field("createdAt").nullable);
+        assert_eq!(field("createdAt").format.as_deref(), Some("date-time"));
+        assert_eq!(field("tags").field_type, "array");
+        assert_eq!(fields[0].name, "id");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_monster_from_the_tank_preset() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(get_monster_presets)
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters/presets")
+            .to_request();
+        let presets: Vec<Value> = test::call_and_read_body_json(&app, req).await;
+        let names: Vec<&str> = presets
+            .iter()
+            .filter_map(|preset| preset["name"].as_str())
+            .collect();
+        assert_eq!(names, ["tank", "glass_cannon", "speedster", "balanced"]);
+        let req = test::TestRequest::post()
+            .uri("/monsters?preset=tank")
+            .set_json(json!({
+                "name": "preset tank",
+                "image_url": "https://loremflickr.com/640/480",
+                "hp": 60
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let monster: Monster = test::read_body_json(resp).await;
+        assert_eq!(
+            (monster.attack, monster.defense, monster.hp, monster.speed),
+            (30, 80, 60, 20)
+        );
+        let req = test::TestRequest::post()
+            .uri("/monsters?preset=wizard")
+            .set_json(json!({ "name": "no preset" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_record_who_created_a_monster_and_filter_by_it() {
+        let creator = format!("user-{}", Uuid::new_v4());
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(AppConfig {
+                api_key: ApiKey::with_identities(vec![(
+                    creator.clone(),
+                    "creator-key".to_string(),
+                )]),
+                ..AppConfig::default()
+            }))
+            .wrap(from_fn(require_api_key))
+            .service(create_monster)
+            .service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .insert_header((API_KEY_HEADER, "creator-key"))
+            .set_json(json!({
+                "name": "owned monster",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10,
+                "createdBy": "someone else"
+            }))
+            .to_request();
+        let created: Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(created["createdBy"], json!(creator));
+        let req = test::TestRequest::get()
+            .uri(&format!("/monsters?created_by={creator}"))
+            .to_request();
+        let listed: Vec<Value> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0]["id"], created["id"]);
+        assert_eq!(listed[0]["createdBy"], json!(creator));
+
+        let open_app = App::new()
+            .app_data(Data::new(Database::new()))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_monster);
+        let open_app = test::init_service(open_app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "unowned monster",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            }))
+            .to_request();
+        let created: Value = test::call_and_read_body_json(&open_app, req).await;
+        assert_eq!(created["createdBy"], Value::Null);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_monster_with_a_trimmed_name() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "  Dragon  ",
+                "image_url": " https://loremflickr.com/640/480 ",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let created: Monster = test::read_body_json(resp).await;
+        let db = Database::new();
+        let stored = monster_repository::get_monster_by_id(&db, &created.id)
+            .unwrap()
+            .expect("monster should be stored");
+        assert_eq!(stored.name, "Dragon");
+        assert_eq!(stored.image_url, "https://loremflickr.com/640/480");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_monster_with_neutral_element_by_default() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "elementless",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let monster: Monster = test::read_body_json(resp).await;
+        assert_eq!(monster.element, "neutral");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_with_422_error_naming_the_out_of_range_stat() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "overpowered monster",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 150,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let message: String = test::read_body_json(resp).await;
+        assert_eq!(message, "Invalid data: attack (range)");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_reject_a_lopsided_tank_only_under_the_configured_stat_spread() {
+        let tank = json!({
+            "name": "lopsided tank",
+            "image_url": "https://loremflickr.com/640/480",
+            "attack": 10,
+            "defense": 95,
+            "hp": 10,
+            "speed": 10
+        });
+        let limited = test::init_service(
+            App::new()
+                .app_data(Data::new(Database::new()))
+                .app_data(Data::new(Metrics::default()))
+                .app_data(Data::new(AppConfig {
+                    max_stat_spread: Some(50),
+                    ..AppConfig::default()
+                }))
+                .service(create_monster),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(&tank)
+            .to_request();
+        let resp = test::call_service(&limited, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let message: String = test::read_body_json(resp).await;
+        assert_eq!(message, "Invalid data: stat_spread");
+
+        let unlimited = test::init_service(
+            App::new()
+                .app_data(Data::new(Database::new()))
+                .app_data(Data::new(Metrics::default()))
+                .service(create_monster),
+        )
+        .await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(&tank)
+            .to_request();
+        let resp = test::call_service(&unlimited, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_patch_with_422_error_if_the_patched_stats_break_the_stat_spread() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(AppConfig {
+                max_stat_spread: Some(50),
+                ..AppConfig::default()
+            }))
+            .service(patch_monster_by_id);
+        let app = test::init_service(app).await;
+        let defense = test_monsters[0].attack + 51;
+        let req = test::TestRequest::patch()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .set_json(json!({ "defense": defense }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let message: String = test::read_body_json(resp).await;
+        assert_eq!(message, "Invalid data: stat_spread");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_with_422_error_if_element_is_unknown() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_monster);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "plasma monster",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10,
+                "element": "plasma"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_update_with_422_error_if_element_is_unknown() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(update_monster_by_id);
+        let app = test::init_service(app).await;
+        let update_monster_data = Monster {
+            element: "plasma".to_string(),
+            ..test_monsters[0].clone()
+        };
+        let req = test::TestRequest::put()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .set_json(&update_monster_data)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_import_all_the_csv_objects_into_the_database_successfully() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let file_contents = "name,attack,defense,hp,speed,image_url\r\n
+        insect rabbit,82,45,66,42,https://loremflickr.com/640/480";
+        let (payload, content_type_header) =
+            build_multipart_payload_and_header("monsters-correct.csv", file_contents);
+        let request = test::TestRequest::post()
+            .uri("/monsters/import_csv")
+            .insert_header(content_type_header)
+            .set_payload(payload)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        let status = response.status();
+        let body_bytes = test::read_body(response).await;
+        let res: Result<Vec<Monster>, _> = serde_json::from_slice(&body_bytes);
+        //let res = String::from_utf8(test::read_body(response).await.to_vec()).expect("Error al convertir a String");
+        assert!(status == StatusCode::OK);
+        assert!(res.is_ok());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_import_with_507_error_if_the_disk_is_too_full() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .app_data(Data::new(AppConfig {
+                disk_space: DiskSpace::new(u64::MAX),
+                ..AppConfig::default()
+            }))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let (payload, content_type_header) = build_multipart_payload_and_header(
+            "monsters-full-disk.csv",
+            "name,attack,defense,hp,speed,image_url\r\nfull disk,1,1,1,1,https://loremflickr.com/640/480",
+        );
+        let request = test::TestRequest::post()
+            .uri("/monsters/import_csv")
+            .insert_header(content_type_header)
+            .set_payload(payload)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+        let message: String = test::read_body_json(response).await;
+        assert!(
+            message.starts_with("Not enough disk space to import"),
+            "{message}"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_import_with_422_error_if_a_row_breaks_a_rule() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let file_contents = "name,attack,defense,hp,speed,image_url\r\n\
+        fair rabbit,82,45,66,42,https://loremflickr.com/640/480\r\n\
+        unfair rabbit,150,45,66,42,https://loremflickr.com/640/480";
+        let (payload, content_type_header) =
+            build_multipart_payload_and_header("monsters-invalid.csv", file_contents);
+        let request = test::TestRequest::post()
+            .uri("/monsters/import_csv")
+            .insert_header(content_type_header)
+            .set_payload(payload)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let message: String = test::read_body_json(response).await;
+        assert_eq!(message, "Invalid data in row 2: attack (range)");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_fail_when_importing_csv_file_with_inexistent_columns() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let file_contents = "name,attack,defense,hp,speed,image_url\r\n
+        insect rabbit,82,45,66,https://loremflickr.com/640/480";
+        let (payload, content_type_header) =
+            build_multipart_payload_and_header("monsters-correct.csv", file_contents);
+        let request = test::TestRequest::post()
+            .uri("/monsters/import_csv")
+            .insert_header(content_type_header)
+            .set_payload(payload)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        let status = response.status();
+        let body_bytes = test::read_body(response).await;
+        let res: Result<Vec<Monster>, _> = serde_json::from_slice(&body_bytes);
+        assert!(res.is_err());
+        assert!(status == StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_import_with_400_error_if_the_header_is_unreadable() {
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let (payload, content_type_header) = build_multipart_file_payload(
+            "monsters.csv",
+            "text/csv",
+            b"name,\xff\xfeattack\nrabbit,10\n",
+        );
+        let request = test::TestRequest::post()
+            .uri("/monsters/import_csv")
+            .insert_header(content_type_header)
+            .set_payload(payload)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let message: String = test::read_body_json(response).await;
+        assert_eq!(message, "Unreadable CSV file");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_429_when_every_import_slot_is_taken() {
+        let db = Database::new();
+        let import_limit = Data::new(ImportLimit::new(1));
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(import_limit.clone())
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let _held_permit = import_limit.try_acquire().expect("slot should be free");
+        let file_contents = "name,attack,defense,hp,speed,image_url\r\n
+        insect rabbit,82,45,66,42,https://loremflickr.com/640/480";
+        let (payload, content_type_header) =
+            build_multipart_payload_and_header("monsters-correct.csv", file_contents);
+        let request = test::TestRequest::post()
+            .uri("/monsters/import_csv")
+            .insert_header(content_type_header)
+            .set_payload(payload)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_validate_a_csv_file_without_importing_it_on_dry_run() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let monster_name = format!("dry run {}", Uuid::new_v4());
+        let file_contents = format!(
+            "name,attack,defense,hp,speed,image_url\r\n\
+            {monster_name},82,45,66,42,https://loremflickr.com/640/480\r\n\
+            too strong,182,45,66,42,https://loremflickr.com/640/480\r\n\
+            missing speed,82,45,66,,https://loremflickr.com/640/480"
+        );
+        let (payload, content_type_header) =
+            build_multipart_payload_and_header("monsters.csv", &file_contents);
+        let request = test::TestRequest::post()
+            .uri("/monsters/import_csv?dry_run=true")
+            .insert_header(content_type_header)
+            .set_payload(payload)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let report: ImportReport = test::read_body_json(response).await;
+        assert_eq!(report.valid, 1);
+        assert_eq!(report.errors.len(), 2);
+        assert!(report.errors[0].error.contains("attack"));
 
-        assert!(resp.status().is_success());
+        let db = Database::new();
+        let stored = monster_repository::get_monsters(&db, &Default::default()).unwrap();
+        assert!(!stored.iter().any(|monster| monster.name == monster_name));
     }
 
     #[actix_rt::test]
-    async fn test_should_get_404_error_if_monster_does_not_exists() {
+    async fn test_should_generate_random_monsters() {
         let db = Database::new();
         let app = App::new()
             .app_data(Data::new(db))
-            .service(get_monster_by_id);
+            .app_data(Data::new(Metrics::default()))
+            .service(generate_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters/generate?count=5")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let monsters: Vec<Monster> = test::read_body_json(resp).await;
+        assert_eq!(monsters.len(), 5);
+        assert!(monsters.iter().all(|monster| !monster.id.is_empty()));
+    }
 
-        let mut app = test::init_service(app).await;
+    #[actix_rt::test]
+    async fn test_should_generate_with_400_error_if_count_is_over_the_cap() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(generate_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters/generate?count=101")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
 
+    #[actix_rt::test]
+    async fn test_should_get_the_battle_history_of_a_monster_with_win_flags() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let new_battle = |opponent: &Monster, winner: &Monster| Battle {
+            id: String::new(),
+            monster_a: test_monsters[0].id.clone(),
+            monster_b: opponent.id.clone(),
+            winner: Some(winner.id.clone()),
+            created_at: None,
+            updated_at: None,
+            winner_remaining_hp: 0,
+            rounds: 0,
+            deleted_at: None,
+            log: None,
+            result: BattleResult::Win,
+            status: BattleStatus::Completed,
+        };
+        let won =
+            battle_repository::create_battle(&db, new_battle(&test_monsters[1], &test_monsters[0]))
+                .unwrap();
+        let lost =
+            battle_repository::create_battle(&db, new_battle(&test_monsters[2], &test_monsters[2]))
+                .unwrap();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_battles);
+        let app = test::init_service(app).await;
         let req = test::TestRequest::get()
-            .uri("/monsters/999999")
+            .uri(format!("/monsters/{}/battles", test_monsters[0].id).as_str())
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let history: Vec<MonsterBattle> = test::read_body_json(resp).await;
+        assert_eq!(history.len(), 2);
+        let won_entry = history.iter().find(|entry| entry.battle.id == won.id);
+        let lost_entry = history.iter().find(|entry| entry.battle.id == lost.id);
+        assert!(won_entry.expect("won battle should be listed").won);
+        assert!(!lost_entry.expect("lost battle should be listed").won);
 
-        let resp = test::call_service(&mut app, req).await;
+        let req = test::TestRequest::get()
+            .uri(
+                format!(
+                    "/monsters/{}/battles?per_page=1&page=2",
+                    test_monsters[0].id
+                )
+                .as_str(),
+            )
+            .to_request();
+        let history: Vec<MonsterBattle> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(history.len(), 1);
+    }
 
+    #[actix_rt::test]
+    async fn test_should_get_the_battle_history_with_404_error_if_monster_does_not_exists() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_battles);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters/{}/battles", Uuid::new_v4()).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
     }
 
     #[actix_rt::test]
-    async fn test_should_get_a_single_monster_correctly() {
-        let mut db = Database::new();
-        let test_monsters = init_test_monsters(&mut db).await;
-
+    async fn test_should_get_a_monster_with_its_battle_record() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        for (opponent, winner) in [(1, 0), (2, 0), (3, 3)] {
+            battle_repository::create_battle(
+                &db,
+                Battle {
+                    id: String::new(),
+                    monster_a: test_monsters[0].id.clone(),
+                    monster_b: test_monsters[opponent].id.clone(),
+                    winner: Some(test_monsters[winner].id.clone()),
+                    created_at: None,
+                    updated_at: None,
+                    winner_remaining_hp: 0,
+                    rounds: 0,
+                    deleted_at: None,
+                    log: None,
+                    result: BattleResult::Win,
+                    status: BattleStatus::Completed,
+                },
+            )
+            .unwrap();
+        }
         let app = App::new()
             .app_data(Data::new(db))
             .service(get_monster_by_id);
-
-        let mut app = test::init_service(app).await;
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters/{}?include_record=true", test_monsters[0].id).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: MonsterView = test::read_body_json(resp).await;
+        assert_eq!(body.monster.id, test_monsters[0].id);
+        let record = body.record.expect("record should be included");
+        assert_eq!(record.wins, 2);
+        assert_eq!(record.losses, 1);
+        assert_eq!(record.draws, 0);
 
         let req = test::TestRequest::get()
             .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
             .to_request();
-
-        let resp = test::call_service(&mut app, req).await;
-
-        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert!(body.get("record").is_none());
     }
 
     #[actix_rt::test]
-    async fn test_should_create_a_new_monster() {
-        let mut db = Database::new();
-        let _test_monsters = init_test_monsters(&mut db).await;
-
-        let app = App::new().app_data(Data::new(db)).service(create_monster);
-
-        let mut app = test::init_service(app).await;
-
-        let new_monster_data = Monster {
-            id: _test_monsters[0].id.clone(),
-            name: _test_monsters[0].name.clone(),
-            image_url: _test_monsters[0].image_url.clone(),
-            attack: _test_monsters[0].attack.clone(),
-            defense: _test_monsters[0].defense.clone(),
-            speed: _test_monsters[0].speed.clone(),
-            hp: _test_monsters[0].hp.clone(),
-            created_at: _test_monsters[0].created_at.clone(),
-            updated_at: _test_monsters[0].updated_at.clone(),
-        };
-
+    async fn test_should_batch_get_existing_monsters_and_report_missing_ones() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let missing_id = Uuid::new_v4().to_string();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(batch_get_monsters);
+        let app = test::init_service(app).await;
         let req = test::TestRequest::post()
-            .uri("/monsters")
-            .set_json(&new_monster_data)
+            .uri("/monsters/batch_get")
+            .set_json(json!({
+                "ids": [test_monsters[2].id, missing_id, test_monsters[0].id]
+            }))
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: BatchGetResponse = test::read_body_json(resp).await;
+        let found: Vec<&str> = body
+            .monsters
+            .iter()
+            .map(|monster| monster.id.as_str())
+            .collect();
+        assert_eq!(
+            found,
+            vec![test_monsters[2].id.as_str(), test_monsters[0].id.as_str()]
+        );
+        assert_eq!(body.not_found, vec![missing_id]);
 
-        let resp = test::call_service(&mut app, req).await;
-
-        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let too_many: Vec<String> = (0..101).map(|_| Uuid::new_v4().to_string()).collect();
+        let req = test::TestRequest::post()
+            .uri("/monsters/batch_get")
+            .set_json(json!({ "ids": too_many }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
     }
 
     #[actix_rt::test]
-    async fn test_should_update_a_monster_correctly() {
-        let mut db = Database::new();
-        let _test_monsters = init_test_monsters(&mut db).await;
-
+    async fn test_should_bulk_delete_existing_monsters_and_report_missing_ones() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let missing_id = Uuid::new_v4().to_string();
         let app = App::new()
             .app_data(Data::new(db))
-            .service(update_monster_by_id);
-
-        let mut app = test::init_service(app).await;
-
-        let update_monster_data = Monster {
-            id: _test_monsters[0].id.clone(),
-            name: "Update name of monster".to_string(),
-            image_url: _test_monsters[0].image_url.clone(),
-            attack: _test_monsters[0].attack.clone(),
-            defense: _test_monsters[0].defense.clone(),
-            speed: _test_monsters[0].speed.clone(),
-            hp: _test_monsters[0].hp.clone(),
-            created_at: _test_monsters[0].created_at.clone(),
-            updated_at: _test_monsters[0].updated_at.clone(),
-        };
-        let req = test::TestRequest::put()
-            .uri(format!("/monsters/{}", _test_monsters[0].id).as_str())
-            .set_json(&update_monster_data)
+            .service(bulk_delete_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters/bulk_delete")
+            .set_json(json!({
+                "ids": [test_monsters[0].id, missing_id, test_monsters[1].id]
+            }))
             .to_request();
-
-        let resp = test::call_service(&mut app, req).await;
-
+        let resp = test::call_service(&app, req).await;
         assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: BulkDeleteResponse = test::read_body_json(resp).await;
+        assert_eq!(body.deleted, 2);
+        assert_eq!(body.not_found, vec![missing_id]);
     }
 
     #[actix_rt::test]
-    async fn test_should_update_with_404_error_if_monster_does_not_exists() {
-        let mut db = Database::new();
-        let _test_monsters = init_test_monsters(&mut db).await;
+    async fn test_should_bulk_delete_with_400_error_if_an_id_is_malformed() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .service(bulk_delete_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters/bulk_delete")
+            .set_json(json!({ "ids": [test_monsters[0].id, "999999"] }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+        assert!(
+            monster_repository::get_monster_by_id(&db, &test_monsters[0].id)
+                .unwrap()
+                .is_some()
+        );
+    }
 
+    #[actix_rt::test]
+    async fn test_should_bulk_update_the_attack_of_every_listed_monster() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let missing_id = Uuid::new_v4().to_string();
         let app = App::new()
             .app_data(Data::new(db))
-            .service(update_monster_by_id);
+            .service(bulk_update_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters/bulk_update")
+            .set_json(json!({
+                "ids": [test_monsters[0].id, test_monsters[1].id, missing_id],
+                "changes": { "attack": 5 }
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: BulkUpdateResponse = test::read_body_json(resp).await;
+        assert_eq!(body.not_found, vec![missing_id]);
+        for original in &test_monsters[..2] {
+            let updated = body
+                .updated
+                .iter()
+                .find(|monster| monster.id == original.id)
+                .expect("monster should be updated");
+            assert_eq!(updated.attack, original.attack + 5);
+            assert_eq!(updated.defense, original.defense);
+            assert_eq!(updated.version, original.version + 1);
+        }
+    }
 
-        let mut app = test::init_service(app).await;
+    #[actix_rt::test]
+    async fn test_should_bulk_update_nothing_if_one_monster_would_be_out_of_range() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .service(bulk_update_monsters);
+        let app = test::init_service(app).await;
+        // monster-1 has 40 attack, monster-2 has 70
+        let req = test::TestRequest::post()
+            .uri("/monsters/bulk_update")
+            .set_json(json!({
+                "ids": [test_monsters[0].id, test_monsters[1].id],
+                "changes": { "attack": 40 }
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let unchanged = monster_repository::get_monster_by_id(&db, &test_monsters[0].id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(unchanged.attack, test_monsters[0].attack);
+    }
 
-        let update_monster_data = Monster {
-            id: _test_monsters[0].id.clone(),
-            name: "Update name of monster".to_string(),
-            image_url: _test_monsters[0].image_url.clone(),
-            attack: _test_monsters[0].attack.clone(),
-            defense: _test_monsters[0].defense.clone(),
-            speed: _test_monsters[0].speed.clone(),
-            hp: _test_monsters[0].hp.clone(),
-            created_at: _test_monsters[0].created_at.clone(),
-            updated_at: _test_monsters[0].updated_at.clone(),
-        };
-        let req = test::TestRequest::put()
-            .uri(format!("/monsters/{}", 99999).as_str())
-            .set_json(&update_monster_data)
+    #[actix_rt::test]
+    async fn test_should_bulk_update_with_422_error_if_a_stat_would_overflow() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(Database::new()))
+            .service(bulk_update_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters/bulk_update")
+            .set_json(json!({
+                "ids": [test_monsters[0].id],
+                "changes": { "hp": i32::MAX }
+            }))
             .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let unchanged = monster_repository::get_monster_by_id(&db, &test_monsters[0].id)
+            .unwrap()
+            .unwrap();
+        assert_eq!(unchanged.hp, test_monsters[0].hp);
+    }
 
-        let resp = test::call_service(&mut app, req).await;
+    const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
 
-        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    #[actix_rt::test]
+    async fn test_should_upload_a_monster_image() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let upload_dir = tempfile::tempdir().unwrap();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(AppConfig {
+                upload_dir: UploadDir::new(upload_dir.path()),
+                ..AppConfig::default()
+            }))
+            .service(upload_monster_image);
+        let app = test::init_service(app).await;
+        let (payload, content_type_header) =
+            build_multipart_file_payload("avatar.png", "image/png", &PNG_SIGNATURE);
+        let request = test::TestRequest::post()
+            .uri(format!("/monsters/{}/image", test_monsters[0].id).as_str())
+            .insert_header(content_type_header)
+            .set_payload(payload)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let monster: Monster = test::read_body_json(response).await;
+        assert_ne!(monster.image_url, test_monsters[0].image_url);
+        assert_eq!(
+            monster.image_url,
+            format!("/uploads/{}.png", test_monsters[0].id)
+        );
+        let stored = std::fs::read(upload_dir.path().join(format!("{}.png", monster.id))).unwrap();
+        assert_eq!(stored, PNG_SIGNATURE);
     }
 
     #[actix_rt::test]
-    async fn test_should_delete_a_monster_correctly() {
-        let mut db = Database::new();
-        let _test_monsters = init_test_monsters(&mut db).await;
-
+    async fn test_should_upload_a_monster_image_with_400_error_if_it_is_not_png_or_jpeg() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let upload_dir = tempfile::tempdir().unwrap();
         let app = App::new()
             .app_data(Data::new(db))
-            .service(delete_monster_by_id);
-
-        let mut app = test::init_service(app).await;
-
-        let req = test::TestRequest::delete()
-            .uri(format!("/monsters/{}", _test_monsters[0].id).as_str())
+            .app_data(Data::new(AppConfig {
+                upload_dir: UploadDir::new(upload_dir.path()),
+                ..AppConfig::default()
+            }))
+            .service(upload_monster_image);
+        let app = test::init_service(app).await;
+        let (payload, content_type_header) =
+            build_multipart_file_payload("avatar.gif", "image/gif", b"GIF89a");
+        let request = test::TestRequest::post()
+            .uri(format!("/monsters/{}/image", test_monsters[0].id).as_str())
+            .insert_header(content_type_header)
+            .set_payload(payload)
             .to_request();
-
-        let resp = test::call_service(&mut app, req).await;
-
-        assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
     #[actix_rt::test]
-    async fn test_should_delete_with_404_error_if_monster_does_not_exists() {
-        let mut db = Database::new();
-        let _test_monsters = init_test_monsters(&mut db).await;
-
+    async fn test_should_skip_existing_names_when_importing_with_dedupe() {
+        let db = Database::new();
         let app = App::new()
             .app_data(Data::new(db))
-            .service(delete_monster_by_id);
-
-        let mut app = test::init_service(app).await;
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let name = format!("dedupe {}", Uuid::new_v4());
+        let file_contents = format!(
+            "name,attack,defense,hp,speed,image_url\r\n{},82,45,66,42,https://loremflickr.com/640/480",
+            name
+        );
+        for pass in 0..2 {
+            let (payload, content_type_header) =
+                build_multipart_payload_and_header("monsters-dedupe.csv", &file_contents);
+            let request = test::TestRequest::post()
+                .uri("/monsters/import_csv?dedupe_by=name")
+                .insert_header(content_type_header)
+                .set_payload(payload)
+                .to_request();
+            let response = test::call_service(&app, request).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let result: ImportResult = test::read_body_json(response).await;
+            if pass == 0 {
+                assert_eq!(result.imported.len(), 1);
+                assert!(result.skipped.is_empty());
+            } else {
+                assert!(result.imported.is_empty());
+                assert_eq!(result.skipped, vec![name.clone()]);
+            }
+        }
+        let db = Database::new();
+        let stored = monster_repository::get_monsters(&db, &Default::default())
+            .unwrap()
+            .into_iter()
+            .filter(|monster| monster.name == name)
+            .count();
+        assert_eq!(stored, 1);
+    }
 
-        let req = test::TestRequest::delete()
-            .uri(format!("/monsters/{}", 99999).as_str())
+    #[actix_rt::test]
+    async fn test_should_save_nothing_when_an_atomic_import_has_a_failing_row() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let name = format!("atomic {}", Uuid::new_v4());
+        // postgres rejects NUL bytes in text, so the second row fails on insert
+        let file_contents = format!(
+            "name,attack,defense,hp,speed,image_url\r\n\
+            {name},82,45,66,42,https://loremflickr.com/640/480\r\n\
+            broken\u{0}{name},82,45,66,42,https://loremflickr.com/640/480"
+        );
+        let (payload, content_type_header) =
+            build_multipart_payload_and_header("monsters-atomic.csv", &file_contents);
+        let request = test::TestRequest::post()
+            .uri("/monsters/import_csv?atomic=true")
+            .insert_header(content_type_header)
+            .set_payload(payload)
             .to_request();
-
-        let resp = test::call_service(&mut app, req).await;
-
-        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let db = Database::new();
+        let stored = monster_repository::get_monsters(&db, &Default::default())
+            .unwrap()
+            .into_iter()
+            .filter(|monster| monster.name.ends_with(&name))
+            .count();
+        assert_eq!(stored, 0);
     }
 
     #[actix_rt::test]
-    async fn test_should_import_all_the_csv_objects_into_the_database_successfully() {
+    async fn test_should_fill_missing_stats_when_importing_with_defaults() {
         let db = Database::new();
-        let app = App::new().app_data(Data::new(db)).service(import_csv);
-        let mut app = test::init_service(app).await;
-        let file_contents = "name,attack,defense,hp,speed,image_url\r\n
-        insect rabbit,82,45,66,42,https://loremflickr.com/640/480";
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let file_contents = "name,attack\r\nsparse rabbit,82";
         let (payload, content_type_header) =
-            build_multipart_payload_and_header("monsters-correct.csv", file_contents);
+            build_multipart_payload_and_header("monsters-sparse.csv", file_contents);
+        let request = test::TestRequest::post()
+            .uri("/monsters/import_csv?defaults=true")
+            .insert_header(content_type_header)
+            .set_payload(payload)
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let monsters: Vec<Monster> = test::read_body_json(response).await;
+        assert_eq!(monsters.len(), 1);
+        assert_eq!(monsters[0].name, "sparse rabbit");
+        assert_eq!(monsters[0].attack, 82);
+        assert_eq!(
+            (monsters[0].defense, monsters[0].hp, monsters[0].speed),
+            (50, 50, 50)
+        );
+        assert_eq!(monsters[0].element, "neutral");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_import_a_csv_with_a_column_mapping() {
+        let db = Database::new();
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let file_contents = "name,attack,defense,hit_points,speed,picture\r\n\
+        mapped rabbit,82,45,66,42,https://loremflickr.com/640/480";
+        let (payload, content_type_header) = build_multipart_payload_with_fields(
+            "monsters-mapped.csv",
+            file_contents,
+            &[("mapping", r#"{"hit_points": "hp", "picture": "image_url"}"#)],
+        );
         let request = test::TestRequest::post()
             .uri("/monsters/import_csv")
             .insert_header(content_type_header)
             .set_payload(payload)
             .to_request();
-        let response = test::call_service(&mut app, request).await;
-        let status = response.status();
-        let body_bytes = test::read_body(response).await;
-        let res: Result<Vec<Monster>, _> = serde_json::from_slice(&body_bytes);
-        //let res = String::from_utf8(test::read_body(response).await.to_vec()).expect("Error al convertir a String");
-        assert!(status == StatusCode::OK);
-        assert!(res.is_ok());
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let monsters: Vec<Monster> = test::read_body_json(response).await;
+        assert_eq!(monsters.len(), 1);
+        assert_eq!(monsters[0].hp, 66);
+        assert_eq!(monsters[0].image_url, "https://loremflickr.com/640/480");
     }
 
     #[actix_rt::test]
-    async fn test_should_fail_when_importing_csv_file_with_inexistent_columns() {
+    async fn test_should_import_with_400_error_if_the_mapping_targets_an_unknown_field() {
         let db = Database::new();
-        let app = App::new().app_data(Data::new(db)).service(import_csv);
-        let mut app = test::init_service(app).await;
-        let file_contents = "name,attack,defense,hp,speed,image_url\r\n
-        insect rabbit,82,45,66,https://loremflickr.com/640/480";
-        let (payload, content_type_header) =
-            build_multipart_payload_and_header("monsters-correct.csv", file_contents);
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .app_data(Data::new(ImportLimit::default()))
+            .service(import_csv);
+        let app = test::init_service(app).await;
+        let (payload, content_type_header) = build_multipart_payload_with_fields(
+            "monsters-mapped.csv",
+            "name,attack,defense,hit_points,speed,image_url",
+            &[("mapping", r#"{"hit_points": "health"}"#)],
+        );
         let request = test::TestRequest::post()
             .uri("/monsters/import_csv")
             .insert_header(content_type_header)
             .set_payload(payload)
             .to_request();
-        let response = test::call_service(&mut app, request).await;
-        let status = response.status();
-        let body_bytes = test::read_body(response).await;
-        let res: Result<Vec<Monster>, _> = serde_json::from_slice(&body_bytes);
-        assert!(res.is_err());
-        assert!(status == StatusCode::BAD_REQUEST);
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_a_monster_with_its_power() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_by_id);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters/{}", test_monsters[0].id).as_str())
+            .to_request();
+        let body: MonsterView = test::call_and_read_body_json(&app, req).await;
+        // 40 attack + 20 defense + 50 / 10 hp + 80 speed
+        assert_eq!(
+            (
+                body.monster.attack,
+                body.monster.defense,
+                body.monster.hp,
+                body.monster.speed
+            ),
+            (40, 20, 50, 80)
+        );
+        assert_eq!(body.power, 145);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_get_monsters_sorted_by_power() {
+        let db = Database::new();
+        let _test_monsters = init_test_monsters(&db).await;
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/monsters?sort_by=power")
+            .to_request();
+        let monsters: Vec<MonsterView> = test::call_and_read_body_json(&app, req).await;
+        assert!(!monsters.is_empty());
+        assert!(monsters
+            .iter()
+            .all(|view| view.power == view.monster.power()));
+        assert!(monsters
+            .windows(2)
+            .all(|pair| pair[0].power >= pair[1].power));
+
+        let req = test::TestRequest::get()
+            .uri("/monsters?sort_by=speed")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_sort_before_paging_and_link_the_last_page_from_the_count() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let tag = uuid::Uuid::new_v4().to_string();
+        let tagged: Vec<Monster> = [10, 50, 30, 20, 40]
+            .into_iter()
+            .map(|attack| Monster {
+                attack,
+                tags: StringList(vec![tag.clone()]),
+                ..test_monsters[0].clone()
+            })
+            .collect();
+        monster_repository::create_monsters(&db, tagged).unwrap();
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters?tag={}&sort_by=power&page=2&per_page=2", tag).as_str())
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let link = resp
+            .headers()
+            .get(http::header::LINK)
+            .and_then(|value| value.to_str().ok())
+            .expect("Link header should be set")
+            .to_string();
+        assert!(
+            link.contains("page=3&per_page=2>; rel=\"last\""),
+            "{}",
+            link
+        );
+        let monsters: Vec<MonsterView> = test::read_body_json(resp).await;
+        let attacks: Vec<i32> = monsters.iter().map(|view| view.monster.attack).collect();
+        assert_eq!(attacks, vec![30, 20]);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_list_and_sort_monsters_with_extreme_stats_by_power() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let tag = uuid::Uuid::new_v4().to_string();
+        let tagged: Vec<Monster> = [i32::MIN, 10, i32::MAX]
+            .into_iter()
+            .map(|stat| Monster {
+                defense: stat,
+                hp: stat,
+                speed: stat,
+                tags: StringList(vec![tag.clone()]),
+                ..test_monsters[0].clone()
+            })
+            .collect();
+        monster_repository::create_monsters(&db, tagged).unwrap();
+        let app = App::new().app_data(Data::new(db)).service(get_monsters);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(format!("/monsters?tag={}&sort_by=power", tag).as_str())
+            .to_request();
+        let monsters: Vec<MonsterView> = test::call_and_read_body_json(&app, req).await;
+        let defenses: Vec<i32> = monsters.iter().map(|view| view.monster.defense).collect();
+        assert_eq!(defenses, vec![i32::MAX, 10, i32::MIN]);
+        assert!(monsters
+            .iter()
+            .all(|view| view.power == view.monster.power()));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_report_valid_and_invalid_rows_without_a_file() {
+        let app = test::init_service(App::new().service(validate_rows)).await;
+        let req = test::TestRequest::post()
+            .uri("/monsters/validate_rows")
+            .set_json(json!([
+                {
+                    "name": "typed monster",
+                    "image_url": "https://loremflickr.com/640/480",
+                    "attack": "40",
+                    "defense": "20",
+                    "hp": "50",
+                    "speed": "80"
+                },
+                {
+                    "name": "typo monster",
+                    "image_url": "https://loremflickr.com/640/480",
+                    "attack": "4o",
+                    "defense": "20",
+                    "hp": "50",
+                    "speed": "80"
+                }
+            ]))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let report: ImportReport = test::read_body_json(resp).await;
+        assert_eq!(report.valid, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].line, 2);
+        assert!(report.errors[0].error.contains("attack"));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_bucket_extreme_and_negative_stats_without_overflowing() {
+        let buckets = histogram(&[-2_000_000_000, 0, 2_000_000_000, i32::MAX, i32::MIN], 4);
+        assert_eq!(buckets.len(), 4);
+        assert_eq!(buckets[0].min, i32::MIN as i64);
+        assert!(buckets[3].max >= i32::MAX as i64);
+        assert_eq!(
+            buckets
+                .iter()
+                .map(|bucket| bucket.count)
+                .collect::<Vec<_>>(),
+            vec![2, 0, 1, 2]
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_bucket_a_stat_into_a_histogram() {
+        let db = Database::new();
+        let tag = format!("histogram-{}", Uuid::new_v4());
+        for attack in [10, 20, 30, 40, 90] {
+            monster_repository::create_monster(
+                &db,
+                Monster {
+                    attack,
+                    tags: StringList(vec![tag.clone()]),
+                    ..random_monster(&mut rand::thread_rng())
+                },
+            )
+            .unwrap();
+        }
+        let app = App::new()
+            .app_data(Data::new(db))
+            .service(get_monster_histogram);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri(&format!(
+                "/monsters/histogram?stat=attack&buckets=4&tag={tag}"
+            ))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let histogram: Vec<HistogramBucket> = test::read_body_json(resp).await;
+        let bucket = |min, max, count| HistogramBucket { min, max, count };
+        assert_eq!(
+            histogram,
+            vec![
+                bucket(10, 30, 3),
+                bucket(31, 51, 1),
+                bucket(52, 72, 0),
+                bucket(73, 93, 1)
+            ]
+        );
+
+        for query in [
+            "stat=luck",
+            "stat=attack&buckets=1",
+            "stat=attack&buckets=51",
+        ] {
+            let req = test::TestRequest::get()
+                .uri(&format!("/monsters/histogram?{query}"))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::BAD_REQUEST, "{query}");
+        }
     }
 }
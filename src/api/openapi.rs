@@ -0,0 +1,80 @@
+use super::{admin_apis, battle_apis, monster_apis, tournament_apis};
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+#[derive(OpenApi)]
+#[openapi(
+    servers((url = "/api")),
+    paths(
+        monster_apis::get_monsters,
+        monster_apis::count_monsters,
+        monster_apis::search_monsters,
+        monster_apis::get_monster_names,
+        monster_apis::compare_monsters,
+        monster_apis::get_monster_presets,
+        monster_apis::get_monster_schema,
+        monster_apis::get_monster_histogram,
+        monster_apis::export_csv,
+        monster_apis::export_ndjson,
+        monster_apis::create_monster,
+        monster_apis::generate_monsters,
+        monster_apis::spar_monsters,
+        monster_apis::batch_get_monsters,
+        monster_apis::bulk_delete_monsters,
+        monster_apis::bulk_update_monsters,
+        monster_apis::get_monster_by_id,
+        monster_apis::get_monster_battles,
+        monster_apis::get_monster_advantage,
+        monster_apis::clone_monster,
+        monster_apis::level_up_monster,
+        monster_apis::add_monster_tags,
+        monster_apis::remove_monster_tag,
+        monster_apis::delete_monster_by_id,
+        monster_apis::update_monster_by_id,
+        monster_apis::patch_monster_by_id,
+        monster_apis::upload_monster_image,
+        monster_apis::import_csv,
+        monster_apis::validate_rows,
+        battle_apis::get_battles,
+        battle_apis::create_battle,
+        battle_apis::create_battles,
+        battle_apis::preview_battle,
+        battle_apis::battle_odds,
+        battle_apis::quick_battle,
+        battle_apis::get_battle_by_id,
+        battle_apis::rematch_battle,
+        battle_apis::replay_battle,
+        battle_apis::restore_battle,
+        battle_apis::delete_battle_by_id,
+        battle_apis::get_leaderboard,
+        tournament_apis::create_tournament,
+        admin_apis::reset_database,
+    )
+)]
+pub struct ApiDoc;
+
+/// Serves the spec at `/api-docs/openapi.json` and the UI at `/swagger-ui/`.
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-docs/openapi.json", ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::swagger_ui;
+    use actix_web::{http::StatusCode, test, App};
+    use serde_json::Value;
+
+    #[actix_rt::test]
+    async fn test_should_serve_the_openapi_spec() {
+        let app = test::init_service(App::new().service(swagger_ui())).await;
+        let req = test::TestRequest::get()
+            .uri("/api-docs/openapi.json")
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let spec: Value = test::read_body_json(resp).await;
+        assert!(spec["paths"].get("/monsters").is_some());
+        assert!(spec["paths"].get("/battles/{id}").is_some());
+        assert!(spec["components"]["schemas"].get("Monster").is_some());
+    }
+}
@@ -0,0 +1,214 @@
+use super::errors::ApiError;
+use super::json::Json;
+use super::monster_apis::parse_monster_id;
+use crate::battle_engine::{self, BattleOptions};
+use crate::config::AppConfig;
+use crate::models::battle::Battle;
+use crate::models::monster::Monster;
+use crate::repository::database::Database;
+use crate::repository::{battle_repository, monster_repository};
+use crate::utils::metrics::Metrics;
+use actix_web::{post, web, HttpResponse};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+const MAX_TOURNAMENT_MONSTERS: usize = 20;
+const POINTS_PER_WIN: i32 = 3;
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CreateTournamentRequest {
+    monster_ids: Vec<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TournamentQuery {
+    persist: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct Standing {
+    pub monster_id: String,
+    pub wins: i32,
+    pub losses: i32,
+    pub points: i32,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct TournamentResponse {
+    pub matches: usize,
+    pub standings: Vec<Standing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battles: Option<Vec<Battle>>,
+}
+
+#[utoipa::path(
+    request_body = CreateTournamentRequest,
+    params(TournamentQuery),
+    responses(
+        (status = 200, description = "Round-robin standings", body = TournamentResponse),
+        (status = 400, description = "Invalid or duplicated monster ids", body = String),
+        (status = 404, description = "Monster not found", body = String)
+    )
+)]
+#[post("/tournaments")]
+pub async fn create_tournament(
+    db: web::Data<Database>,
+    metrics: web::Data<Metrics>,
+    config: Option<web::Data<AppConfig>>,
+    query: web::Query<TournamentQuery>,
+    request: Json<CreateTournamentRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let monster_ids = &request.monster_ids;
+    if monster_ids.len() < 2 || monster_ids.len() > MAX_TOURNAMENT_MONSTERS {
+        return Err(ApiError::BadRequest(format!(
+            "A tournament needs between 2 and {} monsters",
+            MAX_TOURNAMENT_MONSTERS
+        )));
+    }
+    let mut monsters: Vec<Monster> = Vec::with_capacity(monster_ids.len());
+    for monster_id in monster_ids {
+        let monster_id = parse_monster_id(monster_id)?.to_string();
+        if monsters.iter().any(|monster| monster.id == monster_id) {
+            return Err(ApiError::BadRequest(
+                "A monster cannot enter a tournament twice".to_string(),
+            ));
+        }
+        let monster = monster_repository::get_monster_by_id(&db, &monster_id)?
+            .ok_or_else(|| ApiError::NotFound(format!("Monster {} not found", monster_id)))?;
+        monsters.push(monster);
+    }
+
+    let mut standings: Vec<Standing> = monsters
+        .iter()
+        .map(|monster| Standing {
+            monster_id: monster.id.clone(),
+            wins: 0,
+            losses: 0,
+            points: 0,
+        })
+        .collect();
+    let options = BattleOptions::from_config(config.as_ref().map(web::Data::get_ref));
+    let mut battles = Vec::new();
+    for (a, monster_a) in monsters.iter().enumerate() {
+        for (b, monster_b) in monsters.iter().enumerate().skip(a + 1) {
+            let outcome = battle_engine::simulate(monster_a, monster_b, &options);
+            // a draw earns neither side a win nor a loss
+            if let Some(winner_id) = &outcome.winner {
+                let (winner, loser) = if *winner_id == monster_a.id {
+                    (a, b)
+                } else {
+                    (b, a)
+                };
+                standings[winner].wins += 1;
+                standings[winner].points += POINTS_PER_WIN;
+                standings[loser].losses += 1;
+            }
+            battles.push(Battle::fought(monster_a, monster_b, outcome));
+        }
+    }
+    standings.sort_by(|a, b| {
+        b.wins
+            .cmp(&a.wins)
+            .then(b.points.cmp(&a.points))
+            .then(a.monster_id.cmp(&b.monster_id))
+    });
+
+    let matches = battles.len();
+    let battles = if query.persist.unwrap_or(false) {
+        let battles = battle_repository::create_battles(&db, battles)?;
+        for battle in &battles {
+            metrics.inc_battles_created();
+            metrics.observe_battle_rounds(battle.rounds as u64);
+        }
+        Some(battles)
+    } else {
+        None
+    };
+    Ok(HttpResponse::Ok().json(TournamentResponse {
+        matches,
+        standings,
+        battles,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{create_tournament, TournamentResponse};
+    use crate::repository::database::Database;
+    use crate::utils::metrics::Metrics;
+    use crate::utils::test_utils::init_test_monsters;
+    use actix_web::{http, test, web::Data, App};
+    use serde_json::json;
+    use uuid::Uuid;
+
+    #[actix_rt::test]
+    async fn test_should_run_every_pairing_of_a_round_robin_tournament() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_tournament);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/tournaments")
+            .set_json(json!({
+                "monster_ids": [test_monsters[0].id, test_monsters[1].id, test_monsters[6].id]
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: TournamentResponse = test::read_body_json(resp).await;
+        assert_eq!(body.matches, 3);
+        assert_eq!(body.standings.len(), 3);
+        assert_eq!(body.standings.iter().map(|s| s.wins).sum::<i32>(), 3);
+        assert_eq!(body.standings.iter().map(|s| s.losses).sum::<i32>(), 3);
+        assert!(body
+            .standings
+            .windows(2)
+            .all(|pair| pair[0].wins >= pair[1].wins));
+        assert!(body.battles.is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_persist_tournament_battles_when_requested() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_tournament);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/tournaments?persist=true")
+            .set_json(json!({
+                "monster_ids": [test_monsters[0].id, test_monsters[1].id, test_monsters[2].id]
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        let body: TournamentResponse = test::read_body_json(resp).await;
+        let battles = body.battles.expect("battles should be persisted");
+        assert_eq!(battles.len(), 3);
+        assert!(battles.iter().all(|battle| !battle.id.is_empty()));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_create_a_tournament_with_404_error_if_a_monster_does_not_exists() {
+        let db = Database::new();
+        let test_monsters = init_test_monsters(&db).await;
+        let app = App::new()
+            .app_data(Data::new(db))
+            .app_data(Data::new(Metrics::default()))
+            .service(create_tournament);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/tournaments")
+            .set_json(json!({
+                "monster_ids": [test_monsters[0].id, Uuid::new_v4().to_string()]
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,755 @@
+use crate::config::AppConfig;
+use crate::models::monster::Monster;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// (attacker element, defender element, damage multiplier)
+// fire > air > earth > water > fire; neutral is never affected.
+const ELEMENT_MULTIPLIERS: [(&str, &str, f64); 8] = [
+    ("fire", "air", 1.5),
+    ("air", "earth", 1.5),
+    ("earth", "water", 1.5),
+    ("water", "fire", 1.5),
+    ("air", "fire", 0.5),
+    ("earth", "air", 0.5),
+    ("water", "earth", 0.5),
+    ("fire", "water", 0.5),
+];
+
+/// Battle-only copy of a monster's stats, so a simulation never touches the source `Monster`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Combatant {
+    pub id: String,
+    pub attack: i32,
+    pub defense: i32,
+    pub speed: i32,
+    pub hp: i32,
+    pub element: String,
+}
+
+impl From<&Monster> for Combatant {
+    fn from(monster: &Monster) -> Self {
+        Combatant {
+            id: monster.id.clone(),
+            attack: monster.attack,
+            defense: monster.defense,
+            speed: monster.speed,
+            hp: monster.hp,
+            element: monster.element.clone(),
+        }
+    }
+}
+
+pub const DEFAULT_MIN_DAMAGE: i32 = 1;
+
+/// Raw damage of one hit before element multipliers and the minimum-damage floor.
+///
+/// Only `attack` is bounded by validation, so implementations work in f64 rather than i32.
+pub trait DamageFormula {
+    fn base_damage(&self, attack: i32, defense: i32) -> f64;
+}
+
+/// Classic `attack - defense`.
+pub struct Linear;
+
+/// `attack^2 / (attack + defense)`: defense softens hits but never cancels them.
+pub struct Ratio;
+
+/// Defense blocks that percentage of the attack, capped at 100.
+pub struct Percentage;
+
+impl DamageFormula for Linear {
+    fn base_damage(&self, attack: i32, defense: i32) -> f64 {
+        f64::from(attack) - f64::from(defense)
+    }
+}
+
+impl DamageFormula for Ratio {
+    fn base_damage(&self, attack: i32, defense: i32) -> f64 {
+        let (attack, defense) = (f64::from(attack), f64::from(defense));
+        if attack + defense <= 0.0 {
+            return 0.0;
+        }
+        attack * attack / (attack + defense)
+    }
+}
+
+impl DamageFormula for Percentage {
+    fn base_damage(&self, attack: i32, defense: i32) -> f64 {
+        attack as f64 * (100 - defense.clamp(0, 100)) as f64 / 100.0
+    }
+}
+
+/// Formula picked per battle; the default keeps the classic rules.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Formula {
+    #[default]
+    Linear,
+    Ratio,
+    Percentage,
+}
+
+impl Formula {
+    pub fn damage_formula(self) -> &'static dyn DamageFormula {
+        match self {
+            Formula::Linear => &Linear,
+            Formula::Ratio => &Ratio,
+            Formula::Percentage => &Percentage,
+        }
+    }
+}
+
+/// Who wins when both monsters end a round at or below 0 hp.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KnockoutRule {
+    /// Classic rules: a knockout ends the battle before the defender strikes back.
+    #[default]
+    FirstAttackerWins,
+    /// Both monsters fall and nobody wins.
+    Draw,
+    /// The monster left closer to 0 wins; an exact tie is a draw.
+    HigherRemaining,
+}
+
+/// How the monster that strikes first is picked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Initiative {
+    /// Classic rules: the faster monster, then the stronger one, then a coin flip.
+    #[default]
+    Speed,
+    /// A draw weighted by speed, so a monster three times as fast goes first 3 times in 4.
+    Weighted,
+}
+
+/// Tunable battle rules; `Default` matches the classic rules.
+#[derive(Debug, Clone, Copy)]
+pub struct BattleOptions {
+    pub min_damage: i32,
+    pub formula: Formula,
+    pub knockout_rule: KnockoutRule,
+    pub initiative: Initiative,
+    /// Seeds the tie-break RNG; `None` draws from entropy.
+    pub seed: Option<u64>,
+}
+
+impl Default for BattleOptions {
+    fn default() -> Self {
+        BattleOptions {
+            min_damage: DEFAULT_MIN_DAMAGE,
+            formula: Formula::default(),
+            knockout_rule: KnockoutRule::default(),
+            initiative: Initiative::default(),
+            seed: None,
+        }
+    }
+}
+
+impl BattleOptions {
+    /// Classic rules, seeded from `BATTLE_RNG_SEED` when the app was configured with one.
+    pub fn from_config(config: Option<&AppConfig>) -> Self {
+        BattleOptions {
+            seed: config.and_then(|config| config.battle_rng_seed),
+            ..BattleOptions::default()
+        }
+    }
+}
+
+/// One attack within a round, as replayed by the live battle stream and `/battles/{id}/replay`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct RoundLog {
+    pub round: i32,
+    pub attacker: String,
+    pub damage: i32,
+    pub defender_hp: i32,
+}
+
+pub struct BattleOutcome {
+    /// `None` for a draw.
+    pub winner: Option<String>,
+    pub winner_remaining_hp: i32,
+    pub rounds: i32,
+    pub log: Vec<RoundLog>,
+    /// Both monsters were still standing after `MAX_ROUNDS`; counted as a draw.
+    pub timed_out: bool,
+}
+
+/// Rounds fought before a battle is called off as a draw. Stats are unbounded and every hit
+/// deals at least `min_damage`, so without it two high-hp monsters could fight for billions
+/// of rounds; it also caps the stored log at `2 * MAX_ROUNDS` attacks.
+pub const MAX_ROUNDS: i32 = 500;
+
+pub fn element_multiplier(attacker_element: &str, defender_element: &str) -> f64 {
+    ELEMENT_MULTIPLIERS
+        .iter()
+        .find(|(attacker, defender, _)| {
+            *attacker == attacker_element && *defender == defender_element
+        })
+        .map(|(_, _, multiplier)| *multiplier)
+        .unwrap_or(1.0)
+}
+
+pub fn damage(
+    attacker: &Combatant,
+    defender: &Combatant,
+    formula: &dyn DamageFormula,
+    min_damage: i32,
+) -> i32 {
+    let base = formula.base_damage(attacker.attack, defender.defense);
+    let multiplied = base * element_multiplier(&attacker.element, &defender.element);
+    (multiplied.round() as i32).max(min_damage)
+}
+
+pub fn simulate(
+    monster_a: &Monster,
+    monster_b: &Monster,
+    options: &BattleOptions,
+) -> BattleOutcome {
+    fight(monster_a, monster_b, options, true)
+}
+
+/// Same fight as `simulate` but the round log is never built, for callers that only need
+/// the result.
+pub fn simulate_result(
+    monster_a: &Monster,
+    monster_b: &Monster,
+    options: &BattleOptions,
+) -> BattleOutcome {
+    fight(monster_a, monster_b, options, false)
+}
+
+fn fight(
+    monster_a: &Monster,
+    monster_b: &Monster,
+    options: &BattleOptions,
+    record_log: bool,
+) -> BattleOutcome {
+    let monster_a = Combatant::from(monster_a);
+    let monster_b = Combatant::from(monster_b);
+    let formula = options.formula.damage_formula();
+    let mut rng = match options.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    //sets turn order, a full speed and attack tie is a coin flip
+    let (mut first_monster, mut second_monster) = if options.initiative == Initiative::Weighted {
+        let total_speed = f64::from(monster_a.speed.max(0)) + f64::from(monster_b.speed.max(0));
+        let a_first = if total_speed == 0.0 {
+            rng.gen_bool(0.5)
+        } else {
+            rng.gen_bool(f64::from(monster_a.speed.max(0)) / total_speed)
+        };
+        if a_first {
+            (monster_a, monster_b)
+        } else {
+            (monster_b, monster_a)
+        }
+    } else if monster_a.speed > monster_b.speed {
+        (monster_a, monster_b)
+    } else if monster_a.speed < monster_b.speed {
+        (monster_b, monster_a)
+    } else if monster_a.attack > monster_b.attack {
+        (monster_a, monster_b)
+    } else if monster_a.attack < monster_b.attack || rng.gen_bool(0.5) {
+        (monster_b, monster_a)
+    } else {
+        (monster_a, monster_b)
+    };
+    //battle
+    let mut rounds = 0;
+    let mut log = Vec::new();
+    loop {
+        rounds += 1;
+        //first monster attack
+        let dealt = damage(&first_monster, &second_monster, formula, options.min_damage);
+        // saturating, as hp is unbounded and may already sit near i32::MIN
+        second_monster.hp = second_monster.hp.saturating_sub(dealt);
+        if record_log {
+            log.push(RoundLog {
+                round: rounds,
+                attacker: first_monster.id.clone(),
+                damage: dealt,
+                defender_hp: second_monster.hp,
+            });
+        }
+        // only the classic rule ends the round before the defender strikes back
+        if second_monster.hp <= 0 && options.knockout_rule == KnockoutRule::FirstAttackerWins {
+            return BattleOutcome {
+                winner: Some(first_monster.id),
+                winner_remaining_hp: first_monster.hp,
+                rounds,
+                log,
+                timed_out: false,
+            };
+        }
+        //second monster attack
+        let dealt = damage(&second_monster, &first_monster, formula, options.min_damage);
+        first_monster.hp = first_monster.hp.saturating_sub(dealt);
+        if record_log {
+            log.push(RoundLog {
+                round: rounds,
+                attacker: second_monster.id.clone(),
+                damage: dealt,
+                defender_hp: first_monster.hp,
+            });
+        }
+        let (winner, winner_remaining_hp) = match (first_monster.hp <= 0, second_monster.hp <= 0) {
+            (false, false) if rounds < MAX_ROUNDS => continue,
+            (false, false) => {
+                return BattleOutcome {
+                    winner: None,
+                    winner_remaining_hp: 0,
+                    rounds,
+                    log,
+                    timed_out: true,
+                }
+            }
+            (false, true) => (Some(first_monster.id), first_monster.hp),
+            (true, false) => (Some(second_monster.id), second_monster.hp),
+            (true, true) => (
+                double_knockout_winner(options.knockout_rule, &first_monster, &second_monster),
+                0,
+            ),
+        };
+        return BattleOutcome {
+            winner,
+            winner_remaining_hp,
+            rounds,
+            log,
+            timed_out: false,
+        };
+    }
+}
+
+/// Tally of many simulations of the same matchup.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct OddsTally {
+    pub monster_a_wins: u32,
+    pub monster_b_wins: u32,
+    pub draws: u32,
+}
+
+/// Fights the matchup `trials` times, each with its own seed counted up from `options.seed`
+/// (or a random start), so random tie-breaks and initiative average out.
+pub fn odds(
+    monster_a: &Monster,
+    monster_b: &Monster,
+    options: &BattleOptions,
+    trials: u32,
+) -> OddsTally {
+    let first_seed = options.seed.unwrap_or_else(rand::random);
+    let mut tally = OddsTally::default();
+    for trial in 0..trials {
+        let options = BattleOptions {
+            seed: Some(first_seed.wrapping_add(trial as u64)),
+            ..*options
+        };
+        match simulate_result(monster_a, monster_b, &options).winner {
+            Some(winner) if winner == monster_a.id => tally.monster_a_wins += 1,
+            Some(_) => tally.monster_b_wins += 1,
+            None => tally.draws += 1,
+        }
+    }
+    tally
+}
+
+/// Plain-language account of a battle built from its round log, e.g.
+/// "Dragon struck first for 23 damage; Goblin retaliated for 9 damage. ...".
+pub fn narrate(monster_a: &Monster, monster_b: &Monster, outcome: &BattleOutcome) -> String {
+    let name_of = |id: &str| {
+        if id == monster_a.id {
+            monster_a.name.as_str()
+        } else {
+            monster_b.name.as_str()
+        }
+    };
+    let other_than = |id: &str| {
+        if id == monster_a.id {
+            monster_b.name.as_str()
+        } else {
+            monster_a.name.as_str()
+        }
+    };
+    let mut sentences: Vec<String> = Vec::new();
+    for (index, entry) in outcome.log.iter().enumerate() {
+        let attacker = name_of(&entry.attacker);
+        let mut clause = match (index, index % 2) {
+            (0, _) => format!("{attacker} struck first for {} damage", entry.damage),
+            (_, 0) => format!(
+                "Round {}: {attacker} attacked for {} damage",
+                entry.round, entry.damage
+            ),
+            _ => format!("{attacker} retaliated for {} damage", entry.damage),
+        };
+        if entry.defender_hp <= 0 {
+            clause.push_str(&format!(", knocking out {}", other_than(&entry.attacker)));
+        }
+        match sentences.last_mut() {
+            Some(sentence) if index % 2 == 1 => {
+                sentence.push_str("; ");
+                sentence.push_str(&clause);
+            }
+            _ => sentences.push(clause),
+        }
+    }
+    let rounds = match outcome.rounds {
+        1 => "1 round".to_string(),
+        rounds => format!("{rounds} rounds"),
+    };
+    sentences.push(match &outcome.winner {
+        Some(winner) => format!(
+            "{} won after {rounds} with {} hp left",
+            name_of(winner),
+            outcome.winner_remaining_hp
+        ),
+        None => format!("The battle ended in a draw after {rounds}"),
+    });
+    sentences.join(". ") + "."
+}
+
+/// Winner of a round that knocked both monsters out; `None` for a draw.
+fn double_knockout_winner(
+    rule: KnockoutRule,
+    first: &Combatant,
+    second: &Combatant,
+) -> Option<String> {
+    match rule {
+        KnockoutRule::FirstAttackerWins => Some(first.id.clone()),
+        KnockoutRule::Draw => None,
+        KnockoutRule::HigherRemaining if first.hp > second.hp => Some(first.id.clone()),
+        KnockoutRule::HigherRemaining if second.hp > first.hp => Some(second.id.clone()),
+        KnockoutRule::HigherRemaining => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        damage, element_multiplier, narrate, simulate, BattleOptions, Combatant, DamageFormula,
+        Formula, Initiative, KnockoutRule, Linear, Percentage, Ratio, DEFAULT_MIN_DAMAGE,
+        MAX_ROUNDS,
+    };
+    use crate::models::battle::{Battle, BattleResult};
+    use crate::models::monster::{Monster, StringList};
+
+    fn monster(id: &str, element: &str) -> Monster {
+        Monster {
+            id: id.to_string(),
+            image_url: "https://loremflickr.com/640/480".to_string(),
+            name: id.to_string(),
+            attack: 50,
+            defense: 20,
+            hp: 100,
+            speed: 50,
+            created_at: None,
+            updated_at: None,
+            element: element.to_string(),
+            version: 1,
+            aliases: StringList::default(),
+            tags: StringList::default(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
+        }
+    }
+
+    #[test]
+    fn test_should_deal_more_damage_to_air_than_to_water_with_a_fire_monster() {
+        let fire = Combatant::from(&monster("fire", "fire"));
+        let air = Combatant::from(&monster("air", "air"));
+        let water = Combatant::from(&monster("water", "water"));
+        assert!(
+            damage(&fire, &air, &Linear, DEFAULT_MIN_DAMAGE)
+                > damage(&fire, &water, &Linear, DEFAULT_MIN_DAMAGE)
+        );
+        assert_eq!(damage(&fire, &air, &Linear, DEFAULT_MIN_DAMAGE), 45);
+        assert_eq!(damage(&fire, &water, &Linear, DEFAULT_MIN_DAMAGE), 15);
+    }
+
+    #[test]
+    fn test_should_not_change_damage_for_neutral_monsters() {
+        let fire = Combatant::from(&monster("fire", "fire"));
+        let neutral = Combatant::from(&monster("neutral", "neutral"));
+        assert_eq!(element_multiplier("neutral", "fire"), 1.0);
+        assert_eq!(element_multiplier("fire", "neutral"), 1.0);
+        assert_eq!(damage(&fire, &neutral, &Linear, DEFAULT_MIN_DAMAGE), 30);
+        assert_eq!(damage(&neutral, &fire, &Linear, DEFAULT_MIN_DAMAGE), 30);
+    }
+
+    #[test]
+    fn test_should_keep_the_minimum_damage_after_multipliers() {
+        let mut fire = Combatant::from(&monster("fire", "fire"));
+        fire.attack = 10;
+        let water = Combatant::from(&monster("water", "water"));
+        assert_eq!(damage(&fire, &water, &Linear, DEFAULT_MIN_DAMAGE), 1);
+    }
+
+    #[test]
+    fn test_should_let_the_advantaged_monster_win_an_otherwise_even_battle() {
+        let water = monster("water", "water");
+        let mut fire = monster("fire", "fire");
+        fire.speed = 51;
+        // fire strikes first but still loses to its counter element
+        let outcome = simulate(&water, &fire, &BattleOptions::default());
+        assert_eq!(outcome.winner.as_deref(), Some("water"));
+        assert_eq!(outcome.rounds, 3);
+    }
+
+    #[test]
+    fn test_should_report_the_winner_remaining_hp() {
+        let water = monster("water", "water");
+        let mut fire = monster("fire", "fire");
+        fire.speed = 51;
+        // fire strikes first, deals 15 per hit and water deals 45: water ends on 100 - 3 * 15
+        let outcome = simulate(&water, &fire, &BattleOptions::default());
+        assert_eq!(outcome.winner_remaining_hp, 55);
+        assert_eq!(outcome.rounds, 3);
+    }
+
+    #[test]
+    fn test_should_leave_the_source_monsters_untouched_after_a_simulation() {
+        let water = monster("water", "water");
+        let fire = monster("fire", "fire");
+        let outcome = simulate(&water, &fire, &BattleOptions::default());
+        assert_eq!(outcome.winner.as_deref(), Some("water"));
+        assert_eq!(water.hp, 100);
+        assert_eq!(fire.hp, 100);
+    }
+
+    #[test]
+    fn test_should_resolve_tanky_battles_faster_with_a_higher_damage_floor() {
+        let mut tank_a = monster("tank_a", "neutral");
+        tank_a.defense = 80;
+        let mut tank_b = monster("tank_b", "neutral");
+        tank_b.defense = 80;
+        let classic = simulate(&tank_a, &tank_b, &BattleOptions::default());
+        let floored = simulate(
+            &tank_a,
+            &tank_b,
+            &BattleOptions {
+                min_damage: 5,
+                ..BattleOptions::default()
+            },
+        );
+        assert_eq!(classic.rounds, 100);
+        assert_eq!(floored.rounds, 20);
+    }
+
+    #[test]
+    fn test_should_produce_identical_winners_with_the_same_seed() {
+        // identical stats make every turn order a coin flip
+        for seed in 0..20 {
+            let options = BattleOptions {
+                seed: Some(seed),
+                ..BattleOptions::default()
+            };
+            let first = simulate(&monster("a", "neutral"), &monster("b", "neutral"), &options);
+            let second = simulate(&monster("a", "neutral"), &monster("b", "neutral"), &options);
+            assert_eq!(first.winner, second.winner);
+        }
+    }
+
+    #[test]
+    fn test_should_log_every_attack_of_the_battle() {
+        let water = monster("water", "water");
+        let mut fire = monster("fire", "fire");
+        fire.speed = 51;
+        let outcome = simulate(&water, &fire, &BattleOptions::default());
+        // fire opens every round, water lands the last hit in round 3
+        assert_eq!(outcome.log.len(), 6);
+        assert_eq!(outcome.log[0].attacker, "fire");
+        assert_eq!(outcome.log[0].damage, 15);
+        assert_eq!(outcome.log[1].defender_hp, 55);
+        let last = outcome.log.last().unwrap();
+        assert_eq!((last.round, last.attacker.as_str()), (3, "water"));
+        assert!(last.defender_hp <= 0);
+    }
+
+    #[test]
+    fn test_should_compute_the_base_damage_of_each_formula() {
+        assert_eq!(Linear.base_damage(60, 50), 10.0);
+        assert_eq!(Ratio.base_damage(60, 40), 36.0);
+        assert_eq!(Percentage.base_damage(60, 50), 30.0);
+        assert_eq!(Percentage.base_damage(60, 150), 0.0);
+    }
+
+    #[test]
+    fn test_should_keep_the_damage_floor_with_every_formula() {
+        let mut weak = Combatant::from(&monster("weak", "neutral"));
+        weak.attack = 1;
+        let mut tank = Combatant::from(&monster("tank", "neutral"));
+        tank.defense = 100;
+        for formula in [Formula::Linear, Formula::Ratio, Formula::Percentage] {
+            assert_eq!(damage(&weak, &tank, formula.damage_formula(), 3), 3);
+        }
+    }
+
+    #[test]
+    fn test_should_pick_the_winner_according_to_the_formula() {
+        let mut cannon = monster("cannon", "neutral");
+        cannon.attack = 60;
+        cannon.defense = 10;
+        let mut tank = monster("tank", "neutral");
+        tank.attack = 30;
+        tank.defense = 50;
+        tank.speed = 40;
+        let winner = |formula| {
+            let options = BattleOptions {
+                formula,
+                ..BattleOptions::default()
+            };
+            simulate(&cannon, &tank, &options)
+                .winner
+                .unwrap_or_default()
+        };
+        // linear: 10 vs 20 per hit; ratio: 33 vs 23; percentage: 30 vs 27
+        assert_eq!(winner(Formula::Linear), "tank");
+        assert_eq!(winner(Formula::Ratio), "cannon");
+        assert_eq!(winner(Formula::Percentage), "cannon");
+    }
+
+    #[test]
+    fn test_should_apply_the_knockout_rule_when_both_monsters_fall_in_the_same_round() {
+        // both one-shot each other; "fast" strikes first and ends on -50, "slow" on -60
+        let mut fast = monster("fast", "neutral");
+        fast.attack = 100;
+        fast.defense = 0;
+        fast.hp = 50;
+        fast.speed = 60;
+        let mut slow = monster("slow", "neutral");
+        slow.attack = 100;
+        slow.defense = 0;
+        slow.hp = 40;
+        let outcome = |slow: &Monster, knockout_rule| {
+            let options = BattleOptions {
+                knockout_rule,
+                ..BattleOptions::default()
+            };
+            simulate(&fast, slow, &options)
+        };
+        let classic = outcome(&slow, KnockoutRule::FirstAttackerWins);
+        assert_eq!(
+            (classic.winner.as_deref(), classic.log.len()),
+            (Some("fast"), 1)
+        );
+        let draw = outcome(&slow, KnockoutRule::Draw);
+        assert_eq!((draw.winner.as_deref(), draw.log.len()), (None, 2));
+        assert_eq!(draw.winner_remaining_hp, 0);
+        assert_eq!(
+            outcome(&slow, KnockoutRule::HigherRemaining)
+                .winner
+                .as_deref(),
+            Some("fast")
+        );
+        slow.hp = 80;
+        assert_eq!(
+            outcome(&slow, KnockoutRule::HigherRemaining)
+                .winner
+                .as_deref(),
+            Some("slow")
+        );
+    }
+
+    #[test]
+    fn test_should_narrate_a_battle_with_monster_names() {
+        let mut dragon = monster("dragon-id", "neutral");
+        dragon.name = "Dragon".to_string();
+        dragon.speed = 60;
+        let mut goblin = monster("goblin-id", "neutral");
+        goblin.name = "Goblin".to_string();
+        goblin.hp = 50;
+        let outcome = simulate(&dragon, &goblin, &BattleOptions::default());
+        let story = narrate(&dragon, &goblin, &outcome);
+        assert!(
+            story
+                .starts_with("Dragon struck first for 30 damage; Goblin retaliated for 30 damage."),
+            "{story}"
+        );
+        assert!(story.contains("knocking out Goblin"), "{story}");
+        assert!(
+            story.ends_with("Dragon won after 2 rounds with 70 hp left."),
+            "{story}"
+        );
+        assert!(!story.contains("-id"), "{story}");
+    }
+
+    #[test]
+    fn test_should_usually_but_not_always_let_the_faster_monster_start_with_weighted_initiative() {
+        let mut fast = monster("fast", "neutral");
+        fast.speed = 75;
+        let mut slow = monster("slow", "neutral");
+        slow.speed = 25;
+        let fast_first = (0..1000)
+            .filter(|seed| {
+                let options = BattleOptions {
+                    initiative: Initiative::Weighted,
+                    seed: Some(*seed),
+                    ..BattleOptions::default()
+                };
+                simulate(&slow, &fast, &options).log[0].attacker == "fast"
+            })
+            .count();
+        // 750 expected; the bounds sit well over five standard deviations away
+        assert!((680..=820).contains(&fast_first), "{fast_first}");
+        let strict = simulate(&slow, &fast, &BattleOptions::default());
+        assert_eq!(strict.log[0].attacker, "fast");
+    }
+
+    #[test]
+    fn test_should_compute_damage_for_extreme_defense_without_overflowing() {
+        assert_eq!(Linear.base_damage(100, i32::MIN), 100.0 - i32::MIN as f64);
+        assert_eq!(Linear.base_damage(100, i32::MAX), 100.0 - i32::MAX as f64);
+        assert_eq!(Ratio.base_damage(100, i32::MIN), 0.0);
+        assert!(Ratio.base_damage(100, i32::MAX) > 0.0);
+        assert_eq!(Ratio.base_damage(i32::MAX, 0), i32::MAX as f64);
+
+        for formula in [Formula::Linear, Formula::Ratio, Formula::Percentage] {
+            for initiative in [Initiative::Speed, Initiative::Weighted] {
+                let options = BattleOptions {
+                    formula,
+                    initiative,
+                    ..BattleOptions::default()
+                };
+                let outcome = simulate(
+                    &Monster {
+                        defense: i32::MIN,
+                        hp: i32::MIN,
+                        speed: i32::MAX,
+                        ..monster("a", "neutral")
+                    },
+                    &Monster {
+                        defense: i32::MAX,
+                        hp: i32::MAX,
+                        speed: i32::MAX,
+                        ..monster("b", "neutral")
+                    },
+                    &options,
+                );
+                assert!(outcome.rounds >= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_should_call_off_a_battle_that_outlasts_the_round_limit() {
+        let mut wall = monster("wall", "neutral");
+        wall.attack = 0;
+        wall.defense = 100;
+        wall.hp = i32::MAX;
+        let mut other_wall = wall.clone();
+        other_wall.id = "other-wall".to_string();
+        let outcome = simulate(&wall, &other_wall, &BattleOptions::default());
+        assert!(outcome.timed_out);
+        assert_eq!(outcome.winner, None);
+        assert_eq!(outcome.rounds, MAX_ROUNDS);
+        assert_eq!(outcome.log.len(), 2 * MAX_ROUNDS as usize);
+        let battle = Battle::fought(&wall, &other_wall, outcome);
+        assert_eq!(battle.result, BattleResult::Timeout);
+        assert_eq!(battle.log.unwrap().rounds.len(), 2 * MAX_ROUNDS as usize);
+    }
+}
@@ -0,0 +1,298 @@
+use crate::api::json::DEFAULT_MAX_JSON_BODY_BYTES;
+use crate::models::pagination::{DefaultPageSize, DEFAULT_PER_PAGE};
+use crate::repository::database::{DEFAULT_POOL_SIZE, DEFAULT_POOL_TIMEOUT};
+use crate::utils::auth::{parse_named_keys, ApiKey, DEFAULT_IDENTITY};
+use crate::utils::disk_space::{DiskSpace, DEFAULT_MIN_FREE_BYTES};
+use crate::utils::flags::{parse_flag, AdminReset, UniqueNames};
+use crate::utils::image_hosts::ImageHosts;
+use crate::utils::import_limit::DEFAULT_MAX_CONCURRENT_IMPORTS;
+use crate::utils::leaderboard_cache::DEFAULT_LEADERBOARD_TTL;
+use crate::utils::monster_quota::MonsterQuota;
+use crate::utils::shutdown::DEFAULT_SHUTDOWN_TIMEOUT_SECS;
+use crate::utils::tls::{parse_tls_paths, TlsPaths};
+use crate::utils::uploads::{UploadDir, DEFAULT_UPLOAD_DIR};
+use actix_web::{web, HttpRequest};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::sync::OnceLock;
+use std::time::Duration;
+
+const DEFAULT_HOST: &str = "127.0.0.1";
+const DEFAULT_PORT: u16 = 8080;
+
+/// Every setting taken from the environment, read and checked once at startup.
+///
+/// Logging (`RUST_LOG`, `LOG_LEVEL`, `LOG_FILE`) is set up before this is loaded. Handlers
+/// read the rest through `AppConfig::of`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: Option<TlsPaths>,
+    pub database_url: String,
+    pub pool_size: u32,
+    pub pool_timeout: Duration,
+    pub shutdown_timeout_secs: u64,
+    pub json_logs: bool,
+    pub run_migrations: bool,
+    pub seed_on_start: bool,
+    pub default_page_size: DefaultPageSize,
+    pub max_json_body_bytes: usize,
+    pub max_concurrent_imports: usize,
+    pub monster_quota: MonsterQuota,
+    pub disk_space: DiskSpace,
+    pub upload_dir: UploadDir,
+    pub image_hosts: ImageHosts,
+    /// Keys from `API_KEY` and `API_KEYS`.
+    pub api_key: ApiKey,
+    pub unique_names: UniqueNames,
+    pub admin_reset: AdminReset,
+    pub max_stat_spread: Option<i32>,
+    pub battle_rng_seed: Option<u64>,
+    pub leaderboard_cache_ttl: Duration,
+}
+
+/// `None` when unset or blank, `Err` naming the variable when it does not parse.
+fn strict<T: FromStr>(vars: &HashMap<String, String>, name: &str) -> Result<Option<T>, String> {
+    match vars.get(name).map(|value| value.trim()) {
+        None | Some("") => Ok(None),
+        Some(value) => value
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("{} has an invalid value: {}", name, value)),
+    }
+}
+
+/// Like `strict`, and also `Err` when the value is below `min`.
+fn strict_at_least<T: FromStr + PartialOrd + Display>(
+    vars: &HashMap<String, String>,
+    name: &str,
+    min: T,
+) -> Result<Option<T>, String> {
+    match strict::<T>(vars, name)? {
+        Some(value) if value < min => Err(format!("{} must be at least {}", name, min)),
+        value => Ok(value),
+    }
+}
+
+impl AppConfig {
+    pub fn from_env() -> Result<Self, String> {
+        dotenvy::dotenv().ok();
+        AppConfig::from_vars(&std::env::vars().collect())
+    }
+
+    /// Builds the config from variable name -> value pairs; `Err` explains the first bad one.
+    pub fn from_vars(vars: &HashMap<String, String>) -> Result<Self, String> {
+        let var = |name: &str| vars.get(name).map(String::as_str);
+        let flag = |name: &str| parse_flag(var(name));
+
+        let port = strict::<u16>(vars, "PORT")?.unwrap_or(DEFAULT_PORT);
+        if port == 0 {
+            return Err("PORT must be between 1 and 65535".to_string());
+        }
+        let database_url = var("DATABASE_URL")
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .ok_or_else(|| "DATABASE_URL must be set".to_string())?
+            .to_string();
+        let mut api_keys: Vec<(String, String)> = var("API_KEY")
+            .map(|key| (DEFAULT_IDENTITY.to_string(), key.to_string()))
+            .into_iter()
+            .collect();
+        api_keys.extend(var("API_KEYS").map(parse_named_keys).unwrap_or_default());
+
+        Ok(AppConfig {
+            host: var("HOST")
+                .map(str::trim)
+                .filter(|host| !host.is_empty())
+                .unwrap_or(DEFAULT_HOST)
+                .to_string(),
+            port,
+            tls: parse_tls_paths(
+                var("TLS_CERT_PATH").map(str::to_string),
+                var("TLS_KEY_PATH").map(str::to_string),
+            )?,
+            database_url,
+            pool_size: strict_at_least(vars, "DB_POOL_SIZE", 1)?.unwrap_or(DEFAULT_POOL_SIZE),
+            pool_timeout: strict_at_least(vars, "DB_POOL_TIMEOUT_SECS", 1)?
+                .map_or(DEFAULT_POOL_TIMEOUT, Duration::from_secs),
+            shutdown_timeout_secs: strict(vars, "SHUTDOWN_TIMEOUT_SECS")?
+                .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS),
+            json_logs: var("LOG_FORMAT").is_some_and(|format| format.eq_ignore_ascii_case("json")),
+            // on unless explicitly turned off
+            run_migrations: var("RUN_MIGRATIONS").is_none_or(|value| parse_flag(Some(value))),
+            seed_on_start: flag("SEED_ON_START"),
+            default_page_size: DefaultPageSize::new(
+                strict(vars, "DEFAULT_PAGE_SIZE")?.unwrap_or(DEFAULT_PER_PAGE),
+            ),
+            max_json_body_bytes: strict_at_least(vars, "MAX_JSON_BODY_BYTES", 1)?
+                .unwrap_or(DEFAULT_MAX_JSON_BODY_BYTES),
+            max_concurrent_imports: strict_at_least(vars, "MAX_CONCURRENT_IMPORTS", 1)?
+                .unwrap_or(DEFAULT_MAX_CONCURRENT_IMPORTS),
+            monster_quota: MonsterQuota::new(strict_at_least(vars, "MAX_MONSTERS", 0)?),
+            disk_space: DiskSpace::new(
+                strict(vars, "IMPORT_MIN_FREE_BYTES")?.unwrap_or(DEFAULT_MIN_FREE_BYTES),
+            ),
+            upload_dir: UploadDir::new(var("UPLOAD_DIR").unwrap_or(DEFAULT_UPLOAD_DIR)),
+            image_hosts: ImageHosts::parse(var("ALLOWED_IMAGE_HOSTS")),
+            api_key: ApiKey::with_identities(api_keys),
+            unique_names: UniqueNames::new(flag("UNIQUE_MONSTER_NAMES")),
+            admin_reset: AdminReset::new(flag("ENABLE_ADMIN_RESET")),
+            max_stat_spread: strict_at_least(vars, "MAX_STAT_SPREAD", 0)?,
+            battle_rng_seed: strict(vars, "BATTLE_RNG_SEED")?,
+            leaderboard_cache_ttl: strict(vars, "LEADERBOARD_CACHE_TTL_SECS")?
+                .map_or(DEFAULT_LEADERBOARD_TTL, Duration::from_secs),
+        })
+    }
+
+    /// The config registered on the app, or the defaults when a test app registers none.
+    pub fn of(req: &HttpRequest) -> &AppConfig {
+        static DEFAULT: OnceLock<AppConfig> = OnceLock::new();
+        req.app_data::<web::Data<AppConfig>>()
+            .map(web::Data::get_ref)
+            .unwrap_or_else(|| DEFAULT.get_or_init(AppConfig::default))
+    }
+}
+
+/// What every unset variable falls back to; `database_url` is left empty.
+impl Default for AppConfig {
+    fn default() -> Self {
+        AppConfig {
+            host: DEFAULT_HOST.to_string(),
+            port: DEFAULT_PORT,
+            tls: None,
+            database_url: String::new(),
+            pool_size: DEFAULT_POOL_SIZE,
+            pool_timeout: DEFAULT_POOL_TIMEOUT,
+            shutdown_timeout_secs: DEFAULT_SHUTDOWN_TIMEOUT_SECS,
+            json_logs: false,
+            run_migrations: true,
+            seed_on_start: false,
+            default_page_size: DefaultPageSize::default(),
+            max_json_body_bytes: DEFAULT_MAX_JSON_BODY_BYTES,
+            max_concurrent_imports: DEFAULT_MAX_CONCURRENT_IMPORTS,
+            monster_quota: MonsterQuota::new(None),
+            disk_space: DiskSpace::default(),
+            upload_dir: UploadDir::new(DEFAULT_UPLOAD_DIR),
+            image_hosts: ImageHosts::new(None),
+            api_key: ApiKey::with_identities(Vec::new()),
+            unique_names: UniqueNames::new(false),
+            admin_reset: AdminReset::new(false),
+            max_stat_spread: None,
+            battle_rng_seed: None,
+            leaderboard_cache_ttl: DEFAULT_LEADERBOARD_TTL,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AppConfig;
+    use crate::models::pagination::DefaultPageSize;
+    use crate::utils::auth::ApiKey;
+    use crate::utils::flags::UniqueNames;
+    use crate::utils::monster_quota::MonsterQuota;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    const DATABASE_URL: (&str, &str) = ("DATABASE_URL", "postgres://localhost/monsters");
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .chain([&DATABASE_URL])
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_should_fall_back_to_defaults_without_any_other_variable() {
+        let config = AppConfig::from_vars(&vars(&[])).unwrap();
+        assert_eq!(
+            config,
+            AppConfig {
+                database_url: DATABASE_URL.1.to_string(),
+                ..AppConfig::default()
+            }
+        );
+        assert_eq!(config.host, "127.0.0.1");
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.pool_size, 10);
+        assert_eq!(config.shutdown_timeout_secs, 30);
+        assert!(config.run_migrations);
+        assert_eq!(config.default_page_size.per_page(), 20);
+        assert_eq!(config.max_json_body_bytes, 256 * 1024);
+        assert_eq!(config.max_concurrent_imports, 2);
+        assert_eq!(config.upload_dir.path().to_str(), Some("uploads"));
+        assert!(config.api_key.is_open());
+        assert_eq!(config.max_stat_spread, None);
+        assert_eq!(config.battle_rng_seed, None);
+    }
+
+    #[test]
+    fn test_should_read_every_variable_it_is_given() {
+        let config = AppConfig::from_vars(&vars(&[
+            ("HOST", "0.0.0.0"),
+            ("PORT", "9090"),
+            ("DB_POOL_SIZE", "4"),
+            ("DEFAULT_PAGE_SIZE", "500"),
+            ("MAX_MONSTERS", "50"),
+            ("API_KEY", "secret"),
+            ("API_KEYS", "alice:key-a"),
+            ("UNIQUE_MONSTER_NAMES", "true"),
+            ("RUN_MIGRATIONS", "false"),
+            ("MAX_STAT_SPREAD", "30"),
+            ("BATTLE_RNG_SEED", "7"),
+            ("LEADERBOARD_CACHE_TTL_SECS", "5"),
+        ]))
+        .unwrap();
+        assert_eq!(config.host, "0.0.0.0");
+        assert_eq!(config.port, 9090);
+        assert_eq!(config.database_url, "postgres://localhost/monsters");
+        assert_eq!(config.pool_size, 4);
+        assert_eq!(config.default_page_size, DefaultPageSize::new(100));
+        assert_eq!(config.monster_quota, MonsterQuota::new(Some(50)));
+        assert_eq!(
+            config.api_key,
+            ApiKey::with_identities(vec![
+                ("api_key".to_string(), "secret".to_string()),
+                ("alice".to_string(), "key-a".to_string())
+            ])
+        );
+        assert_eq!(config.unique_names, UniqueNames::new(true));
+        assert!(!config.run_migrations);
+        assert_eq!(config.max_stat_spread, Some(30));
+        assert_eq!(config.battle_rng_seed, Some(7));
+        assert_eq!(config.leaderboard_cache_ttl, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_should_reject_invalid_settings() {
+        for pairs in [
+            vec![("PORT", "http")],
+            vec![("PORT", "0")],
+            vec![("DB_POOL_SIZE", "0")],
+            vec![("DB_POOL_TIMEOUT_SECS", "0")],
+            vec![("DB_POOL_TIMEOUT_SECS", "soon")],
+            vec![("SHUTDOWN_TIMEOUT_SECS", "-1")],
+            vec![("DEFAULT_PAGE_SIZE", "twenty")],
+            vec![("MAX_JSON_BODY_BYTES", "0")],
+            vec![("MAX_JSON_BODY_BYTES", "1MB")],
+            vec![("MAX_CONCURRENT_IMPORTS", "0")],
+            vec![("MAX_MONSTERS", "-1")],
+            vec![("MAX_MONSTERS", "lots")],
+            vec![("IMPORT_MIN_FREE_BYTES", "64MB")],
+            vec![("MAX_STAT_SPREAD", "-5")],
+            vec![("BATTLE_RNG_SEED", "random")],
+            vec![("LEADERBOARD_CACHE_TTL_SECS", "30s")],
+            vec![("TLS_CERT_PATH", "cert.pem")],
+        ] {
+            assert!(AppConfig::from_vars(&vars(&pairs)).is_err(), "{:?}", pairs);
+        }
+        assert_eq!(
+            AppConfig::from_vars(&HashMap::new()),
+            Err("DATABASE_URL must be set".to_string())
+        );
+    }
+}
@@ -1,7 +1,19 @@
-use actix_web::{get, web, App, HttpResponse, HttpServer, Responder, Result};
-use serde::Serialize;
+use actix_web::http::header;
+use actix_web::middleware::{from_fn, Compress, Condition, Logger};
+use actix_web::{get, web, App, HttpRequest, HttpResponse, HttpServer, Responder, Result};
+use chrono::{DateTime, Utc};
+use config::AppConfig;
+use serde::{Deserialize, Serialize};
+use std::time::Instant;
+use utils::import_limit::ImportLimit;
+use utils::leaderboard_cache::LeaderboardCache;
+use utils::metrics::Metrics;
+use utils::temp_dir::TempDir;
+use utils::uploads::UPLOADS_PATH;
 
 mod api;
+mod battle_engine;
+mod config;
 mod models;
 mod repository;
 mod utils;
@@ -11,15 +23,100 @@ pub struct Response {
     pub message: String,
 }
 
+pub struct StartTime {
+    pub instant: Instant,
+    pub started_at: DateTime<Utc>,
+}
+
+impl StartTime {
+    pub fn now() -> Self {
+        StartTime {
+            instant: Instant::now(),
+            started_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct HealthResponse {
+    pub status: String,
+    pub version: String,
+    pub uptime_seconds: u64,
+    pub started_at: DateTime<Utc>,
+}
+
 #[get("/health")]
-async fn healthcheck() -> impl Responder {
-    let response = Response {
-        message: "Everything is working fine".to_string(),
+async fn healthcheck(start_time: web::Data<StartTime>) -> impl Responder {
+    let response = HealthResponse {
+        status: "ok".to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        uptime_seconds: start_time.instant.elapsed().as_secs(),
+        started_at: start_time.started_at,
     };
     HttpResponse::Ok().json(response)
 }
 
-async fn not_found() -> Result<HttpResponse> {
+#[derive(Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    pub status: String,
+    pub database: String,
+    pub temp_dir: String,
+}
+
+/// 503 until the database answers and the import temp dir is writable.
+#[get("/ready")]
+async fn readiness(
+    db: web::Data<repository::database::Database>,
+    temp_dir: web::Data<TempDir>,
+) -> Result<HttpResponse> {
+    let (database, temp_dir) = web::block(move || {
+        let database = db
+            .get_connection()
+            .map(|_| ())
+            .map_err(|err| err.to_string());
+        (database, temp_dir.check_writable())
+    })
+    .await?;
+    let ready = database.is_ok() && temp_dir.is_ok();
+    let describe = |check: Result<(), String>| check.err().unwrap_or_else(|| "ok".to_string());
+    let response = ReadinessResponse {
+        status: if ready { "ready" } else { "unavailable" }.to_string(),
+        database: describe(database),
+        temp_dir: describe(temp_dir),
+    };
+    if ready {
+        Ok(HttpResponse::Ok().json(response))
+    } else {
+        Ok(HttpResponse::ServiceUnavailable().json(response))
+    }
+}
+
+#[get("/metrics")]
+async fn metrics(
+    metrics: web::Data<Metrics>,
+    db: web::Data<repository::database::Database>,
+) -> impl Responder {
+    let body = metrics.render() + &utils::metrics::render_pool_state(&db.pool_state());
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(body)
+}
+
+async fn not_found(req: HttpRequest) -> Result<HttpResponse> {
+    let allowed = api::config::allowed_methods(req.path());
+    if !allowed.is_empty() {
+        let allow = allowed
+            .iter()
+            .map(|method| method.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let response = Response {
+            message: format!("Method {} not allowed", req.method()),
+        };
+        return Ok(HttpResponse::MethodNotAllowed()
+            .insert_header((header::ALLOW, allow))
+            .json(response));
+    }
     let response = Response {
         message: "Resource not found".to_string(),
     };
@@ -29,43 +126,268 @@ async fn not_found() -> Result<HttpResponse> {
 #[actix_web::main]
 #[cfg(not(tarpaulin_include))]
 async fn main() -> std::io::Result<()> {
-    let todo_db = repository::database::Database::new();
+    utils::logging::init();
+    // a bad setting stops startup instead of falling back to a default
+    let config = match AppConfig::from_env() {
+        Ok(config) => config,
+        Err(message) => {
+            log::error!("Invalid configuration: {}", message);
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                message,
+            ));
+        }
+    };
+    if config.api_key.is_open() {
+        log::warn!("API_KEY is not set, mutating routes are not authenticated");
+    }
+    if config.admin_reset.enabled() {
+        log::warn!("ENABLE_ADMIN_RESET is set, POST /api/admin/reset can wipe the database");
+    }
+    config.upload_dir.create();
+    let todo_db = repository::database::Database::with_pool_size(
+        &config.database_url,
+        config.pool_size,
+        config.pool_timeout,
+    );
+    if config.run_migrations {
+        match todo_db.run_migrations() {
+            Ok(applied) => log::info!("Applied {} pending migrations", applied),
+            Err(err) => {
+                log::error!("Running migrations failed: {}", err);
+                return Err(std::io::Error::other(err.to_string()));
+            }
+        }
+    }
+    if config.seed_on_start {
+        match repository::seed_repository::seed(&todo_db) {
+            Ok(seeded) => log::info!("Seeded {} demo monsters", seeded),
+            Err(err) => log::error!("Seeding demo monsters failed: {}", err),
+        }
+    }
     let app_data = web::Data::new(todo_db);
+    let metrics_data = web::Data::new(Metrics::default());
+    let import_limit_data = web::Data::new(ImportLimit::from_config(&config));
+    let leaderboard_data = web::Data::new(LeaderboardCache::from_config(&config));
+    let start_time_data = web::Data::new(StartTime::now());
+    let temp_dir_data = web::Data::new(TempDir::default());
+    let payload_config = api::json::payload_config(config.max_json_body_bytes);
+    let json_logs = config.json_logs;
+    let shutdown_timeout = config.shutdown_timeout_secs;
+    let tls_config = match &config.tls {
+        Some(paths) => Some(utils::tls::load_server_config(paths)?),
+        None => None,
+    };
+    let address = (config.host.clone(), config.port);
+    let config_data = web::Data::new(config);
 
-    HttpServer::new(move || {
+    let server = HttpServer::new(move || {
         App::new()
             .app_data(app_data.clone())
+            .app_data(config_data.clone())
+            .app_data(metrics_data.clone())
+            .app_data(import_limit_data.clone())
+            .app_data(leaderboard_data.clone())
+            .app_data(start_time_data.clone())
+            .app_data(temp_dir_data.clone())
+            .app_data(payload_config.clone())
             .configure(api::config::config)
             .service(healthcheck)
+            .service(readiness)
+            .service(metrics)
+            .service(api::battle_ws::battle_ws)
+            .service(api::openapi::swagger_ui())
+            .service(actix_files::Files::new(
+                UPLOADS_PATH,
+                config_data.upload_dir.path(),
+            ))
             .default_service(web::route().to(not_found))
-            .wrap(actix_web::middleware::Logger::default())
+            .wrap(from_fn(utils::auth::require_api_key))
+            .wrap(Compress::default())
+            .wrap(from_fn(utils::request_id::assign_request_id))
+            .wrap(Condition::new(
+                !json_logs,
+                Logger::new(r#"%a "%r" %s %b "%{Referer}i" "%{User-Agent}i" %T %{X-Request-Id}o"#),
+            ))
+            .wrap(Condition::new(
+                json_logs,
+                from_fn(utils::logging::json_logger),
+            ))
     })
-    .bind(("127.0.0.1", 8080))?
-    .run()
-    .await
+    .shutdown_timeout(shutdown_timeout)
+    .disable_signals();
+    let server = match tls_config {
+        Some(tls_config) => server.bind_rustls_0_23(address, tls_config)?,
+        None => server.bind(address)?,
+    }
+    .run();
+
+    let server_handle = server.handle();
+    actix_web::rt::spawn(async move {
+        utils::shutdown::shutdown_signal().await;
+        log::info!(
+            "Shutdown signal received, draining in-flight requests for up to {}s",
+            shutdown_timeout
+        );
+        server_handle.stop(true).await;
+    });
+
+    server.await
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{healthcheck, not_found};
+    use super::{
+        healthcheck, metrics, not_found, readiness, HealthResponse, ReadinessResponse, StartTime,
+    };
+    use crate::api::monster_apis::{create_monster, get_monsters};
+    use crate::repository::database::Database;
+    use crate::utils::metrics::Metrics;
+    use crate::utils::temp_dir::TempDir;
+    use crate::utils::test_utils::init_test_monsters;
+    use actix_web::http::header;
     use actix_web::http::StatusCode;
+    use actix_web::middleware::Compress;
     use actix_web::{test, web, App};
+    use serde_json::json;
 
     #[actix_rt::test]
     async fn test_should_get_health_check_correctly() {
-        let app = App::new().service(healthcheck);
-        let mut app = test::init_service(app).await;
+        let app = App::new()
+            .app_data(web::Data::new(StartTime::now()))
+            .service(healthcheck);
+        let app = test::init_service(app).await;
         let req = test::TestRequest::get().uri("/health").to_request();
-        let resp = test::call_service(&mut app, req).await;
+        let resp = test::call_service(&app, req).await;
         assert!(resp.status().is_success());
     }
 
+    #[actix_rt::test]
+    async fn test_should_report_the_crate_version_and_uptime_in_health_check() {
+        let start_time = StartTime::now();
+        let started_at = start_time.started_at;
+        let app = App::new()
+            .app_data(web::Data::new(start_time))
+            .service(healthcheck);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/health").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let health: HealthResponse = test::read_body_json(resp).await;
+        assert_eq!(health.status, "ok");
+        assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(health.started_at, started_at);
+        assert!(health.uptime_seconds < 60);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_report_ready_when_the_database_and_temp_dir_work() {
+        let app = App::new()
+            .app_data(web::Data::new(Database::new()))
+            .app_data(web::Data::new(TempDir::default()))
+            .service(readiness);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/ready").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let report: ReadinessResponse = test::read_body_json(resp).await;
+        assert_eq!(report.status, "ready");
+        assert_eq!(report.database, "ok");
+        assert_eq!(report.temp_dir, "ok");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_report_unavailable_when_the_temp_dir_is_not_writable() {
+        let missing = std::env::temp_dir().join(format!("missing-{}", uuid::Uuid::new_v4()));
+        let app = App::new()
+            .app_data(web::Data::new(Database::new()))
+            .app_data(web::Data::new(TempDir::new(missing)))
+            .service(readiness);
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/ready").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let report: ReadinessResponse = test::read_body_json(resp).await;
+        assert_eq!(report.status, "unavailable");
+        assert_eq!(report.database, "ok");
+        assert_ne!(report.temp_dir, "ok");
+    }
+
     #[actix_rt::test]
     async fn test_should_get_not_found_correctly() {
-        let mut app =
-            test::init_service(App::new().default_service(web::route().to(not_found))).await;
+        let app = test::init_service(App::new().default_service(web::route().to(not_found))).await;
         let request = test::TestRequest::get().uri("/lorem").to_request();
-        let response = test::call_service(&mut app, request).await;
+        let response = test::call_service(&app, request).await;
         assert_eq!(response.status(), StatusCode::NOT_FOUND);
     }
+
+    #[actix_rt::test]
+    async fn test_should_get_method_not_allowed_on_a_known_path() {
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Database::new()))
+                .configure(crate::api::config::config)
+                .default_service(web::route().to(not_found)),
+        )
+        .await;
+        let request = test::TestRequest::patch().uri("/api/monsters").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers().get(header::ALLOW).unwrap(), "GET, POST");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_compress_the_monster_list_when_gzip_is_accepted() {
+        let db = Database::new();
+        init_test_monsters(&db).await;
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(db))
+                .service(get_monsters)
+                .wrap(Compress::default()),
+        )
+        .await;
+        let request = test::TestRequest::get()
+            .uri("/monsters")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(header::CONTENT_ENCODING).unwrap(),
+            "gzip"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_should_count_created_monsters_in_metrics() {
+        let app = App::new()
+            .app_data(web::Data::new(Database::new()))
+            .app_data(web::Data::new(Metrics::default()))
+            .service(create_monster)
+            .service(metrics);
+        let app = test::init_service(app).await;
+        let request = test::TestRequest::post()
+            .uri("/monsters")
+            .set_json(json!({
+                "name": "metered monster",
+                "image_url": "https://loremflickr.com/640/480",
+                "attack": 10,
+                "defense": 10,
+                "hp": 10,
+                "speed": 10
+            }))
+            .to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        let request = test::TestRequest::get().uri("/metrics").to_request();
+        let response = test::call_service(&app, request).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = test::read_body(response).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains("# TYPE monsters_created_total counter"));
+        assert!(body.contains("monsters_created_total 1"));
+        assert!(body.contains("db_pool_idle_connections"));
+    }
 }
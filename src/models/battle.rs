@@ -1,6 +1,13 @@
+use crate::battle_engine::{BattleOutcome, RoundLog};
 use crate::models::monster::Monster;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::{Jsonb, Text};
 use diesel::{AsChangeset, Associations, Identifiable, Insertable, Queryable};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 #[derive(
     Serialize,
@@ -12,6 +19,7 @@ use serde::{Deserialize, Serialize};
     AsChangeset,
     Identifiable,
     Associations,
+    ToSchema,
 )]
 #[diesel(belongs_to(Monster, foreign_key = winner))]
 #[diesel(table_name = crate::repository::schema::battles)]
@@ -20,10 +28,295 @@ pub struct Battle {
     pub id: String,
     pub monster_a: String,
     pub monster_b: String,
+    /// `None` when the battle ended without a winner; see `result`.
     #[serde(default)]
-    pub winner: String,
-    #[serde(rename = "createdAt")]
+    pub winner: Option<String>,
+    #[serde(rename = "createdAt", default, with = "crate::models::timestamp")]
     pub created_at: Option<chrono::NaiveDateTime>,
-    #[serde(rename = "updatedAt")]
+    #[serde(rename = "updatedAt", default, with = "crate::models::timestamp")]
     pub updated_at: Option<chrono::NaiveDateTime>,
+    #[serde(default)]
+    pub winner_remaining_hp: i32,
+    #[serde(default)]
+    pub rounds: i32,
+    /// Set when the battle is soft-deleted; hidden from reads until restored.
+    #[serde(
+        default,
+        rename = "deletedAt",
+        skip_serializing_if = "Option::is_none",
+        with = "crate::models::timestamp"
+    )]
+    pub deleted_at: Option<chrono::NaiveDateTime>,
+    /// Every attack of the battle; `None` for battles fought before logs were stored.
+    #[serde(default, skip_serializing)]
+    #[schema(ignore)]
+    pub log: Option<BattleLog>,
+    #[serde(default)]
+    pub result: BattleResult,
+    /// `winner`, `rounds` and `result` are only meaningful once the battle is `completed`.
+    #[serde(default)]
+    pub status: BattleStatus,
+}
+
+impl Battle {
+    /// Unsaved battle recording `outcome`, with the log of the fight.
+    pub fn fought(monster_a: &Monster, monster_b: &Monster, outcome: BattleOutcome) -> Battle {
+        Battle {
+            id: String::new(),
+            monster_a: monster_a.id.clone(),
+            monster_b: monster_b.id.clone(),
+            created_at: None,
+            updated_at: None,
+            winner_remaining_hp: outcome.winner_remaining_hp,
+            rounds: outcome.rounds,
+            deleted_at: None,
+            log: Some(BattleLog {
+                monster_a_hp: monster_a.hp,
+                monster_b_hp: monster_b.hp,
+                rounds: outcome.log,
+            }),
+            result: if outcome.timed_out {
+                BattleResult::Timeout
+            } else {
+                BattleResult::from_winner(&outcome.winner)
+            },
+            winner: outcome.winner,
+            status: BattleStatus::Completed,
+        }
+    }
+
+    /// Unsaved placeholder for a battle that is simulated in the background.
+    pub fn pending(monster_a: &Monster, monster_b: &Monster) -> Battle {
+        Battle {
+            id: String::new(),
+            monster_a: monster_a.id.clone(),
+            monster_b: monster_b.id.clone(),
+            winner: None,
+            created_at: None,
+            updated_at: None,
+            winner_remaining_hp: 0,
+            rounds: 0,
+            deleted_at: None,
+            log: None,
+            result: BattleResult::default(),
+            status: BattleStatus::Pending,
+        }
+    }
+}
+
+/// How a battle ended; every result but `win` leaves `Battle::winner` empty.
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    AsExpression,
+    FromSqlRow,
+    ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "lowercase")]
+pub enum BattleResult {
+    #[default]
+    Win,
+    Draw,
+    /// Called off as a draw after `battle_engine::MAX_ROUNDS`.
+    Timeout,
+}
+
+impl BattleResult {
+    pub fn from_winner(winner: &Option<String>) -> Self {
+        match winner {
+            Some(_) => BattleResult::Win,
+            None => BattleResult::Draw,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BattleResult::Win => "win",
+            BattleResult::Draw => "draw",
+            BattleResult::Timeout => "timeout",
+        }
+    }
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    AsExpression,
+    FromSqlRow,
+    ToSchema,
+)]
+#[diesel(sql_type = Text)]
+#[serde(rename_all = "lowercase")]
+pub enum BattleStatus {
+    /// Queued with `POST /battles?async=true`, not simulated yet.
+    Pending,
+    #[default]
+    Completed,
+    /// The background simulation could not be saved; the battle will never complete.
+    Failed,
+}
+
+impl BattleStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BattleStatus::Pending => "pending",
+            BattleStatus::Completed => "completed",
+            BattleStatus::Failed => "failed",
+        }
+    }
+}
+
+impl FromSql<Text, Pg> for BattleStatus {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Pg>>::from_sql(bytes)?.as_str() {
+            "pending" => Ok(BattleStatus::Pending),
+            "completed" => Ok(BattleStatus::Completed),
+            "failed" => Ok(BattleStatus::Failed),
+            other => Err(format!("Unknown battle status: {}", other).into()),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for BattleStatus {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), &mut out.reborrow())
+    }
+}
+
+impl FromSql<Text, Pg> for BattleResult {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        match <String as FromSql<Text, Pg>>::from_sql(bytes)?.as_str() {
+            "win" => Ok(BattleResult::Win),
+            "draw" => Ok(BattleResult::Draw),
+            "timeout" => Ok(BattleResult::Timeout),
+            other => Err(format!("Unknown battle result: {}", other).into()),
+        }
+    }
+}
+
+impl ToSql<Text, Pg> for BattleResult {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        <str as ToSql<Text, Pg>>::to_sql(self.as_str(), &mut out.reborrow())
+    }
+}
+
+/// Starting hp of both sides and each attack in order, stored as JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, AsExpression, FromSqlRow, ToSchema)]
+#[diesel(sql_type = Jsonb)]
+pub struct BattleLog {
+    pub monster_a_hp: i32,
+    pub monster_b_hp: i32,
+    pub rounds: Vec<RoundLog>,
+}
+
+impl FromSql<Jsonb, Pg> for BattleLog {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl ToSql<Jsonb, Pg> for BattleLog {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value = serde_json::to_value(self)?;
+        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&value, &mut out.reborrow())
+    }
+}
+
+/// Order of `GET /battles`; ties fall back to newest first.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum BattleSort {
+    #[default]
+    CreatedAt,
+    /// Longest fights first.
+    Rounds,
+    /// Closest fights first, by the winner's remaining hp; battles without a winner come last.
+    Margin,
+}
+
+impl BattleSort {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "created_at" => Some(BattleSort::CreatedAt),
+            "rounds" => Some(BattleSort::Rounds),
+            "margin" => Some(BattleSort::Margin),
+            _ => None,
+        }
+    }
+}
+
+/// Inclusive `created_at` window; either end may be open.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BattleFilter {
+    pub from: Option<chrono::NaiveDateTime>,
+    pub to: Option<chrono::NaiveDateTime>,
+    pub sort: BattleSort,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct BattleMonsters {
+    pub monster_a: Option<Monster>,
+    pub monster_b: Option<Monster>,
+    pub winner: Option<Monster>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct BattleDetail {
+    #[serde(flatten)]
+    pub battle: Battle,
+    pub monsters: BattleMonsters,
+}
+
+/// A freshly fought battle with its narrative, returned for `?explain=true`.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ExplainedBattle {
+    #[serde(flatten)]
+    pub battle: Battle,
+    pub explanation: String,
+}
+
+/// A battle seen from one participant's side.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct MonsterBattle {
+    #[serde(flatten)]
+    pub battle: Battle,
+    pub won: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Hash, ToSchema)]
+pub struct BattleRecord {
+    pub wins: i64,
+    pub losses: i64,
+    pub draws: i64,
+}
+
+impl BattleRecord {
+    /// Counts one completed battle of `monster_id`; no winner is a draw.
+    pub fn tally(&mut self, monster_id: &str, winner: Option<&str>) {
+        match winner {
+            Some(winner) if winner == monster_id => self.wins += 1,
+            Some(_) => self.losses += 1,
+            None => self.draws += 1,
+        }
+    }
+}
+
+/// One row of `GET /leaderboard`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, ToSchema)]
+pub struct LeaderboardEntry {
+    pub monster_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub record: BattleRecord,
 }
@@ -1,2 +1,4 @@
 pub mod monster;
-pub mod battle;
\ No newline at end of file
+pub mod battle;
+pub mod pagination;
+pub mod timestamp;
\ No newline at end of file
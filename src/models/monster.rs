@@ -1,23 +1,436 @@
+use crate::models::battle::BattleRecord;
+use diesel::deserialize::{self, FromSql, FromSqlRow};
+use diesel::expression::AsExpression;
+use diesel::pg::{Pg, PgValue};
+use diesel::serialize::{self, Output, ToSql};
+use diesel::sql_types::Jsonb;
 use diesel::{AsChangeset, Identifiable, Insertable, Queryable};
-use serde::{Deserialize, Serialize};
-use validator::Validate;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+pub const ELEMENTS: [&str; 5] = ["fire", "water", "earth", "air", "neutral"];
+
+/// Highest attack leveling up can reach, the top of its validated range.
+pub const MAX_STAT: i32 = 100;
+
+/// Serialized `Monster` keys that `?fields=` may select.
+pub const MONSTER_FIELDS: [&str; 16] = [
+    "id",
+    "image_url",
+    "name",
+    "attack",
+    "defense",
+    "hp",
+    "speed",
+    "createdAt",
+    "updatedAt",
+    "element",
+    "aliases",
+    "tags",
+    "createdBy",
+    "level",
+    "availableFrom",
+    "availableTo",
+];
+
+pub fn default_element() -> String {
+    "neutral".to_string()
+}
+
+pub fn default_level() -> i32 {
+    1
+}
+
+pub fn validate_element(element: &str) -> Result<(), ValidationError> {
+    if ELEMENTS.contains(&element) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("element"))
+    }
+}
+
+/// Rejects tanks whose defense outgrows their attack by more than `max_spread`, which comes
+/// from `AppConfig::max_stat_spread`; `None` turns the check off.
+pub fn check_stat_spread(
+    monster: &Monster,
+    max_spread: Option<i32>,
+) -> Result<(), ValidationError> {
+    match max_spread {
+        // widened so an extreme defense cannot overflow the subtraction
+        Some(max_spread)
+            if i64::from(monster.defense) - i64::from(monster.attack) > i64::from(max_spread) =>
+        {
+            Err(ValidationError::new("stat_spread"))
+        }
+        _ => Ok(()),
+    }
+}
 
 #[derive(
-    Serialize, Deserialize, Debug, Clone, Queryable, Insertable, AsChangeset, Identifiable, Validate,
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Queryable,
+    Insertable,
+    AsChangeset,
+    Identifiable,
+    Validate,
+    ToSchema,
 )]
 #[diesel(table_name = crate::repository::schema::monsters)]
 pub struct Monster {
+    // Field order must match the `monsters` table columns for `Queryable`.
     #[serde(default)]
     pub id: String,
-    pub name: String,
     pub image_url: String,
+    pub name: String,
     #[validate(range(min = 0, max = 100))]
+    #[schema(minimum = 0, maximum = 100)]
     pub attack: i32,
     pub defense: i32,
     pub hp: i32,
     pub speed: i32,
-    #[serde(rename = "createdAt")]
+    #[serde(rename = "createdAt", default, with = "crate::models::timestamp")]
     pub created_at: Option<chrono::NaiveDateTime>,
-    #[serde(rename = "updatedAt")]
+    #[serde(rename = "updatedAt", default, with = "crate::models::timestamp")]
+    pub updated_at: Option<chrono::NaiveDateTime>,
+    #[serde(default = "default_element")]
+    #[validate(custom = "validate_element")]
+    pub element: String,
+    /// Bumped on every update; `PUT` must send the version it read.
+    #[serde(default)]
+    pub version: i32,
+    /// Nicknames that `/monsters/search` matches besides the name.
+    #[serde(default)]
+    pub aliases: StringList,
+    /// Lowercase labels such as `boss`, managed through `/monsters/{id}/tags`.
+    #[serde(default)]
+    pub tags: StringList,
+    /// Identity behind the API key that created the monster; `None` while auth is off.
+    #[serde(rename = "createdBy", default, skip_deserializing)]
+    pub created_by: Option<String>,
+    /// Starts at 1 and only changes through `POST /monsters/{id}/level_up`.
+    #[serde(default = "default_level", skip_deserializing)]
+    pub level: i32,
+    /// Battles are refused before this moment; `None` means no lower bound.
+    #[serde(rename = "availableFrom", default, with = "crate::models::timestamp")]
+    pub available_from: Option<chrono::NaiveDateTime>,
+    /// Battles are refused after this moment; `None` means no upper bound.
+    #[serde(rename = "availableTo", default, with = "crate::models::timestamp")]
+    pub available_to: Option<chrono::NaiveDateTime>,
+}
+
+/// Strings stored as a JSON array, such as aliases and tags.
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Default, PartialEq, AsExpression, FromSqlRow, ToSchema,
+)]
+#[diesel(sql_type = Jsonb)]
+#[serde(transparent)]
+pub struct StringList(pub Vec<String>);
+
+impl FromSql<Jsonb, Pg> for StringList {
+    fn from_sql(bytes: PgValue<'_>) -> deserialize::Result<Self> {
+        let value = <serde_json::Value as FromSql<Jsonb, Pg>>::from_sql(bytes)?;
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+impl ToSql<Jsonb, Pg> for StringList {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Pg>) -> serialize::Result {
+        let value = serde_json::to_value(&self.0)?;
+        <serde_json::Value as ToSql<Jsonb, Pg>>::to_sql(&value, &mut out.reborrow())
+    }
+}
+
+/// A monster id that is known to be a well-formed UUID; deserializing anything else fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MonsterId(pub Uuid);
+
+impl FromStr for MonsterId {
+    type Err = String;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        Uuid::parse_str(id)
+            .map(MonsterId)
+            .map_err(|_| "Invalid monster id".to_string())
+    }
+}
+
+impl fmt::Display for MonsterId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl Serialize for MonsterId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MonsterId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default, AsChangeset, Validate, ToSchema)]
+#[diesel(table_name = crate::repository::schema::monsters, treat_none_as_null = false)]
+pub struct PartialMonster {
+    pub name: Option<String>,
+    pub image_url: Option<String>,
+    #[validate(range(min = 0, max = 100))]
+    #[schema(minimum = 0, maximum = 100)]
+    pub attack: Option<i32>,
+    pub defense: Option<i32>,
+    pub hp: Option<i32>,
+    pub speed: Option<i32>,
+    #[validate(custom = "validate_element")]
+    pub element: Option<String>,
+    pub aliases: Option<StringList>,
+    #[serde(skip)]
     pub updated_at: Option<chrono::NaiveDateTime>,
 }
+
+#[derive(Deserialize, Debug, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MonsterFilter {
+    pub element: Option<String>,
+    /// Only monsters carrying this tag, ignoring case.
+    pub tag: Option<String>,
+    /// Only monsters created under this API key identity.
+    pub created_by: Option<String>,
+}
+
+/// Order of `GET /monsters`; ties fall back to newest first.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MonsterSort {
+    #[default]
+    CreatedAt,
+    /// Strongest first, by `Monster::power`.
+    Power,
+}
+
+impl MonsterSort {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "power" => Some(MonsterSort::Power),
+            _ => None,
+        }
+    }
+}
+
+/// A stat chosen by name, e.g. for `/monsters/histogram`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stat {
+    Attack,
+    Defense,
+    Hp,
+    Speed,
+}
+
+impl Stat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "attack" => Some(Stat::Attack),
+            "defense" => Some(Stat::Defense),
+            "hp" => Some(Stat::Hp),
+            "speed" => Some(Stat::Speed),
+            _ => None,
+        }
+    }
+}
+
+/// Trims and lowercases a tag; `None` when nothing is left.
+pub fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().to_lowercase();
+    (!tag.is_empty()).then_some(tag)
+}
+
+impl Monster {
+    /// Single comparison score; derived on the fly, never stored. Summed in i64 because only
+    /// `attack` is bounded.
+    pub fn power(&self) -> i64 {
+        i64::from(self.attack)
+            + i64::from(self.defense)
+            + i64::from(self.hp / 10)
+            + i64::from(self.speed)
+    }
+
+    /// Whether `at` falls inside the monster's availability window, both ends inclusive.
+    pub fn is_available_at(&self, at: chrono::NaiveDateTime) -> bool {
+        self.available_from.is_none_or(|from| from <= at)
+            && self.available_to.is_none_or(|to| at <= to)
+    }
+
+    /// The monster one level up: every stat grows by `percent`, rounded up. Attack stops at
+    /// `MAX_STAT` and the other stats at `i32::MAX`; no stat ever goes down.
+    pub fn leveled_up(&self, percent: i32) -> Monster {
+        // i64 so large stored stats cannot overflow before the clamp
+        let scale = |stat: i32, cap: i32| {
+            let scaled = (stat as i64 * (100 + percent as i64) + 99) / 100;
+            scaled.min(cap as i64).max(stat as i64) as i32
+        };
+        Monster {
+            attack: scale(self.attack, MAX_STAT),
+            defense: scale(self.defense, i32::MAX),
+            hp: scale(self.hp, i32::MAX),
+            speed: scale(self.speed, i32::MAX),
+            level: self.level + 1,
+            ..self.clone()
+        }
+    }
+}
+
+/// Monster as returned by the read endpoints, with derived fields.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct MonsterView {
+    #[serde(flatten)]
+    pub monster: Monster,
+    pub power: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub record: Option<BattleRecord>,
+}
+
+impl From<Monster> for MonsterView {
+    fn from(monster: Monster) -> Self {
+        MonsterView {
+            power: monster.power(),
+            monster,
+            record: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_stat_spread, Monster, MonsterId, MAX_STAT};
+    use crate::utils::monster_generator::random_monster;
+
+    #[test]
+    fn test_should_only_deserialize_well_formed_monster_ids() {
+        let id = uuid::Uuid::new_v4();
+        let parsed: MonsterId = serde_json::from_value(serde_json::json!(id.to_string())).unwrap();
+        assert_eq!(parsed, MonsterId(id));
+        assert_eq!(serde_json::to_value(parsed).unwrap(), id.to_string());
+        let err = serde_json::from_str::<MonsterId>("\"999999\"").unwrap_err();
+        assert!(err.to_string().contains("Invalid monster id"));
+    }
+
+    fn monster_with(attack: i32, defense: i32) -> Monster {
+        Monster {
+            attack,
+            defense,
+            ..random_monster(&mut rand::thread_rng())
+        }
+    }
+
+    #[test]
+    fn test_should_accept_a_balanced_monster() {
+        assert!(check_stat_spread(&monster_with(40, 90), Some(50)).is_ok());
+        assert!(check_stat_spread(&monster_with(90, 10), Some(50)).is_ok());
+    }
+
+    #[test]
+    fn test_should_reject_a_lopsided_tank_only_while_the_check_is_on() {
+        let tank = monster_with(10, 95);
+        let err = check_stat_spread(&tank, Some(50)).expect_err("tank should be rejected");
+        assert_eq!(err.code, "stat_spread");
+        assert!(check_stat_spread(&tank, None).is_ok());
+    }
+
+    #[test]
+    fn test_should_check_the_spread_of_extreme_defense_without_overflowing() {
+        let err = check_stat_spread(&monster_with(0, i32::MAX), Some(50))
+            .expect_err("an unbounded tank should be rejected");
+        assert_eq!(err.code, "stat_spread");
+        assert!(check_stat_spread(&monster_with(100, i32::MIN), Some(50)).is_ok());
+    }
+
+    #[test]
+    fn test_should_grow_every_stat_when_leveling_up_without_passing_the_cap() {
+        let monster = Monster {
+            attack: 95,
+            defense: 95,
+            hp: 5,
+            speed: 100,
+            level: 3,
+            ..random_monster(&mut rand::thread_rng())
+        };
+        let leveled = monster.leveled_up(10);
+        assert_eq!(
+            (leveled.attack, leveled.defense, leveled.hp, leveled.speed),
+            (MAX_STAT, 105, 6, 110)
+        );
+        assert_eq!(leveled.level, 4);
+        assert_eq!(leveled.id, monster.id);
+    }
+
+    #[test]
+    fn test_should_never_lower_a_stat_when_leveling_up() {
+        let monster = Monster {
+            attack: 150,
+            defense: 10,
+            hp: 150,
+            speed: 10,
+            ..random_monster(&mut rand::thread_rng())
+        };
+        let leveled = monster.leveled_up(10);
+        assert_eq!(leveled.hp, 165);
+        assert_eq!(leveled.attack, 150);
+        let huge = Monster {
+            hp: i32::MAX,
+            ..monster
+        };
+        assert_eq!(huge.leveled_up(100).hp, i32::MAX);
+    }
+
+    #[test]
+    fn test_should_sum_extreme_stats_into_power_without_overflowing() {
+        let monster = Monster {
+            attack: 100,
+            defense: i32::MAX,
+            hp: i32::MAX,
+            speed: i32::MAX,
+            ..random_monster(&mut rand::thread_rng())
+        };
+        assert_eq!(
+            monster.power(),
+            100 + 2 * i32::MAX as i64 + (i32::MAX / 10) as i64
+        );
+        let weakest = Monster {
+            attack: 0,
+            defense: i32::MIN,
+            hp: i32::MIN,
+            speed: i32::MIN,
+            ..monster
+        };
+        assert_eq!(
+            weakest.power(),
+            2 * i32::MIN as i64 + (i32::MIN / 10) as i64
+        );
+    }
+
+    #[test]
+    fn test_should_be_available_only_inside_the_window() {
+        let now = chrono::Utc::now().naive_utc();
+        let day = chrono::Duration::days(1);
+        let always = random_monster(&mut rand::thread_rng());
+        assert!(always.is_available_at(now));
+        let seasonal = Monster {
+            available_from: Some(now - day),
+            available_to: Some(now + day),
+            ..always
+        };
+        assert!(seasonal.is_available_at(now));
+        assert!(!seasonal.is_available_at(now - day * 2));
+        assert!(!seasonal.is_available_at(now + day * 2));
+    }
+}
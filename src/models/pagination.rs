@@ -0,0 +1,154 @@
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+pub const DEFAULT_PER_PAGE: i64 = 20;
+pub const MAX_PER_PAGE: i64 = 100;
+
+/// The `per_page` list endpoints fall back to, from `DEFAULT_PAGE_SIZE` clamped to 1..=100.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DefaultPageSize {
+    per_page: i64,
+}
+
+impl DefaultPageSize {
+    pub fn new(per_page: i64) -> Self {
+        DefaultPageSize {
+            per_page: per_page.clamp(1, MAX_PER_PAGE),
+        }
+    }
+
+    pub fn per_page(&self) -> i64 {
+        self.per_page
+    }
+}
+
+impl Default for DefaultPageSize {
+    fn default() -> Self {
+        DefaultPageSize::new(DEFAULT_PER_PAGE)
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct Pagination {
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl Pagination {
+    /// A page past the one whose offset still fits in an `i64` is invalid too.
+    pub fn is_valid(&self) -> bool {
+        self.page.unwrap_or(1) >= 1
+            && (1..=MAX_PER_PAGE).contains(&self.limit())
+            && self.checked_offset().is_some()
+    }
+
+    pub fn limit(&self) -> i64 {
+        self.per_page.unwrap_or(DEFAULT_PER_PAGE)
+    }
+
+    /// Only call on a pagination that `is_valid`.
+    pub fn offset(&self) -> i64 {
+        self.checked_offset()
+            .expect("offset of a pagination that was not validated")
+    }
+
+    fn checked_offset(&self) -> Option<i64> {
+        (self.page.unwrap_or(1) - 1).checked_mul(self.limit())
+    }
+
+    /// Fills in `per_page` when the client left it out.
+    pub fn or_per_page(self, per_page: i64) -> Self {
+        Pagination {
+            per_page: self.per_page.or(Some(per_page)),
+            ..self
+        }
+    }
+
+    /// RFC 5988 `Link` header value for `total` items; `query` keeps every other parameter.
+    pub fn link_header(&self, path: &str, query: &str, total: i64) -> String {
+        let page = self.page.unwrap_or(1);
+        let last = ((total + self.limit() - 1) / self.limit()).max(1);
+        let kept: String = query
+            .split('&')
+            .filter(|pair| {
+                !pair.is_empty() && !pair.starts_with("page=") && !pair.starts_with("per_page=")
+            })
+            .map(|pair| format!("{}&", pair))
+            .collect();
+        let link = |target: i64, rel: &str| {
+            format!(
+                "<{}?{}page={}&per_page={}>; rel=\"{}\"",
+                path,
+                kept,
+                target,
+                self.limit(),
+                rel
+            )
+        };
+        let mut links = vec![link(1, "first")];
+        if page > 1 {
+            links.push(link((page - 1).min(last), "prev"));
+        }
+        if page < last {
+            links.push(link(page + 1, "next"));
+        }
+        links.push(link(last, "last"));
+        links.join(", ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DefaultPageSize, Pagination};
+
+    #[test]
+    fn test_should_link_every_neighbouring_page_and_keep_other_parameters() {
+        let pagination = Pagination {
+            page: Some(2),
+            per_page: Some(10),
+        };
+        assert_eq!(
+            pagination.link_header("/monsters", "element=fire&page=2&per_page=10", 25),
+            "</monsters?element=fire&page=1&per_page=10>; rel=\"first\", \
+             </monsters?element=fire&page=1&per_page=10>; rel=\"prev\", \
+             </monsters?element=fire&page=3&per_page=10>; rel=\"next\", \
+             </monsters?element=fire&page=3&per_page=10>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn test_should_omit_next_on_the_last_page() {
+        let pagination = Pagination {
+            page: Some(3),
+            per_page: Some(10),
+        };
+        let links = pagination.link_header("/battles", "", 25);
+        assert!(links.contains("rel=\"prev\""));
+        assert!(!links.contains("rel=\"next\""));
+        assert!(links.ends_with("</battles?page=3&per_page=10>; rel=\"last\""));
+    }
+
+    #[test]
+    fn test_should_reject_a_page_whose_offset_overflows() {
+        let pagination = Pagination {
+            page: Some(i64::MAX),
+            per_page: Some(20),
+        };
+        assert!(!pagination.is_valid());
+        let last_page = Pagination {
+            page: Some(i64::MAX / 20 + 1),
+            per_page: Some(20),
+        };
+        assert!(last_page.is_valid());
+        assert_eq!(last_page.offset(), i64::MAX / 20 * 20);
+    }
+
+    #[test]
+    fn test_should_clamp_the_default_page_size() {
+        assert_eq!(DefaultPageSize::new(0).per_page(), 1);
+        assert_eq!(DefaultPageSize::new(500).per_page(), 100);
+        assert_eq!(DefaultPageSize::new(5).per_page(), 5);
+        assert_eq!(DefaultPageSize::default().per_page(), 20);
+    }
+}
@@ -0,0 +1,69 @@
+//! Serde for stored `NaiveDateTime`s, which are UTC: written as RFC 3339 with a `Z`
+//! suffix so clients never read them as local time.
+//!
+//! Use as `#[serde(default, with = "crate::models::timestamp")]` on `Option` fields.
+use chrono::{DateTime, NaiveDateTime, SecondsFormat};
+use serde::{de, Deserialize, Deserializer, Serializer};
+
+pub fn serialize<S: Serializer>(
+    timestamp: &Option<NaiveDateTime>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match timestamp {
+        Some(timestamp) => serializer.serialize_str(
+            &timestamp
+                .and_utc()
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true),
+        ),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Accepts RFC 3339 in any offset, and the offset-less form older responses used.
+pub fn deserialize<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<NaiveDateTime>, D::Error> {
+    let Some(value) = Option::<String>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
+    DateTime::parse_from_rfc3339(&value)
+        .map(|timestamp| timestamp.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M:%S%.f"))
+        .map(Some)
+        .map_err(|_| de::Error::custom(format!("invalid timestamp {value}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Stamped {
+        #[serde(default, with = "super")]
+        at: Option<chrono::NaiveDateTime>,
+    }
+
+    #[test]
+    fn test_should_write_utc_timestamps_with_a_z_suffix() {
+        let at = NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_micro_opt(12, 30, 0, 250)
+            .unwrap();
+        let value = serde_json::to_value(Stamped { at: Some(at) }).unwrap();
+        let written = value["at"].as_str().unwrap();
+        assert!(written.ends_with('Z'), "{written}");
+        assert_eq!(written, "2024-03-01T12:30:00.000250Z");
+        let read: Stamped = serde_json::from_value(value).unwrap();
+        assert_eq!(read.at, Some(at));
+        let shifted: Stamped =
+            serde_json::from_value(json!({ "at": "2024-03-01T14:30:00.000250+02:00" })).unwrap();
+        assert_eq!(shifted.at, Some(at));
+        let legacy: Stamped =
+            serde_json::from_value(json!({ "at": "2024-03-01T12:30:00.000250" })).unwrap();
+        assert_eq!(legacy.at, Some(at));
+        let missing: Stamped = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(missing.at, None);
+    }
+}
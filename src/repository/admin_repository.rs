@@ -0,0 +1,43 @@
+use super::database::{Database, DbResult};
+use super::schema::{battles, monsters};
+use diesel::{Connection, PgConnection, QueryResult, RunQueryDsl};
+
+/// Deletes every battle, then every monster, on the given connection.
+pub fn delete_everything(connection: &mut PgConnection) -> QueryResult<()> {
+    // battles reference monsters, so they go first
+    diesel::delete(battles::table).execute(connection)?;
+    diesel::delete(monsters::table).execute(connection)?;
+    Ok(())
+}
+
+/// Empties both tables in one transaction.
+pub fn reset(db: &Database) -> DbResult<()> {
+    let mut connection = db.get_connection()?;
+    Ok(connection.transaction(|connection| delete_everything(connection))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::delete_everything;
+    use crate::repository::database::Database;
+    use crate::repository::schema::{battles, monsters};
+    use crate::utils::test_utils::init_test_battle;
+    use diesel::{Connection, QueryDsl, RunQueryDsl};
+
+    #[actix_rt::test]
+    async fn test_should_leave_both_tables_empty_after_a_reset() {
+        let db = Database::new();
+        init_test_battle(&db).await;
+        let mut connection = db.get_connection().unwrap();
+        // rolled back afterwards so tests running in parallel keep their data
+        connection.test_transaction::<_, diesel::result::Error, _>(|connection| {
+            diesel::sql_query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ")
+                .execute(connection)?;
+            delete_everything(connection)?;
+            let monsters_left: i64 = monsters::table.count().get_result(connection)?;
+            let battles_left: i64 = battles::table.count().get_result(connection)?;
+            assert_eq!((monsters_left, battles_left), (0, 0));
+            Ok(())
+        });
+    }
+}
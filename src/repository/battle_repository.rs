@@ -1,53 +1,255 @@
-use super::{database::Database, schema::battles::dsl::battles};
-use crate::models::battle::Battle;
-use diesel::{QueryDsl, RunQueryDsl};
+use super::{
+    database::{Database, DbResult},
+    schema::battles::{
+        dsl::{battles, created_at, deleted_at, id, monster_a, monster_b, rounds},
+        BoxedQuery,
+    },
+};
+use crate::models::{
+    battle::{Battle, BattleFilter, BattleRecord, BattleSort, BattleStatus, LeaderboardEntry},
+    pagination::Pagination,
+};
+use diesel::dsl::sql;
+use diesel::sql_types::{Integer, Nullable};
+use diesel::{
+    pg::Pg, BoolExpressionMethods, Connection, ExpressionMethods, PgSortExpressionMethods,
+    QueryDsl, RunQueryDsl,
+};
+use std::collections::HashMap;
 
-pub fn get_battles(db: &Database) -> Vec<Battle> {
-    let mut connection = db.get_connection();
+fn battles_for_monster(monster_id: &str) -> BoxedQuery<'_, Pg> {
     battles
-        .load::<Battle>(&mut connection)
-        .expect("Error loading all battles")
+        .filter(monster_a.eq(monster_id).or(monster_b.eq(monster_id)))
+        .filter(deleted_at.is_null())
+        .into_boxed()
 }
 
-pub fn create_battle(db: &Database, battle: Battle) -> Result<Battle, diesel::result::Error> {
-    let mut connection = db.get_connection();
+fn filtered_battles(filter: &BattleFilter) -> BoxedQuery<'_, Pg> {
+    let query = battles.filter(deleted_at.is_null()).into_boxed();
+    match (filter.from, filter.to) {
+        (Some(from), Some(to)) => query.filter(created_at.between(from, to)),
+        (Some(from), None) => query.filter(created_at.ge(from)),
+        (None, Some(to)) => query.filter(created_at.le(to)),
+        (None, None) => query,
+    }
+}
+
+/// Ordered by `filter.sort`, newest first otherwise; legacy rows without `created_at` come last.
+pub fn get_battles(
+    db: &Database,
+    filter: &BattleFilter,
+    pagination: &Pagination,
+) -> DbResult<Vec<Battle>> {
+    let mut connection = db.get_connection()?;
+    let mut query = filtered_battles(filter);
+    query = match filter.sort {
+        BattleSort::CreatedAt => query.order((created_at.desc().nulls_last(), id)),
+        BattleSort::Rounds => query.order((rounds.desc(), created_at.desc().nulls_last(), id)),
+        // draws, timeouts and unfinished battles have no margin, so they go after every win
+        BattleSort::Margin => query.order((
+            sql::<Nullable<Integer>>(
+                "CASE WHEN status = 'completed' AND winner IS NOT NULL \
+                 THEN winner_remaining_hp END",
+            )
+            .asc()
+            .nulls_last(),
+            created_at.desc().nulls_last(),
+            id,
+        )),
+    };
+    Ok(query
+        .limit(pagination.limit())
+        .offset(pagination.offset())
+        .load::<Battle>(&mut connection)?)
+}
+
+/// How many battles match `filter`, across every page.
+pub fn count_battles(db: &Database, filter: &BattleFilter) -> DbResult<i64> {
+    let mut connection = db.get_connection()?;
+    Ok(filtered_battles(filter)
+        .count()
+        .get_result::<i64>(&mut connection)?)
+}
+
+pub fn get_battles_for_monster(
+    db: &Database,
+    monster_id: &str,
+    pagination: &Pagination,
+) -> DbResult<Vec<Battle>> {
+    let mut connection = db.get_connection()?;
+    Ok(battles_for_monster(monster_id)
+        .order((created_at.desc(), id))
+        .limit(pagination.limit())
+        .offset(pagination.offset())
+        .load::<Battle>(&mut connection)?)
+}
+
+/// Tallies every completed battle the monster took part in; a battle without a winner counts
+/// as a draw.
+pub fn get_record_for_monster(db: &Database, monster_id: &str) -> DbResult<BattleRecord> {
+    let mut connection = db.get_connection()?;
+    Ok(battles_for_monster(monster_id)
+        .load::<Battle>(&mut connection)?
+        .iter()
+        .fold(BattleRecord::default(), |mut record, battle| {
+            if battle.status == BattleStatus::Completed {
+                record.tally(monster_id, battle.winner.as_deref());
+            }
+            record
+        }))
+}
+
+/// The `size` monsters with the most wins, then the fewest losses, over every completed
+/// battle; monsters deleted since are left out.
+pub fn get_leaderboard(db: &Database, size: usize) -> DbResult<Vec<LeaderboardEntry>> {
+    use super::schema::battles::dsl::{status, winner};
+    use super::schema::monsters::dsl as monsters;
+
+    let mut connection = db.get_connection()?;
+    let fought: Vec<(String, String, Option<String>)> = battles
+        .select((monster_a, monster_b, winner))
+        .filter(deleted_at.is_null())
+        .filter(status.eq(BattleStatus::Completed))
+        .load(&mut connection)?;
+    let mut records: HashMap<String, BattleRecord> = HashMap::new();
+    for (first, second, battle_winner) in fought {
+        for monster_id in [first, second] {
+            let record = records.entry(monster_id.clone()).or_default();
+            record.tally(&monster_id, battle_winner.as_deref());
+        }
+    }
+    let names: HashMap<String, String> = monsters::monsters
+        .select((monsters::id, monsters::name))
+        .filter(monsters::id.eq_any(records.keys()))
+        .load::<(String, String)>(&mut connection)?
+        .into_iter()
+        .collect();
+    let mut entries: Vec<LeaderboardEntry> = records
+        .into_iter()
+        .filter_map(|(monster_id, record)| {
+            let name = names.get(&monster_id)?.clone();
+            Some(LeaderboardEntry {
+                monster_id,
+                name,
+                record,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.record
+            .wins
+            .cmp(&a.record.wins)
+            .then(a.record.losses.cmp(&b.record.losses))
+            .then_with(|| a.monster_id.cmp(&b.monster_id))
+    });
+    entries.truncate(size);
+    Ok(entries)
+}
+
+pub fn create_battle(db: &Database, battle: Battle) -> DbResult<Battle> {
+    let mut connection = db.get_connection()?;
     let battle = Battle {
         id: uuid::Uuid::new_v4().to_string(),
         created_at: None,
         updated_at: None,
+        deleted_at: None,
         ..battle
     };
     diesel::insert_into(battles)
         .values(&battle)
-        .execute(&mut connection)
-        .expect("Error creating a new battle");
+        .execute(&mut connection)?;
     Ok(battle)
 }
 
-pub fn get_battle_by_id(db: &Database, battle_id: &str) -> Option<Battle> {
-    let mut connection = db.get_connection();
-    match battles
+/// Stores the simulated outcome of a pending battle and marks it completed.
+pub fn complete_battle(db: &Database, battle_id: &str, fought: Battle) -> DbResult<Battle> {
+    use super::schema::battles::dsl::{log, result, rounds, status, winner, winner_remaining_hp};
+    let mut connection = db.get_connection()?;
+    Ok(diesel::update(battles.find(battle_id))
+        .set((
+            winner.eq(fought.winner),
+            winner_remaining_hp.eq(fought.winner_remaining_hp),
+            rounds.eq(fought.rounds),
+            log.eq(fought.log),
+            result.eq(fought.result),
+            status.eq(BattleStatus::Completed),
+        ))
+        .get_result::<Battle>(&mut connection)?)
+}
+
+/// Marks a still pending battle as failed; returns how many rows changed.
+pub fn fail_battle(db: &Database, battle_id: &str) -> DbResult<usize> {
+    use super::schema::battles::dsl::status;
+    let mut connection = db.get_connection()?;
+    Ok(diesel::update(
+        battles
+            .find(battle_id)
+            .filter(status.eq(BattleStatus::Pending)),
+    )
+    .set(status.eq(BattleStatus::Failed))
+    .execute(&mut connection)?)
+}
+
+pub fn create_battles(db: &Database, new_battles: Vec<Battle>) -> DbResult<Vec<Battle>> {
+    let mut connection = db.get_connection()?;
+    let new_battles: Vec<Battle> = new_battles
+        .into_iter()
+        .map(|battle| Battle {
+            id: uuid::Uuid::new_v4().to_string(),
+            created_at: None,
+            updated_at: None,
+            deleted_at: None,
+            ..battle
+        })
+        .collect();
+    Ok(connection.transaction(|connection| {
+        diesel::insert_into(battles)
+            .values(&new_battles)
+            .get_results::<Battle>(connection)
+    })?)
+}
+
+pub fn get_battle_by_id(db: &Database, battle_id: &str) -> DbResult<Option<Battle>> {
+    let mut connection = db.get_connection()?;
+    Ok(battles
         .find(battle_id)
+        .filter(deleted_at.is_null())
+        .get_result::<Battle>(&mut connection)
+        .ok())
+}
+
+pub fn delete_battle_by_id(db: &Database, battle_id: &str) -> DbResult<Option<usize>> {
+    let mut connection = db.get_connection()?;
+
+    if let Ok(_existing_battle) = battles
+        .find(battle_id)
+        .filter(deleted_at.is_null())
         .get_result::<Battle>(&mut connection)
     {
-        Ok(battle) => Some(battle),
-        Err(_) => None,
+        let count = diesel::update(battles.find(battle_id))
+            .set(deleted_at.eq(diesel::dsl::now))
+            .execute(&mut connection)?;
+
+        Ok(Some(count))
+    } else {
+        Ok(None)
     }
 }
 
-pub fn delete_battle_by_id(db: &Database, battle_id: &str) -> Option<usize> {
-    let mut connection = db.get_connection();
+/// Clears the soft-delete mark; `None` when no battle, deleted or not, has this id.
+pub fn restore(db: &Database, battle_id: &str) -> DbResult<Option<Battle>> {
+    let mut connection = db.get_connection()?;
 
     if let Ok(_existing_battle) = battles
         .find(battle_id)
         .get_result::<Battle>(&mut connection)
     {
-        let count = diesel::delete(battles.find(battle_id))
-            .execute(&mut connection)
-            .expect("Error deleting battle by id");
+        let battle = diesel::update(battles.find(battle_id))
+            .set(deleted_at.eq(None::<chrono::NaiveDateTime>))
+            .get_result::<Battle>(&mut connection)?;
 
-        Some(count)
+        Ok(Some(battle))
     } else {
-        None
+        Ok(None)
     }
 }
@@ -1,25 +1,164 @@
 use diesel::r2d2::{self, ConnectionManager};
-use dotenvy::dotenv;
 use diesel::PgConnection;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::fmt;
+use std::time::Duration;
 
 type DBPool = r2d2::Pool<ConnectionManager<PgConnection>>;
 
+pub type PooledConnection = r2d2::PooledConnection<ConnectionManager<PgConnection>>;
+
+/// Why a repository call failed.
+#[derive(Debug)]
+pub enum DbError {
+    /// No pooled connection became free within the pool timeout; worth retrying.
+    Unavailable(r2d2::PoolError),
+    Query(diesel::result::Error),
+}
+
+pub type DbResult<T> = Result<T, DbError>;
+
+impl fmt::Display for DbError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DbError::Unavailable(err) => write!(f, "Database unavailable: {}", err),
+            DbError::Query(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for DbError {}
+
+impl From<r2d2::PoolError> for DbError {
+    fn from(err: r2d2::PoolError) -> Self {
+        DbError::Unavailable(err)
+    }
+}
+
+impl From<diesel::result::Error> for DbError {
+    fn from(err: diesel::result::Error) -> Self {
+        DbError::Query(err)
+    }
+}
+
+pub type MigrationError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Every migration under `migrations/`, compiled into the binary.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Applies the migrations this connection's schema has not seen yet and returns how many ran.
+pub fn run_pending_migrations(connection: &mut PgConnection) -> Result<usize, MigrationError> {
+    connection
+        .run_pending_migrations(MIGRATIONS)
+        .map(|applied| applied.len())
+}
+
+pub const DEFAULT_POOL_SIZE: u32 = 10;
+/// How long to wait for a free connection before answering 503.
+pub const DEFAULT_POOL_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct Database {
     pool: DBPool,
 }
 
+/// Snapshot of the connection pool: every open connection and how many are idle.
+#[derive(Debug, Clone, Copy)]
+pub struct PoolState {
+    pub connections: u32,
+    pub idle_connections: u32,
+}
+
 impl Database {
+    /// Pool of `DEFAULT_POOL_SIZE` connections to the configured database; the server sizes
+    /// its pool from `AppConfig` instead.
+    #[cfg(test)]
     pub fn new() -> Self {
-        dotenv().ok();
-        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+        let config = crate::config::AppConfig::from_env().expect("Invalid configuration");
+        Database::with_pool_size(&config.database_url, DEFAULT_POOL_SIZE, config.pool_timeout)
+    }
+
+    /// Pool of at most `max_size` connections that gives up waiting after `timeout`.
+    pub fn with_pool_size(database_url: &str, max_size: u32, timeout: Duration) -> Self {
         let manager = ConnectionManager::<PgConnection>::new(database_url);
         let pool: DBPool = r2d2::Pool::builder()
+            .max_size(max_size)
+            .connection_timeout(timeout)
             .build(manager)
             .expect("Failed to create pool.");
         Database { pool }
     }
 
-    pub fn get_connection(&self) -> r2d2::PooledConnection<ConnectionManager<PgConnection>> {
-        self.pool.get().expect("Failed to get a database connection")
+    /// Waits up to the pool timeout for a free connection.
+    pub fn get_connection(&self) -> DbResult<PooledConnection> {
+        Ok(self.pool.get()?)
+    }
+
+    pub fn run_migrations(&self) -> Result<usize, MigrationError> {
+        let mut connection = self.get_connection()?;
+        run_pending_migrations(&mut connection)
+    }
+
+    pub fn pool_state(&self) -> PoolState {
+        let state = self.pool.state();
+        PoolState {
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run_pending_migrations, Database};
+    use diesel::sql_types::Text;
+    use diesel::{sql_query, QueryableByName, RunQueryDsl};
+
+    #[derive(QueryableByName)]
+    struct TableName {
+        #[diesel(sql_type = Text)]
+        table_name: String,
+    }
+
+    #[test]
+    fn test_should_create_every_table_in_a_clean_schema() {
+        let db = Database::new();
+        let mut connection = db.get_connection().unwrap();
+        let schema = format!("migrations_{}", uuid::Uuid::new_v4().simple());
+        sql_query(format!("CREATE SCHEMA {}", schema))
+            .execute(&mut connection)
+            .unwrap();
+        sql_query(format!("SET search_path TO {}", schema))
+            .execute(&mut connection)
+            .unwrap();
+        let applied = run_pending_migrations(&mut connection);
+        let tables = sql_query(format!(
+            "SELECT table_name::text FROM information_schema.tables WHERE table_schema = '{}'",
+            schema
+        ))
+        .load::<TableName>(&mut connection);
+        sql_query(format!("DROP SCHEMA {} CASCADE", schema))
+            .execute(&mut connection)
+            .unwrap();
+        sql_query("SET search_path TO DEFAULT")
+            .execute(&mut connection)
+            .unwrap();
+
+        assert!(applied.unwrap() > 0);
+        let tables: Vec<String> = tables
+            .unwrap()
+            .into_iter()
+            .map(|table| table.table_name)
+            .collect();
+        assert!(tables.contains(&"monsters".to_string()));
+        assert!(tables.contains(&"battles".to_string()));
+    }
+
+    #[test]
+    fn test_should_report_a_checked_out_connection_as_not_idle() {
+        let db = Database::new();
+        let _connection = db.get_connection().unwrap();
+        let state = db.pool_state();
+        assert!(state.connections >= 1);
+        assert_eq!(state.idle_connections, state.connections - 1);
     }
 }
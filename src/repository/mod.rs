@@ -1,4 +1,6 @@
+pub mod admin_repository;
+pub mod battle_repository;
 pub mod database;
 pub mod monster_repository;
-pub mod battle_repository;
-pub mod schema;
\ No newline at end of file
+pub mod schema;
+pub mod seed_repository;
@@ -1,77 +1,413 @@
-use crate::models::monster::Monster;
-use crate::repository::{database::Database, schema::monsters::dsl::monsters};
+use crate::models::monster::{
+    normalize_tag, Monster, MonsterFilter, MonsterSort, PartialMonster, Stat, StringList,
+};
+use crate::models::pagination::Pagination;
+use crate::repository::{
+    database::{Database, DbResult},
+    schema,
+    schema::monsters::{
+        dsl::{
+            attack, created_at, created_by, defense, element, hp, id, monsters, name, speed, tags,
+            updated_at, version,
+        },
+        BoxedQuery,
+    },
+};
 use chrono::Utc;
-use diesel::{QueryDsl, RunQueryDsl};
+use diesel::dsl::sql;
+use diesel::sql_types::{BigInt, Bool, Text};
+use diesel::{
+    pg::Pg, BoolExpressionMethods, Connection, ExpressionMethods, OptionalExtension,
+    PgJsonbExpressionMethods, PgSortExpressionMethods, PgTextExpressionMethods, QueryDsl,
+    RunQueryDsl,
+};
 
-pub fn get_monsters(db: &Database) -> Vec<Monster> {
-    let mut connection = db.get_connection();
-    monsters
-        .load::<Monster>(&mut connection)
-        .expect("Error loading all monsters")
+diesel::define_sql_function!(fn lower(value: Text) -> Text);
+
+fn filtered_monsters(filter: &MonsterFilter) -> BoxedQuery<'_, Pg> {
+    let mut query = monsters.into_boxed();
+    if let Some(monster_element) = &filter.element {
+        query = query.filter(element.eq(monster_element));
+    }
+    if let Some(tag) = filter.tag.as_deref().and_then(normalize_tag) {
+        query = query.filter(tags.contains(StringList(vec![tag])));
+    }
+    if let Some(creator) = &filter.created_by {
+        query = query.filter(created_by.eq(creator));
+    }
+    query
+}
+
+/// Newest first; legacy rows without `created_at` come last.
+pub fn get_monsters(db: &Database, filter: &MonsterFilter) -> DbResult<Vec<Monster>> {
+    let mut connection = db.get_connection()?;
+    Ok(filtered_monsters(filter)
+        .order((created_at.desc().nulls_last(), id))
+        .load::<Monster>(&mut connection)?)
+}
+
+/// The requested page of monsters ordered by `sort`.
+pub fn get_monster_page(
+    db: &Database,
+    filter: &MonsterFilter,
+    sort: MonsterSort,
+    pagination: &Pagination,
+) -> DbResult<Vec<Monster>> {
+    let mut connection = db.get_connection()?;
+    let query = match sort {
+        MonsterSort::CreatedAt => {
+            filtered_monsters(filter).order((created_at.desc().nulls_last(), id))
+        }
+        // same formula as `Monster::power`, widened so extreme stats cannot overflow
+        MonsterSort::Power => filtered_monsters(filter).order((
+            sql::<BigInt>("(attack::bigint + defense::bigint + (hp / 10)::bigint + speed::bigint)")
+                .desc(),
+            created_at.desc().nulls_last(),
+            id,
+        )),
+    };
+    Ok(query
+        .limit(pagination.limit())
+        .offset(pagination.offset())
+        .load::<Monster>(&mut connection)?)
+}
+
+/// Up to `limit` monsters ordered by id, starting after `after_id` (keyset pagination).
+pub fn get_monsters_after(
+    db: &Database,
+    after_id: Option<&str>,
+    limit: i64,
+) -> DbResult<Vec<Monster>> {
+    let mut connection = db.get_connection()?;
+    let mut query = monsters.order(id).limit(limit).into_boxed();
+    if let Some(after_id) = after_id {
+        query = query.filter(id.gt(after_id));
+    }
+    Ok(query.load::<Monster>(&mut connection)?)
+}
+
+/// The subset of `names` already taken by a stored monster.
+pub fn existing_names(db: &Database, names: &[String]) -> DbResult<Vec<String>> {
+    let mut connection = db.get_connection()?;
+    Ok(monsters
+        .select(name)
+        .filter(name.eq_any(names))
+        .distinct()
+        .load::<String>(&mut connection)?)
 }
 
-pub fn create_monster(db: &Database, monster: Monster) -> Result<Monster, diesel::result::Error> {
-    let mut connection = db.get_connection();
+/// Whether a monster already uses `monster_name`, compared case-insensitively.
+pub fn name_taken(db: &Database, monster_name: &str) -> DbResult<bool> {
+    let mut connection = db.get_connection()?;
+    let taken = monsters
+        .filter(lower(name).eq(lower(monster_name)))
+        .count()
+        .get_result::<i64>(&mut connection)?;
+    Ok(taken > 0)
+}
+
+/// Escapes `LIKE` wildcards so `term` matches literally.
+fn escape_like(term: &str) -> String {
+    term.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Distinct monster names in order, optionally only those starting with `prefix` (ignoring case).
+pub fn names(db: &Database, prefix: Option<&str>) -> DbResult<Vec<String>> {
+    let mut connection = db.get_connection()?;
+    let mut query = monsters.select(name).distinct().order(name).into_boxed();
+    if let Some(prefix) = prefix {
+        query = query.filter(name.ilike(format!("{}%", escape_like(prefix))));
+    }
+    Ok(query.load::<String>(&mut connection)?)
+}
+
+/// Monsters whose name or one of whose aliases contains `term`, ignoring case.
+pub fn search(db: &Database, term: &str) -> DbResult<Vec<Monster>> {
+    let mut connection = db.get_connection()?;
+    let pattern = format!("%{}%", escape_like(term));
+    let alias_matches = sql::<Bool>(
+        "EXISTS (SELECT 1 FROM jsonb_array_elements_text(aliases) AS alias WHERE alias ILIKE ",
+    )
+    .bind::<Text, _>(pattern.clone())
+    .sql(")");
+    Ok(monsters
+        .filter(name.ilike(pattern).or(alias_matches))
+        .order(name)
+        .load::<Monster>(&mut connection)?)
+}
+
+pub fn count(db: &Database, filter: &MonsterFilter) -> DbResult<i64> {
+    let mut connection = db.get_connection()?;
+    Ok(filtered_monsters(filter)
+        .count()
+        .get_result::<i64>(&mut connection)?)
+}
+
+/// The chosen stat of every monster matching `filter`, without loading whole rows.
+pub fn stat_values(db: &Database, filter: &MonsterFilter, stat: Stat) -> DbResult<Vec<i32>> {
+    let mut connection = db.get_connection()?;
+    let query = filtered_monsters(filter);
+    Ok(match stat {
+        Stat::Attack => query.select(attack).load(&mut connection)?,
+        Stat::Defense => query.select(defense).load(&mut connection)?,
+        Stat::Hp => query.select(hp).load(&mut connection)?,
+        Stat::Speed => query.select(speed).load(&mut connection)?,
+    })
+}
+
+pub fn create_monster(db: &Database, monster: Monster) -> DbResult<Monster> {
+    let mut connection = db.get_connection()?;
     let monster = Monster {
         id: uuid::Uuid::new_v4().to_string(),
         created_at: None,
         updated_at: None,
+        version: 1,
         ..monster
     };
     diesel::insert_into(monsters)
         .values(&monster)
-        .execute(&mut connection)
-        .expect("Error creating a new monster");
+        .execute(&mut connection)?;
     Ok(monster)
 }
 
-pub fn get_monster_by_id(db: &Database, monster_id: &str) -> Option<Monster> {
-    let mut connection = db.get_connection();
-    match monsters
+pub fn create_monsters(db: &Database, new_monsters: Vec<Monster>) -> DbResult<Vec<Monster>> {
+    let mut connection = db.get_connection()?;
+    let new_monsters: Vec<Monster> = new_monsters
+        .into_iter()
+        .map(|monster| Monster {
+            id: uuid::Uuid::new_v4().to_string(),
+            created_at: None,
+            updated_at: None,
+            version: 1,
+            ..monster
+        })
+        .collect();
+    Ok(connection.transaction(|connection| {
+        diesel::insert_into(monsters)
+            .values(&new_monsters)
+            .get_results::<Monster>(connection)
+    })?)
+}
+
+pub fn get_monster_by_id(db: &Database, monster_id: &str) -> DbResult<Option<Monster>> {
+    let mut connection = db.get_connection()?;
+    Ok(monsters
         .find(monster_id)
         .get_result::<Monster>(&mut connection)
-    {
-        Ok(monster) => Some(monster),
-        Err(_) => None,
-    }
+        .ok())
 }
 
-pub fn delete_monster_by_id(db: &Database, monster_id: &str) -> Option<usize> {
-    let mut connection = db.get_connection();
+pub fn delete_monster_by_id(db: &Database, monster_id: &str) -> DbResult<Option<usize>> {
+    let mut connection = db.get_connection()?;
 
     if let Ok(_existing_monster) = monsters
         .find(monster_id)
         .get_result::<Monster>(&mut connection)
     {
-        let count = diesel::delete(monsters.find(monster_id))
-            .execute(&mut connection)
-            .expect("Error deleting monster by id");
+        let count = diesel::delete(monsters.find(monster_id)).execute(&mut connection)?;
 
-        Some(count)
+        Ok(Some(count))
     } else {
-        None
+        Ok(None)
+    }
+}
+
+/// Deletes the monster and every battle it fought, soft-deleted ones included, in one
+/// transaction. Returns how many battles went with it, `None` when the monster is missing.
+pub fn delete_with_battles(db: &Database, monster_id: &str) -> DbResult<Option<usize>> {
+    let mut connection = db.get_connection()?;
+    Ok(connection.transaction(|connection| {
+        let existing = monsters
+            .find(monster_id)
+            .select(id)
+            .first::<String>(connection)
+            .optional()?;
+        if existing.is_none() {
+            return Ok::<_, diesel::result::Error>(None);
+        }
+        let battles_deleted = diesel::delete(
+            schema::battles::table.filter(
+                schema::battles::monster_a
+                    .eq(monster_id)
+                    .or(schema::battles::monster_b.eq(monster_id))
+                    .or(schema::battles::winner.eq(monster_id)),
+            ),
+        )
+        .execute(connection)?;
+        diesel::delete(monsters.find(monster_id)).execute(connection)?;
+        Ok(Some(battles_deleted))
+    })?)
+}
+
+/// Every listed monster that exists, in no particular order.
+pub fn get_monsters_by_ids(db: &Database, monster_ids: &[String]) -> DbResult<Vec<Monster>> {
+    let mut connection = db.get_connection()?;
+    Ok(monsters
+        .filter(id.eq_any(monster_ids))
+        .load::<Monster>(&mut connection)?)
+}
+
+/// Deletes every listed monster in one transaction and returns the ids that existed.
+pub fn delete_many(db: &Database, monster_ids: &[String]) -> DbResult<Vec<String>> {
+    let mut connection = db.get_connection()?;
+    Ok(connection.transaction(|connection| {
+        diesel::delete(monsters.filter(schema::monsters::id.eq_any(monster_ids)))
+            .returning(schema::monsters::id)
+            .get_results::<String>(connection)
+    })?)
+}
+
+/// Rewrites every listed monster with `change` in one transaction, bumping its version.
+/// Ids without a monster are skipped; the first `Err` from `change` rolls everything back.
+pub fn update_many<E>(
+    db: &Database,
+    monster_ids: &[String],
+    mut change: impl FnMut(&mut Monster) -> Result<(), E>,
+) -> DbResult<Result<Vec<Monster>, E>> {
+    let mut connection = db.get_connection()?;
+    let mut rejected = None;
+    let updated = connection.transaction(|connection| {
+        let found = monsters
+            .filter(id.eq_any(monster_ids))
+            .order(id)
+            .for_update()
+            .load::<Monster>(connection)?;
+        let mut updated = Vec::new();
+        for mut monster in found {
+            if let Err(err) = change(&mut monster) {
+                rejected = Some(err);
+                return Err(diesel::result::Error::RollbackTransaction);
+            }
+            monster.updated_at = Some(Utc::now().naive_utc());
+            monster.version += 1;
+            updated.push(
+                diesel::update(monsters.find(&monster.id))
+                    .set(&monster)
+                    .get_result::<Monster>(connection)?,
+            );
+        }
+        Ok(updated)
+    });
+    match rejected {
+        Some(err) => Ok(Err(err)),
+        None => Ok(Ok(updated?)),
     }
 }
 
+pub enum VersionedUpdate {
+    Updated(Box<Monster>),
+    Stale,
+    NotFound,
+}
+
 pub fn update_monster_by_id(
     db: &Database,
     monster_id: &str,
     mut monster: Monster,
-) -> Option<Monster> {
-    let mut connection = db.get_connection();
+    expected_version: i32,
+) -> DbResult<VersionedUpdate> {
+    let mut connection = db.get_connection()?;
 
-    if let Ok(_existing_monster) = monsters
+    if let Ok(existing_monster) = monsters
         .find(monster_id)
         .get_result::<Monster>(&mut connection)
     {
         monster.updated_at = Some(Utc::now().naive_utc());
-        let updated_monster = diesel::update(monsters.find(monster_id))
-            .set(&monster)
-            .get_result::<Monster>(&mut connection)
-            .expect("Error updating monster by id");
+        monster.version = expected_version + 1;
+        monster.level = existing_monster.level;
+        let updated_monsters = diesel::update(
+            monsters
+                .find(monster_id)
+                .filter(version.eq(expected_version)),
+        )
+        .set(&monster)
+        .get_results::<Monster>(&mut connection)?;
+
+        //no affected row means someone else updated it first
+        match updated_monsters.into_iter().next() {
+            Some(updated_monster) => Ok(VersionedUpdate::Updated(Box::new(updated_monster))),
+            None => Ok(VersionedUpdate::Stale),
+        }
+    } else {
+        Ok(VersionedUpdate::NotFound)
+    }
+}
+
+pub fn patch_monster_by_id(
+    db: &Database,
+    monster_id: &str,
+    mut changes: PartialMonster,
+) -> DbResult<Option<Monster>> {
+    let mut connection = db.get_connection()?;
+
+    if let Ok(_existing_monster) = monsters
+        .find(monster_id)
+        .get_result::<Monster>(&mut connection)
+    {
+        changes.updated_at = Some(Utc::now().naive_utc());
+        let patched_monster = diesel::update(monsters.find(monster_id))
+            .set((&changes, version.eq(version + 1)))
+            .get_result::<Monster>(&mut connection)?;
 
-        Some(updated_monster)
+        Ok(Some(patched_monster))
     } else {
-        None
+        Ok(None)
     }
 }
+
+/// Raises the monster one level, growing its stats by `percent`.
+pub fn level_up(db: &Database, monster_id: &str, percent: i32) -> DbResult<Option<Monster>> {
+    let mut connection = db.get_connection()?;
+    Ok(connection.transaction(|connection| {
+        let monster = match monsters
+            .find(monster_id)
+            .for_update()
+            .get_result::<Monster>(connection)
+            .optional()?
+        {
+            Some(monster) => monster,
+            None => return Ok(None),
+        };
+        let mut leveled = monster.leveled_up(percent);
+        leveled.updated_at = Some(Utc::now().naive_utc());
+        leveled.version += 1;
+        diesel::update(monsters.find(monster_id))
+            .set(&leveled)
+            .get_result::<Monster>(connection)
+            .map(Some)
+    })?)
+}
+
+/// Rewrites the monster's tags with `change`, keeping them sorted and unique.
+pub fn update_tags(
+    db: &Database,
+    monster_id: &str,
+    change: impl FnOnce(&mut Vec<String>),
+) -> DbResult<Option<Monster>> {
+    let mut connection = db.get_connection()?;
+    Ok(connection.transaction(|connection| {
+        let monster = match monsters
+            .find(monster_id)
+            .for_update()
+            .get_result::<Monster>(connection)
+            .optional()?
+        {
+            Some(monster) => monster,
+            None => return Ok(None),
+        };
+        let mut new_tags = monster.tags.0;
+        change(&mut new_tags);
+        new_tags.sort();
+        new_tags.dedup();
+        diesel::update(monsters.find(monster_id))
+            .set((
+                tags.eq(StringList(new_tags)),
+                updated_at.eq(Utc::now().naive_utc()),
+                version.eq(version + 1),
+            ))
+            .get_result::<Monster>(connection)
+            .map(Some)
+    })?)
+}
@@ -5,9 +5,15 @@ diesel::table! {
         id -> Varchar,
         monster_a -> Varchar,
         monster_b -> Varchar,
-        winner -> Varchar,
+        winner -> Nullable<Varchar>,
         created_at -> Nullable<Timestamp>,
         updated_at -> Nullable<Timestamp>,
+        winner_remaining_hp -> Int4,
+        rounds -> Int4,
+        deleted_at -> Nullable<Timestamp>,
+        log -> Nullable<Jsonb>,
+        result -> Varchar,
+        status -> Varchar,
     }
 }
 
@@ -22,6 +28,14 @@ diesel::table! {
         speed -> Int4,
         created_at -> Nullable<Timestamp>,
         updated_at -> Nullable<Timestamp>,
+        element -> Varchar,
+        version -> Int4,
+        aliases -> Jsonb,
+        tags -> Jsonb,
+        created_by -> Nullable<Varchar>,
+        level -> Int4,
+        available_from -> Nullable<Timestamp>,
+        available_to -> Nullable<Timestamp>,
     }
 }
 
@@ -0,0 +1,54 @@
+use super::database::{Database, DbResult};
+use super::schema::monsters::dsl::{id, monsters};
+use crate::models::monster::Monster;
+use diesel::RunQueryDsl;
+
+/// Demo monsters inserted by `SEED_ON_START`; the fixed ids make reseeding a no-op.
+const SEED_JSON: &str = include_str!("../../seed.json");
+
+pub fn seed_monsters() -> Vec<Monster> {
+    let seed: Vec<Monster> = serde_json::from_str(SEED_JSON).expect("seed.json is not valid");
+    seed.into_iter()
+        .map(|monster| Monster {
+            created_at: None,
+            updated_at: None,
+            version: 1,
+            ..monster
+        })
+        .collect()
+}
+
+/// Inserts every seed monster that is not stored yet and returns how many were added.
+pub fn seed(db: &Database) -> DbResult<usize> {
+    let mut connection = db.get_connection()?;
+    Ok(diesel::insert_into(monsters)
+        .values(&seed_monsters())
+        .on_conflict(id)
+        .do_nothing()
+        .execute(&mut connection)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{seed, seed_monsters};
+    use crate::repository::database::Database;
+    use crate::repository::schema::monsters::dsl::{id, monsters};
+    use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+    #[actix_rt::test]
+    async fn test_should_not_duplicate_monsters_when_seeding_twice() {
+        let db = Database::new();
+        seed(&db).expect("first seed should succeed");
+        assert_eq!(seed(&db).expect("second seed should succeed"), 0);
+        let seed_ids: Vec<String> = seed_monsters()
+            .into_iter()
+            .map(|monster| monster.id)
+            .collect();
+        let stored: i64 = monsters
+            .filter(id.eq_any(&seed_ids))
+            .count()
+            .get_result(&mut db.get_connection().unwrap())
+            .unwrap();
+        assert_eq!(stored, seed_ids.len() as i64);
+    }
+}
@@ -0,0 +1,194 @@
+use crate::api::errors::ApiError;
+use crate::config::AppConfig;
+use actix_web::{
+    body::{EitherBody, MessageBody},
+    dev::{ServiceRequest, ServiceResponse},
+    http::Method,
+    middleware::Next,
+    Error, HttpMessage, HttpRequest, ResponseError,
+};
+
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Identity recorded for the single `API_KEY`.
+pub const DEFAULT_IDENTITY: &str = "api_key";
+
+/// Who sent an authenticated request, stored in the request extensions.
+#[derive(Debug, Clone)]
+pub struct Identity(pub String);
+
+/// Accepted `X-API-Key` values with the identity each one stands for; none leaves the API open.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKey(Vec<(String, String)>);
+
+impl ApiKey {
+    #[cfg(test)]
+    pub fn new(key: Option<String>) -> Self {
+        ApiKey::with_identities(
+            key.into_iter()
+                .map(|key| (DEFAULT_IDENTITY.to_string(), key))
+                .collect(),
+        )
+    }
+
+    /// `(identity, key)` pairs, so monsters can record which client created them.
+    pub fn with_identities(keys: Vec<(String, String)>) -> Self {
+        ApiKey(
+            keys.into_iter()
+                .filter(|(identity, key)| !identity.is_empty() && !key.is_empty())
+                .collect(),
+        )
+    }
+
+    /// No key is configured, so mutating routes are not authenticated.
+    pub fn is_open(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// `Ok(None)` when the API is open, the identity of the matching key, or `Err` when no
+    /// key matches.
+    fn authenticate(&self, provided: Option<&str>) -> Result<Option<String>, ()> {
+        if self.is_open() {
+            return Ok(None);
+        }
+        self.0
+            .iter()
+            .find(|(_, key)| Some(key.as_str()) == provided)
+            .map(|(identity, _)| Some(identity.clone()))
+            .ok_or(())
+    }
+}
+
+/// Parses `alice:key1,bob:key2`; entries without a `:` are skipped.
+pub fn parse_named_keys(value: &str) -> Vec<(String, String)> {
+    value
+        .split(',')
+        .filter_map(|entry| entry.split_once(':'))
+        .map(|(identity, key)| (identity.trim().to_string(), key.trim().to_string()))
+        .collect()
+}
+
+/// Identity attached by `require_api_key`; `None` when auth is off or the request is a read.
+pub fn identity(req: &HttpRequest) -> Option<String> {
+    req.extensions()
+        .get::<Identity>()
+        .map(|identity| identity.0.clone())
+}
+
+/// Rejects mutating requests without a valid `X-API-Key` header; reads stay public.
+pub async fn require_api_key(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<EitherBody<impl MessageBody>>, Error> {
+    let is_read = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+    if !is_read {
+        let provided = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok());
+        let authenticated = AppConfig::of(req.request()).api_key.authenticate(provided);
+        match authenticated {
+            Ok(Some(identity)) => {
+                req.extensions_mut().insert(Identity(identity));
+            }
+            Ok(None) => {}
+            Err(()) => {
+                let error = ApiError::Unauthorized("Invalid or missing API key".to_string());
+                return Ok(req
+                    .into_response(error.error_response())
+                    .map_into_right_body());
+            }
+        }
+    }
+    Ok(next.call(req).await?.map_into_left_body())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{identity, parse_named_keys, require_api_key, ApiKey, API_KEY_HEADER};
+    use crate::config::AppConfig;
+    use actix_web::{
+        http::StatusCode, middleware::from_fn, test, web, App, HttpRequest, HttpResponse,
+    };
+
+    #[actix_rt::test]
+    async fn test_should_allow_reads_and_requests_with_the_right_key() {
+        let app = App::new()
+            .app_data(web::Data::new(AppConfig {
+                api_key: ApiKey::new(Some("secret".to_string())),
+                ..AppConfig::default()
+            }))
+            .wrap(from_fn(require_api_key))
+            .route("/monsters", web::get().to(HttpResponse::Ok))
+            .route("/monsters", web::post().to(HttpResponse::Created));
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/monsters").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let req = test::TestRequest::post()
+            .uri("/monsters")
+            .insert_header((API_KEY_HEADER, "secret"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_reject_mutations_with_a_missing_or_wrong_key() {
+        let app = App::new()
+            .app_data(web::Data::new(AppConfig {
+                api_key: ApiKey::new(Some("secret".to_string())),
+                ..AppConfig::default()
+            }))
+            .wrap(from_fn(require_api_key))
+            .route("/monsters", web::delete().to(HttpResponse::NoContent));
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::delete().uri("/monsters").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let req = test::TestRequest::delete()
+            .uri("/monsters")
+            .insert_header((API_KEY_HEADER, "guess"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_allow_mutations_when_no_key_is_configured() {
+        let app = App::new()
+            .app_data(web::Data::new(AppConfig {
+                api_key: ApiKey::new(None),
+                ..AppConfig::default()
+            }))
+            .wrap(from_fn(require_api_key))
+            .route("/monsters", web::post().to(HttpResponse::Created));
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post().uri("/monsters").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::CREATED);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_attach_the_identity_of_the_key_used() {
+        async fn whoami(req: HttpRequest) -> HttpResponse {
+            HttpResponse::Ok().json(identity(&req))
+        }
+        let keys = parse_named_keys("alice:key-a, bob:key-b,broken");
+        assert_eq!(keys.len(), 2);
+        let app = App::new()
+            .app_data(web::Data::new(AppConfig {
+                api_key: ApiKey::with_identities(keys),
+                ..AppConfig::default()
+            }))
+            .wrap(from_fn(require_api_key))
+            .route("/whoami", web::post().to(whoami));
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::post()
+            .uri("/whoami")
+            .insert_header((API_KEY_HEADER, "key-b"))
+            .to_request();
+        let who: Option<String> = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(who.as_deref(), Some("bob"));
+    }
+}
@@ -0,0 +1,85 @@
+use std::io;
+use std::path::Path;
+
+pub const DEFAULT_MIN_FREE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Free space the temp dir must keep on top of an upload before an import may start.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskSpace {
+    min_free_bytes: u64,
+}
+
+impl DiskSpace {
+    pub fn new(min_free_bytes: u64) -> Self {
+        DiskSpace { min_free_bytes }
+    }
+
+    /// Explains why an upload of `declared` bytes does not fit in `available` bytes.
+    pub fn check(&self, available: u64, declared: Option<u64>) -> Result<(), String> {
+        let needed = self.min_free_bytes.saturating_add(declared.unwrap_or(0));
+        if available >= needed {
+            return Ok(());
+        }
+        Err(format!(
+            "Not enough disk space to import: {} bytes free, {} needed",
+            available, needed
+        ))
+    }
+}
+
+impl Default for DiskSpace {
+    fn default() -> Self {
+        DiskSpace::new(DEFAULT_MIN_FREE_BYTES)
+    }
+}
+
+/// Bytes an unprivileged process may still write on the filesystem holding `path`.
+#[cfg(unix)]
+pub fn available_bytes(path: &Path) -> io::Result<u64> {
+    use std::ffi::CString;
+    use std::mem::MaybeUninit;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let mut stats = MaybeUninit::<libc::statvfs>::uninit();
+    // SAFETY: `path` is NUL-terminated and `stats` is only read after statvfs filled it in
+    let stats = unsafe {
+        if libc::statvfs(path.as_ptr(), stats.as_mut_ptr()) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        stats.assume_init()
+    };
+    // the field widths differ between platforms
+    #[allow(clippy::unnecessary_cast)]
+    Ok(stats.f_bavail as u64 * stats.f_frsize as u64)
+}
+
+#[cfg(not(unix))]
+pub fn available_bytes(_path: &Path) -> io::Result<u64> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{available_bytes, DiskSpace};
+
+    #[test]
+    fn test_should_require_the_threshold_plus_the_declared_upload() {
+        let disk_space = DiskSpace::new(1000);
+        assert!(disk_space.check(1000, None).is_ok());
+        assert!(disk_space.check(999, None).is_err());
+        assert!(disk_space.check(1500, Some(500)).is_ok());
+        assert_eq!(
+            disk_space.check(1400, Some(500)),
+            Err("Not enough disk space to import: 1400 bytes free, 1500 needed".to_string())
+        );
+        assert!(DiskSpace::new(u64::MAX)
+            .check(u64::MAX - 1, Some(10))
+            .is_err());
+    }
+
+    #[test]
+    fn test_should_read_the_free_space_of_the_temp_dir() {
+        assert!(available_bytes(&std::env::temp_dir()).unwrap() > 0);
+    }
+}
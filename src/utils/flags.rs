@@ -0,0 +1,50 @@
+/// `1` or `true` (any case) turns a flag on; anything else, or unset, leaves it off.
+pub fn parse_flag(value: Option<&str>) -> bool {
+    value.is_some_and(|value| {
+        let value = value.trim();
+        value == "1" || value.eq_ignore_ascii_case("true")
+    })
+}
+
+/// Whether monster names must be unique, ignoring case.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniqueNames(bool);
+
+impl UniqueNames {
+    pub fn new(enabled: bool) -> Self {
+        UniqueNames(enabled)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.0
+    }
+}
+
+/// Whether `POST /admin/reset` may wipe the database.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdminReset(bool);
+
+impl AdminReset {
+    pub fn new(enabled: bool) -> Self {
+        AdminReset(enabled)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_flag;
+
+    #[actix_rt::test]
+    async fn test_should_parse_boolean_flags() {
+        assert!(parse_flag(Some("true")));
+        assert!(parse_flag(Some(" TRUE ")));
+        assert!(parse_flag(Some("1")));
+        assert!(!parse_flag(Some("false")));
+        assert!(!parse_flag(Some("yes please")));
+        assert!(!parse_flag(None));
+    }
+}
@@ -0,0 +1,38 @@
+use url::Url;
+
+/// Hostnames `image_url` may point at; `None` lets any host through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageHosts {
+    allowed: Option<Vec<String>>,
+}
+
+impl ImageHosts {
+    pub fn new(allowed: Option<Vec<String>>) -> Self {
+        ImageHosts { allowed }
+    }
+
+    /// Comma-separated hostnames, compared ignoring case; an empty list allows any host.
+    pub fn parse(value: Option<&str>) -> Self {
+        let allowed: Vec<String> = value
+            .unwrap_or_default()
+            .split(',')
+            .map(|host| host.trim().to_lowercase())
+            .filter(|host| !host.is_empty())
+            .collect();
+        ImageHosts::new((!allowed.is_empty()).then_some(allowed))
+    }
+
+    /// Explains why `image_url` is refused, if it is.
+    pub fn check(&self, image_url: &str) -> Result<(), String> {
+        let Some(allowed) = &self.allowed else {
+            return Ok(());
+        };
+        let url = Url::parse(image_url)
+            .map_err(|_| format!("image_url {} is not a valid URL", image_url))?;
+        match url.host_str() {
+            Some(host) if allowed.iter().any(|allowed| allowed == host) => Ok(()),
+            Some(host) => Err(format!("image_url host {} is not allowed", host)),
+            None => Err(format!("image_url {} has no host", image_url)),
+        }
+    }
+}
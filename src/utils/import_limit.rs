@@ -0,0 +1,32 @@
+use crate::config::AppConfig;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+pub const DEFAULT_MAX_CONCURRENT_IMPORTS: usize = 2;
+
+/// Caps how many CSV imports may run at the same time.
+pub struct ImportLimit {
+    semaphore: Semaphore,
+}
+
+impl ImportLimit {
+    pub fn new(max_concurrent_imports: usize) -> Self {
+        ImportLimit {
+            semaphore: Semaphore::new(max_concurrent_imports),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        ImportLimit::new(config.max_concurrent_imports)
+    }
+
+    /// Returns `None` when every import slot is already taken.
+    pub fn try_acquire(&self) -> Option<SemaphorePermit<'_>> {
+        self.semaphore.try_acquire().ok()
+    }
+}
+
+impl Default for ImportLimit {
+    fn default() -> Self {
+        ImportLimit::new(DEFAULT_MAX_CONCURRENT_IMPORTS)
+    }
+}
@@ -0,0 +1,97 @@
+use crate::config::AppConfig;
+use crate::models::battle::LeaderboardEntry;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_LEADERBOARD_TTL: Duration = Duration::from_secs(30);
+/// `HIT` when `/leaderboard` was answered from the cache, `MISS` otherwise.
+pub const CACHE_HEADER: &str = "X-Cache";
+
+#[derive(Default)]
+struct Cached {
+    /// Bumped by every invalidation, so a leaderboard computed before one is never stored.
+    generation: u64,
+    entries: Option<(Instant, Vec<LeaderboardEntry>)>,
+}
+
+/// Last `/leaderboard` response, reused until it is `ttl` old or a battle is created.
+pub struct LeaderboardCache {
+    ttl: Duration,
+    cached: RwLock<Cached>,
+}
+
+impl LeaderboardCache {
+    pub fn new(ttl: Duration) -> Self {
+        LeaderboardCache {
+            ttl,
+            cached: RwLock::new(Cached::default()),
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        LeaderboardCache::new(config.leaderboard_cache_ttl)
+    }
+
+    /// The cached leaderboard while it is fresh, or `Err` with the generation to `store` a
+    /// newly computed one under.
+    pub fn lookup(&self) -> Result<Vec<LeaderboardEntry>, u64> {
+        let cached = self.cached.read().unwrap_or_else(|err| err.into_inner());
+        match &cached.entries {
+            Some((stored_at, entries)) if stored_at.elapsed() < self.ttl => Ok(entries.clone()),
+            _ => Err(cached.generation),
+        }
+    }
+
+    /// Keeps `entries` unless the cache was invalidated since `generation` was looked up.
+    pub fn store(&self, generation: u64, entries: Vec<LeaderboardEntry>) {
+        let mut cached = self.cached.write().unwrap_or_else(|err| err.into_inner());
+        if cached.generation == generation {
+            cached.entries = Some((Instant::now(), entries));
+        }
+    }
+
+    pub fn invalidate(&self) {
+        let mut cached = self.cached.write().unwrap_or_else(|err| err.into_inner());
+        cached.generation += 1;
+        cached.entries = None;
+    }
+}
+
+impl Default for LeaderboardCache {
+    fn default() -> Self {
+        LeaderboardCache::new(DEFAULT_LEADERBOARD_TTL)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LeaderboardCache;
+    use std::time::Duration;
+
+    #[test]
+    fn test_should_serve_a_stored_leaderboard_until_it_is_invalidated() {
+        let cache = LeaderboardCache::default();
+        let generation = cache.lookup().expect_err("an empty cache should miss");
+        cache.store(generation, Vec::new());
+        assert_eq!(cache.lookup(), Ok(Vec::new()));
+        cache.invalidate();
+        assert!(cache.lookup().is_err());
+    }
+
+    #[test]
+    fn test_should_drop_a_leaderboard_computed_before_an_invalidation() {
+        let cache = LeaderboardCache::default();
+        let generation = cache.lookup().unwrap_err();
+        cache.invalidate();
+        cache.store(generation, Vec::new());
+        assert!(cache.lookup().is_err());
+    }
+
+    #[test]
+    fn test_should_miss_once_the_ttl_has_passed() {
+        let cache = LeaderboardCache::new(Duration::ZERO);
+        let generation = cache.lookup().unwrap_err();
+        cache.store(generation, Vec::new());
+        assert!(cache.lookup().is_err());
+    }
+}
@@ -0,0 +1,214 @@
+use super::request_id::REQUEST_ID_HEADER;
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    middleware::Next,
+    Error,
+};
+use env_logger::{Builder, Env, Target};
+use log::LevelFilter;
+use serde_json::{json, Value};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// `log` target of the `json_logger` entries, written without a header so each line stays
+/// one JSON object.
+pub const ACCESS_LOG_TARGET: &str = "access";
+
+/// Parses a `LOG_LEVEL` value such as `debug` or `OFF`; anything else is `None`.
+pub fn parse_level(value: &str) -> Option<LevelFilter> {
+    value.trim().parse().ok()
+}
+
+/// Copies every write to both writers, e.g. the console and `LOG_FILE`.
+pub struct Tee<A, B>(pub A, pub B);
+
+impl<A: Write, B: Write> Write for Tee<A, B> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write_all(buf)?;
+        self.1.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()?;
+        self.1.flush()
+    }
+}
+
+/// Sets up `env_logger`: a `RUST_LOG` filter wins, then `LOG_LEVEL`, then `info`. With
+/// `LOG_FILE` set, lines are appended to that file as well as the console.
+pub fn init() {
+    let mut builder = if std::env::var_os("RUST_LOG").is_some() {
+        Builder::from_env(Env::default())
+    } else {
+        let level = std::env::var("LOG_LEVEL")
+            .ok()
+            .and_then(|value| parse_level(&value))
+            .unwrap_or(LevelFilter::Info);
+        let mut builder = Builder::new();
+        builder.filter_level(level);
+        builder
+    };
+    let mut file_error = None;
+    if let Ok(path) = std::env::var("LOG_FILE") {
+        match OpenOptions::new().create(true).append(true).open(&path) {
+            Ok(file) => {
+                builder.target(Target::Pipe(Box::new(Tee(std::io::stderr(), file))));
+            }
+            Err(err) => file_error = Some(format!("Could not open LOG_FILE {}: {}", path, err)),
+        }
+    }
+    builder.format(|buf, record| {
+        if record.target() == ACCESS_LOG_TARGET {
+            return writeln!(buf, "{}", record.args());
+        }
+        writeln!(
+            buf,
+            "[{} {:<5} {}] {}",
+            buf.timestamp(),
+            buf.default_styled_level(record.level()),
+            record.target(),
+            record.args()
+        )
+    });
+    builder.init();
+    if let Some(message) = file_error {
+        log::warn!("{}", message);
+    }
+}
+
+pub fn log_entry(
+    method: &str,
+    path: &str,
+    status: u16,
+    duration: Duration,
+    request_id: Option<&str>,
+) -> Value {
+    json!({
+        "request_id": request_id,
+        "method": method,
+        "path": path,
+        "status": status,
+        "duration_ms": duration.as_secs_f64() * 1000.0,
+    })
+}
+
+/// Logs one JSON object per request under `ACCESS_LOG_TARGET` for log aggregators, so the
+/// entries reach `LOG_FILE` like every other line.
+pub async fn json_logger(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let started_at = Instant::now();
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let res = next.call(req).await?;
+    let request_id = res
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok());
+    let entry = log_entry(
+        &method,
+        &path,
+        res.status().as_u16(),
+        started_at.elapsed(),
+        request_id,
+    );
+    log::info!(target: ACCESS_LOG_TARGET, "{}", entry);
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{json_logger, log_entry, parse_level, Tee, ACCESS_LOG_TARGET};
+    use actix_web::{http::StatusCode, middleware::from_fn, test, web, App, HttpResponse};
+    use log::{LevelFilter, Log, Metadata, Record};
+    use std::io::Write;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    /// Keeps every `ACCESS_LOG_TARGET` message logged while the tests run.
+    struct AccessLog(Mutex<Vec<String>>);
+
+    impl Log for AccessLog {
+        fn enabled(&self, _: &Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record) {
+            if record.target() == ACCESS_LOG_TARGET {
+                self.0.lock().unwrap().push(record.args().to_string());
+            }
+        }
+
+        fn flush(&self) {}
+    }
+
+    static ACCESS_LOG: AccessLog = AccessLog(Mutex::new(Vec::new()));
+
+    #[actix_rt::test]
+    async fn test_should_parse_log_levels_ignoring_case() {
+        assert_eq!(parse_level("debug"), Some(LevelFilter::Debug));
+        assert_eq!(parse_level(" WARN "), Some(LevelFilter::Warn));
+        assert_eq!(parse_level("off"), Some(LevelFilter::Off));
+        assert_eq!(parse_level("loud"), None);
+        assert_eq!(parse_level(""), None);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_write_every_line_to_both_sides_of_a_tee() {
+        let mut tee = Tee(Vec::new(), Vec::new());
+        writeln!(tee, "monster created").unwrap();
+        tee.flush().unwrap();
+        assert_eq!(tee.0, b"monster created\n");
+        assert_eq!(tee.1, b"monster created\n");
+    }
+
+    #[actix_rt::test]
+    async fn test_should_build_a_json_log_entry() {
+        let entry = log_entry(
+            "GET",
+            "/api/monsters",
+            200,
+            Duration::from_millis(12),
+            Some("trace-123"),
+        );
+        assert_eq!(entry["request_id"], "trace-123");
+        assert_eq!(entry["method"], "GET");
+        assert_eq!(entry["path"], "/api/monsters");
+        assert_eq!(entry["status"], 200);
+        assert_eq!(entry["duration_ms"], 12.0);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_pass_responses_through_the_json_logger() {
+        let app = App::new()
+            .wrap(from_fn(json_logger))
+            .route("/teapot", web::get().to(HttpResponse::ImATeapot));
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/teapot").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::IM_A_TEAPOT);
+    }
+
+    #[actix_rt::test]
+    async fn test_should_send_json_entries_through_the_logger() {
+        let _ = log::set_logger(&ACCESS_LOG);
+        log::set_max_level(LevelFilter::Info);
+        let app = App::new()
+            .wrap(from_fn(json_logger))
+            .route("/logged-teapot", web::get().to(HttpResponse::ImATeapot));
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/logged-teapot").to_request();
+        test::call_service(&app, req).await;
+        let entries = ACCESS_LOG.0.lock().unwrap();
+        let entry: serde_json::Value = entries
+            .iter()
+            .map(|line| serde_json::from_str(line).expect("entry should be one JSON object"))
+            .find(|entry: &serde_json::Value| entry["path"] == "/logged-teapot")
+            .expect("the request should be logged");
+        assert_eq!(entry["status"], 418);
+    }
+}
@@ -0,0 +1,159 @@
+use crate::repository::database::PoolState;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+const BATTLE_ROUNDS_BUCKETS: [u64; 7] = [1, 2, 5, 10, 20, 50, 100];
+
+/// Shared by every worker; each field is a lone atomic so recording never takes a lock.
+#[derive(Default)]
+pub struct Metrics {
+    monsters_created_total: AtomicU64,
+    battles_created_total: AtomicU64,
+    /// Battles per bucket, not cumulative; the last slot counts those past every bound.
+    battle_rounds_buckets: [AtomicU64; BATTLE_ROUNDS_BUCKETS.len() + 1],
+    battle_rounds_sum: AtomicU64,
+}
+
+impl Metrics {
+    pub fn inc_monsters_created(&self, count: u64) {
+        self.monsters_created_total
+            .fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn inc_battles_created(&self) {
+        self.battles_created_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn observe_battle_rounds(&self, rounds: u64) {
+        let bucket = BATTLE_ROUNDS_BUCKETS
+            .iter()
+            .position(|upper_bound| rounds <= *upper_bound)
+            .unwrap_or(BATTLE_ROUNDS_BUCKETS.len());
+        self.battle_rounds_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.battle_rounds_sum.fetch_add(rounds, Ordering::Relaxed);
+    }
+
+    /// Renders every metric in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        write_counter(
+            &mut output,
+            "monsters_created_total",
+            "Total number of monsters created.",
+            &self.monsters_created_total,
+        );
+        write_counter(
+            &mut output,
+            "battles_created_total",
+            "Total number of battles created.",
+            &self.battles_created_total,
+        );
+
+        let _ = writeln!(
+            output,
+            "# HELP battle_rounds Number of rounds fought per battle."
+        );
+        let _ = writeln!(output, "# TYPE battle_rounds histogram");
+        // cumulative as Prometheus expects, and `+Inf` always equals the count
+        let mut count = 0;
+        for (bucket, upper_bound) in self.battle_rounds_buckets.iter().zip(BATTLE_ROUNDS_BUCKETS) {
+            count += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(
+                output,
+                "battle_rounds_bucket{{le=\"{}\"}} {}",
+                upper_bound, count
+            );
+        }
+        count += self.battle_rounds_buckets[BATTLE_ROUNDS_BUCKETS.len()].load(Ordering::Relaxed);
+        let _ = writeln!(output, "battle_rounds_bucket{{le=\"+Inf\"}} {}", count);
+        let _ = writeln!(
+            output,
+            "battle_rounds_sum {}",
+            self.battle_rounds_sum.load(Ordering::Relaxed)
+        );
+        let _ = writeln!(output, "battle_rounds_count {}", count);
+        output
+    }
+}
+
+/// Renders the database pool gauges, appended to `Metrics::render` by `/metrics`.
+pub fn render_pool_state(state: &PoolState) -> String {
+    let mut output = String::new();
+    write_gauge(
+        &mut output,
+        "db_pool_connections",
+        "Open database connections, idle or in use.",
+        state.connections,
+    );
+    write_gauge(
+        &mut output,
+        "db_pool_idle_connections",
+        "Database connections waiting in the pool.",
+        state.idle_connections,
+    );
+    output
+}
+
+fn write_gauge(output: &mut String, name: &str, help: &str, value: u32) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} gauge", name);
+    let _ = writeln!(output, "{} {}", name, value);
+}
+
+fn write_counter(output: &mut String, name: &str, help: &str, counter: &AtomicU64) {
+    let _ = writeln!(output, "# HELP {} {}", name, help);
+    let _ = writeln!(output, "# TYPE {} counter", name);
+    let _ = writeln!(output, "{} {}", name, counter.load(Ordering::Relaxed));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_pool_state, Metrics};
+    use crate::repository::database::PoolState;
+
+    #[test]
+    fn test_should_render_cumulative_battle_round_buckets() {
+        let metrics = Metrics::default();
+        metrics.observe_battle_rounds(3);
+        metrics.observe_battle_rounds(40);
+        let output = metrics.render();
+        assert!(output.contains("battle_rounds_bucket{le=\"2\"} 0"));
+        assert!(output.contains("battle_rounds_bucket{le=\"5\"} 1"));
+        assert!(output.contains("battle_rounds_bucket{le=\"50\"} 2"));
+        assert!(output.contains("battle_rounds_bucket{le=\"+Inf\"} 2"));
+        assert!(output.contains("battle_rounds_sum 43"));
+        assert!(output.contains("battle_rounds_count 2"));
+    }
+
+    #[test]
+    fn test_should_count_every_battle_observed_from_many_threads() {
+        let metrics = Metrics::default();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for rounds in [1, 7, 500] {
+                        metrics.observe_battle_rounds(rounds);
+                    }
+                });
+            }
+        });
+        let output = metrics.render();
+        assert!(output.contains("battle_rounds_bucket{le=\"1\"} 4"));
+        assert!(output.contains("battle_rounds_bucket{le=\"10\"} 8"));
+        assert!(output.contains("battle_rounds_bucket{le=\"100\"} 8"));
+        assert!(output.contains("battle_rounds_bucket{le=\"+Inf\"} 12"));
+        assert!(output.contains("battle_rounds_sum 2032"));
+        assert!(output.contains("battle_rounds_count 12"));
+    }
+
+    #[test]
+    fn test_should_render_the_pool_state_as_gauges() {
+        let output = render_pool_state(&PoolState {
+            connections: 10,
+            idle_connections: 7,
+        });
+        assert!(output.contains("# TYPE db_pool_connections gauge"));
+        assert!(output.contains("db_pool_connections 10"));
+        assert!(output.contains("db_pool_idle_connections 7"));
+    }
+}
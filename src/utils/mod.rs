@@ -1 +1,18 @@
-pub mod test_utils;
\ No newline at end of file
+pub mod auth;
+pub mod disk_space;
+pub mod flags;
+pub mod image_hosts;
+pub mod import_limit;
+pub mod leaderboard_cache;
+pub mod logging;
+pub mod metrics;
+pub mod monster_generator;
+pub mod monster_quota;
+pub mod normalize;
+pub mod presets;
+pub mod request_id;
+pub mod shutdown;
+pub mod temp_dir;
+pub mod test_utils;
+pub mod tls;
+pub mod uploads;
@@ -0,0 +1,97 @@
+use crate::models::monster::{Monster, StringList, ELEMENTS};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use uuid::Builder;
+
+const NAME_WORDS: [&str; 12] = [
+    "bear",
+    "bird",
+    "cat",
+    "cetacean",
+    "crocodilia",
+    "dog",
+    "horse",
+    "insect",
+    "lion",
+    "rabbit",
+    "snake",
+    "wolf",
+];
+pub const PLACEHOLDER_IMAGE_URL: &str = "https://loremflickr.com/640/480";
+
+/// Builds an unsaved monster with a random two-word name and random stats in the valid range.
+pub fn random_monster<R: Rng + ?Sized>(rng: &mut R) -> Monster {
+    let first_word = NAME_WORDS.choose(rng).unwrap_or(&"mystery");
+    let second_word = NAME_WORDS.choose(rng).unwrap_or(&"monster");
+    Monster {
+        id: String::new(),
+        image_url: PLACEHOLDER_IMAGE_URL.to_string(),
+        name: format!("{} {}", first_word, second_word),
+        attack: rng.gen_range(1..=100),
+        defense: rng.gen_range(1..=100),
+        hp: rng.gen_range(1..=100),
+        speed: rng.gen_range(1..=100),
+        created_at: None,
+        updated_at: None,
+        element: ELEMENTS.choose(rng).unwrap_or(&"neutral").to_string(),
+        version: 1,
+        aliases: StringList::default(),
+        tags: StringList::default(),
+        created_by: None,
+        level: 1,
+        available_from: None,
+        available_to: None,
+    }
+}
+
+pub fn random_monsters<R: Rng + ?Sized>(rng: &mut R, count: usize) -> Vec<Monster> {
+    (0..count).map(|_| random_monster(rng)).collect()
+}
+
+/// Two unsaved opponents that depend only on `seed`, ids included, so a sparring battle
+/// can be replayed exactly.
+pub fn seeded_pair(seed: u64) -> (Monster, Monster) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut opponent = || Monster {
+        id: Builder::from_random_bytes(rng.gen())
+            .into_uuid()
+            .to_string(),
+        ..random_monster(&mut rng)
+    };
+    (opponent(), opponent())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{random_monsters, seeded_pair};
+    use validator::Validate;
+
+    #[actix_rt::test]
+    async fn test_should_generate_valid_random_monsters() {
+        let monsters = random_monsters(&mut rand::thread_rng(), 50);
+        assert_eq!(monsters.len(), 50);
+        for monster in monsters {
+            assert!(monster.validate().is_ok(), "{:?}", monster);
+            assert!(monster.hp > 0 && monster.speed > 0 && monster.defense > 0);
+            assert_eq!(monster.name.split(' ').count(), 2);
+        }
+    }
+
+    #[test]
+    fn test_should_build_the_same_pair_from_the_same_seed() {
+        let (first_a, first_b) = seeded_pair(42);
+        let (second_a, second_b) = seeded_pair(42);
+        assert_eq!(
+            (first_a.id.as_str(), first_a.name.as_str(), first_a.attack),
+            (
+                second_a.id.as_str(),
+                second_a.name.as_str(),
+                second_a.attack
+            )
+        );
+        assert_eq!(first_b.id, second_b.id);
+        assert_ne!(first_a.id, first_b.id);
+        assert_ne!(seeded_pair(43).0.id, first_a.id);
+    }
+}
@@ -0,0 +1,21 @@
+/// Caps how many monsters may be stored; `None` leaves the table unbounded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonsterQuota {
+    max_monsters: Option<i64>,
+}
+
+impl MonsterQuota {
+    pub fn new(max_monsters: Option<i64>) -> Self {
+        MonsterQuota { max_monsters }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.max_monsters.is_some()
+    }
+
+    /// Returns the slots left when adding `adding` monsters to `stored` would pass the cap.
+    pub fn exceeded(&self, stored: i64, adding: i64) -> Option<i64> {
+        let max_monsters = self.max_monsters?;
+        (stored + adding > max_monsters).then(|| (max_monsters - stored).max(0))
+    }
+}
@@ -0,0 +1,31 @@
+use crate::models::monster::Monster;
+
+/// Trims `name` and collapses every run of whitespace inside it to one space.
+pub fn normalize_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+/// Cleans up the free-text fields of a monster before it is validated and stored.
+pub fn normalize(monster: &mut Monster) {
+    monster.name = normalize_name(&monster.name);
+    monster.image_url = monster.image_url.trim().to_string();
+    monster.element = monster.element.trim().to_string();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize;
+    use crate::utils::monster_generator::random_monster;
+
+    #[test]
+    fn test_should_trim_fields_and_collapse_spaces_in_the_name() {
+        let mut monster = random_monster(&mut rand::thread_rng());
+        monster.name = "  Big \t Red\n\nDragon ".to_string();
+        monster.image_url = " https://loremflickr.com/640/480\n".to_string();
+        monster.element = " fire ".to_string();
+        normalize(&mut monster);
+        assert_eq!(monster.name, "Big Red Dragon");
+        assert_eq!(monster.image_url, "https://loremflickr.com/640/480");
+        assert_eq!(monster.element, "fire");
+    }
+}
@@ -0,0 +1,88 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+/// A named starting point for new monsters; stats it leaves out must come from the body.
+#[derive(Serialize, Debug, PartialEq, ToSchema)]
+pub struct MonsterPreset {
+    pub name: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attack: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub defense: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hp: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<i32>,
+}
+
+pub const PRESETS: [MonsterPreset; 4] = [
+    MonsterPreset {
+        name: "tank",
+        attack: Some(30),
+        defense: Some(80),
+        hp: Some(90),
+        speed: Some(20),
+    },
+    MonsterPreset {
+        name: "glass_cannon",
+        attack: Some(90),
+        defense: Some(10),
+        hp: Some(30),
+        speed: None,
+    },
+    MonsterPreset {
+        name: "speedster",
+        attack: Some(50),
+        defense: None,
+        hp: Some(40),
+        speed: Some(95),
+    },
+    MonsterPreset {
+        name: "balanced",
+        attack: Some(50),
+        defense: Some(50),
+        hp: Some(50),
+        speed: Some(50),
+    },
+];
+
+pub fn find_preset(name: &str) -> Option<&'static MonsterPreset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+impl MonsterPreset {
+    /// Adds the preset's stats to a monster body without touching keys it already has.
+    pub fn fill(&self, body: &mut Map<String, Value>) {
+        let stats = [
+            ("attack", self.attack),
+            ("defense", self.defense),
+            ("hp", self.hp),
+            ("speed", self.speed),
+        ];
+        for (key, value) in stats {
+            if let Some(value) = value {
+                body.entry(key).or_insert_with(|| Value::from(value));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_preset;
+    use serde_json::json;
+
+    #[test]
+    fn test_should_fill_only_the_stats_the_body_leaves_out() {
+        let mut body = json!({ "name": "wall", "hp": 10 });
+        find_preset("tank")
+            .expect("tank should be a preset")
+            .fill(body.as_object_mut().unwrap());
+        assert_eq!(
+            body,
+            json!({ "name": "wall", "attack": 30, "defense": 80, "hp": 10, "speed": 20 })
+        );
+        assert!(find_preset("wizard").is_none());
+    }
+}
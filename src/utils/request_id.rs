@@ -0,0 +1,89 @@
+use actix_web::{
+    body::MessageBody,
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+    middleware::Next,
+    Error, HttpMessage, HttpRequest,
+};
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Correlation id of the request being served, stored in the request extensions.
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Reuses the caller's `X-Request-Id` or generates one, and echoes it on the response.
+pub async fn assign_request_id(
+    req: ServiceRequest,
+    next: Next<impl MessageBody>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+    req.extensions_mut().insert(RequestId(id.clone()));
+    let mut res = next.call(req).await?;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        res.headers_mut()
+            .insert(HeaderName::from_static("x-request-id"), value);
+    }
+    Ok(res)
+}
+
+/// Request id assigned by `assign_request_id`, if the middleware is mounted.
+pub fn request_id(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<RequestId>().map(|id| id.0.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assign_request_id, request_id, REQUEST_ID_HEADER};
+    use actix_web::{
+        http::StatusCode, middleware::from_fn, test, web, App, HttpRequest, HttpResponse,
+    };
+
+    async fn echo(req: HttpRequest) -> HttpResponse {
+        HttpResponse::Ok().json(request_id(&req))
+    }
+
+    #[actix_rt::test]
+    async fn test_should_generate_a_request_id() {
+        let app = App::new()
+            .wrap(from_fn(assign_request_id))
+            .route("/echo", web::get().to(echo));
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get().uri("/echo").to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let header = resp
+            .headers()
+            .get(REQUEST_ID_HEADER)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        assert!(!header.is_empty());
+        let body: Option<String> = test::read_body_json(resp).await;
+        assert_eq!(body, Some(header));
+    }
+
+    #[actix_rt::test]
+    async fn test_should_echo_a_supplied_request_id() {
+        let app = App::new()
+            .wrap(from_fn(assign_request_id))
+            .route("/echo", web::get().to(echo));
+        let app = test::init_service(app).await;
+        let req = test::TestRequest::get()
+            .uri("/echo")
+            .insert_header((REQUEST_ID_HEADER, "trace-123"))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.headers().get(REQUEST_ID_HEADER).unwrap(), "trace-123");
+        let body: Option<String> = test::read_body_json(resp).await;
+        assert_eq!(body.as_deref(), Some("trace-123"));
+    }
+}
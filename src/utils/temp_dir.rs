@@ -0,0 +1,49 @@
+use std::io::Write;
+use std::path::PathBuf;
+use tempfile::NamedTempFile;
+
+/// Directory CSV imports buffer their uploads in, probed by `/ready`.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        TempDir(path.into())
+    }
+
+    /// Creates, writes and removes a tiny file to prove the directory is writable.
+    pub fn check_writable(&self) -> Result<(), String> {
+        let mut probe = NamedTempFile::new_in(&self.0)
+            .map_err(|err| format!("cannot create a file in {}: {}", self.0.display(), err))?;
+        probe
+            .write_all(b"ready")
+            .map_err(|err| format!("cannot write to {}: {}", self.0.display(), err))?;
+        probe
+            .close()
+            .map_err(|err| format!("cannot delete a file in {}: {}", self.0.display(), err))
+    }
+}
+
+impl Default for TempDir {
+    fn default() -> Self {
+        TempDir::new(std::env::temp_dir())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TempDir;
+
+    #[test]
+    fn test_should_find_the_system_temp_dir_writable() {
+        assert!(TempDir::default().check_writable().is_ok());
+    }
+
+    #[test]
+    fn test_should_report_a_missing_temp_dir() {
+        let missing = std::env::temp_dir().join(format!("missing-{}", uuid::Uuid::new_v4()));
+        let err = TempDir::new(&missing)
+            .check_writable()
+            .expect_err("a missing directory is not writable");
+        assert!(err.contains(&missing.display().to_string()));
+    }
+}
@@ -1,4 +1,7 @@
-use crate::models::{battle::Battle, monster::Monster};
+use crate::models::{
+    battle::{Battle, BattleResult, BattleStatus},
+    monster::{Monster, StringList},
+};
 use crate::repository::{
     database::Database,
     schema::{battles::dsl::battles, monsters::dsl::monsters},
@@ -9,7 +12,7 @@ use diesel::{associations::HasTable, RunQueryDsl};
 
 #[allow(dead_code)]
 pub async fn init_test_monsters(db: &Database) -> Vec<Monster> {
-    let mut connection = db.get_connection();
+    let mut connection = db.get_connection().unwrap();
     let current_time = Utc::now().naive_utc();
     let monsters_data: Vec<Monster> = vec![
         Monster {
@@ -22,6 +25,14 @@ pub async fn init_test_monsters(db: &Database) -> Vec<Monster> {
             speed: 80,
             created_at: Some(current_time),
             updated_at: Some(current_time),
+            element: "neutral".to_string(),
+            version: 1,
+            aliases: StringList::default(),
+            tags: StringList::default(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
         },
         Monster {
             id: uuid::Uuid::new_v4().to_string(),
@@ -33,6 +44,14 @@ pub async fn init_test_monsters(db: &Database) -> Vec<Monster> {
             speed: 40,
             created_at: Some(current_time),
             updated_at: Some(current_time),
+            element: "neutral".to_string(),
+            version: 1,
+            aliases: StringList::default(),
+            tags: StringList::default(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
         },
         Monster {
             id: uuid::Uuid::new_v4().to_string(),
@@ -44,6 +63,14 @@ pub async fn init_test_monsters(db: &Database) -> Vec<Monster> {
             speed: 80,
             created_at: Some(current_time),
             updated_at: Some(current_time),
+            element: "neutral".to_string(),
+            version: 1,
+            aliases: StringList::default(),
+            tags: StringList::default(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
         },
         Monster {
             id: uuid::Uuid::new_v4().to_string(),
@@ -55,6 +82,14 @@ pub async fn init_test_monsters(db: &Database) -> Vec<Monster> {
             speed: 40,
             created_at: Some(current_time),
             updated_at: Some(current_time),
+            element: "neutral".to_string(),
+            version: 1,
+            aliases: StringList::default(),
+            tags: StringList::default(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
         },
         Monster {
             id: uuid::Uuid::new_v4().to_string(),
@@ -66,6 +101,14 @@ pub async fn init_test_monsters(db: &Database) -> Vec<Monster> {
             speed: 40,
             created_at: Some(current_time),
             updated_at: Some(current_time),
+            element: "neutral".to_string(),
+            version: 1,
+            aliases: StringList::default(),
+            tags: StringList::default(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
         },
         Monster {
             id: uuid::Uuid::new_v4().to_string(),
@@ -77,6 +120,14 @@ pub async fn init_test_monsters(db: &Database) -> Vec<Monster> {
             speed: 80,
             created_at: Some(current_time),
             updated_at: Some(current_time),
+            element: "neutral".to_string(),
+            version: 1,
+            aliases: StringList::default(),
+            tags: StringList::default(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
         },
         Monster {
             id: uuid::Uuid::new_v4().to_string(),
@@ -88,6 +139,14 @@ pub async fn init_test_monsters(db: &Database) -> Vec<Monster> {
             speed: 40,
             created_at: Some(current_time),
             updated_at: Some(current_time),
+            element: "neutral".to_string(),
+            version: 1,
+            aliases: StringList::default(),
+            tags: StringList::default(),
+            created_by: None,
+            level: 1,
+            available_from: None,
+            available_to: None,
         },
     ];
 
@@ -114,15 +173,21 @@ pub async fn init_test_monsters(db: &Database) -> Vec<Monster> {
 #[allow(dead_code)]
 pub async fn init_test_battle(db: &Database) -> Vec<Battle> {
     let test_monsters = init_test_monsters(db).await;
-    let mut connection = db.get_connection();
+    let mut connection = db.get_connection().unwrap();
     let current_time = Utc::now().naive_utc();
     let battle_data = Battle {
         id: uuid::Uuid::new_v4().to_string(),
         monster_a: test_monsters[0].id.clone(),
         monster_b: test_monsters[1].id.clone(),
-        winner: test_monsters[0].id.clone(),
+        winner: Some(test_monsters[0].id.clone()),
         created_at: Some(current_time),
         updated_at: Some(current_time),
+        winner_remaining_hp: 0,
+        rounds: 0,
+        deleted_at: None,
+        log: None,
+        result: BattleResult::Win,
+        status: BattleStatus::Completed,
     };
 
     match diesel::insert_into(battles::table())
@@ -137,6 +202,29 @@ pub async fn init_test_battle(db: &Database) -> Vec<Battle> {
     }
 }
 
+/// Like `build_multipart_payload_and_header`, with extra text fields sent before the file.
+#[allow(dead_code)]
+pub fn build_multipart_payload_with_fields(
+    file_name: &str,
+    file_contents: &str,
+    fields: &[(&str, &str)],
+) -> (String, (HeaderName, HeaderValue)) {
+    let boundary = "-----------------------------202022185716362916172375148227";
+    let (file_part, header) = build_multipart_payload_and_header(file_name, file_contents);
+    let text_parts: String = fields
+        .iter()
+        .map(|(name, value)| {
+            format!(
+                "{boundary}\r\n\
+Content-Disposition: form-data; name=\"{name}\"\r\n\
+\r\n\
+{value}\r\n"
+            )
+        })
+        .collect();
+    (format!("{text_parts}{file_part}"), header)
+}
+
 #[allow(dead_code)]
 pub fn build_multipart_payload_and_header(
     file_name: &str,
@@ -157,3 +245,27 @@ Content-Type: text/csv\r\n\
     );
     (payload, header)
 }
+
+/// Multipart body with a single binary file part of the given content type.
+#[allow(dead_code)]
+pub fn build_multipart_file_payload(
+    file_name: &str,
+    content_type: &str,
+    file_contents: &[u8],
+) -> (Vec<u8>, (HeaderName, HeaderValue)) {
+    let boundary = "-----------------------------202022185716362916172375148227";
+    let mut payload = format!(
+        "{boundary}\r\n\
+Content-Disposition: form-data; name=\"file\"; filename=\"{file_name}\"\r\n\
+Content-Type: {content_type}\r\n\
+\r\n"
+    )
+    .into_bytes();
+    payload.extend_from_slice(file_contents);
+    payload.extend_from_slice(format!("\r\n{boundary}--").as_bytes());
+    let header = (
+        CONTENT_TYPE,
+        HeaderValue::from_static("multipart/form-data; boundary=---------------------------202022185716362916172375148227"),
+    );
+    (payload, header)
+}
@@ -0,0 +1,83 @@
+use rustls::ServerConfig;
+use std::fs::File;
+use std::io::{self, BufReader};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlsPaths {
+    pub cert: String,
+    pub key: String,
+}
+
+/// Both paths turn TLS on and neither keeps plain HTTP; only one of them is a mistake.
+pub fn parse_tls_paths(
+    cert: Option<String>,
+    key: Option<String>,
+) -> Result<Option<TlsPaths>, String> {
+    let non_empty = |value: Option<String>| value.filter(|value| !value.trim().is_empty());
+    match (non_empty(cert), non_empty(key)) {
+        (Some(cert), Some(key)) => Ok(Some(TlsPaths { cert, key })),
+        (None, None) => Ok(None),
+        (Some(_), None) => Err("TLS_CERT_PATH is set but TLS_KEY_PATH is not".to_string()),
+        (None, Some(_)) => Err("TLS_KEY_PATH is set but TLS_CERT_PATH is not".to_string()),
+    }
+}
+
+/// Reads the PEM certificate chain and private key, offering HTTP/2 ahead of HTTP/1.1.
+pub fn load_server_config(paths: &TlsPaths) -> io::Result<ServerConfig> {
+    let invalid = |message: String| io::Error::new(io::ErrorKind::InvalidData, message);
+    let certs = rustls_pemfile::certs(&mut BufReader::new(File::open(&paths.cert)?))
+        .collect::<Result<Vec<_>, _>>()?;
+    if certs.is_empty() {
+        return Err(invalid(format!("No certificate found in {}", paths.cert)));
+    }
+    let key = rustls_pemfile::private_key(&mut BufReader::new(File::open(&paths.key)?))?
+        .ok_or_else(|| invalid(format!("No private key found in {}", paths.key)))?;
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|err| invalid(err.to_string()))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load_server_config, parse_tls_paths, TlsPaths};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[actix_rt::test]
+    async fn test_should_enable_tls_only_when_both_paths_are_set() {
+        let paths = parse_tls_paths(Some("cert.pem".to_string()), Some("key.pem".to_string()))
+            .unwrap()
+            .expect("TLS should be enabled");
+        assert_eq!(
+            (paths.cert.as_str(), paths.key.as_str()),
+            ("cert.pem", "key.pem")
+        );
+        assert!(parse_tls_paths(None, None).unwrap().is_none());
+        assert!(parse_tls_paths(Some(" ".to_string()), None)
+            .unwrap()
+            .is_none());
+        assert!(parse_tls_paths(Some("cert.pem".to_string()), None).is_err());
+        assert!(parse_tls_paths(None, Some("key.pem".to_string())).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_should_fail_to_load_a_file_without_a_certificate() {
+        let mut not_a_cert = NamedTempFile::new().unwrap();
+        writeln!(not_a_cert, "not a certificate").unwrap();
+        let path = not_a_cert.path().to_string_lossy().to_string();
+        let missing = TlsPaths {
+            cert: "/nonexistent/cert.pem".to_string(),
+            key: path.clone(),
+        };
+        assert!(load_server_config(&missing).is_err());
+        let empty = TlsPaths {
+            cert: path.clone(),
+            key: path,
+        };
+        let err = load_server_config(&empty).expect_err("config should be rejected");
+        assert!(err.to_string().starts_with("No certificate found"));
+    }
+}
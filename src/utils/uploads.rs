@@ -0,0 +1,49 @@
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_UPLOAD_DIR: &str = "uploads";
+pub const MAX_IMAGE_BYTES: usize = 2 * 1024 * 1024;
+/// URL prefix the upload directory is served under.
+pub const UPLOADS_PATH: &str = "/uploads";
+
+/// Directory monster images are written to and served from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UploadDir(PathBuf);
+
+impl UploadDir {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        UploadDir(path.into())
+    }
+
+    /// Creates the directory if it is missing, only warning when that fails.
+    pub fn create(&self) {
+        if let Err(err) = std::fs::create_dir_all(self.path()) {
+            log::warn!("Could not create upload dir {:?}: {}", self.path(), err);
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+/// File extension for the accepted image content types.
+pub fn image_extension(content_type: &str) -> Option<&'static str> {
+    match content_type {
+        "image/png" => Some("png"),
+        "image/jpeg" => Some("jpg"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::image_extension;
+
+    #[actix_rt::test]
+    async fn test_should_only_accept_png_and_jpeg_images() {
+        assert_eq!(image_extension("image/png"), Some("png"));
+        assert_eq!(image_extension("image/jpeg"), Some("jpg"));
+        assert_eq!(image_extension("image/gif"), None);
+        assert_eq!(image_extension("text/csv"), None);
+    }
+}